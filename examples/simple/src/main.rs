@@ -113,7 +113,7 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let mut server = Vetis::new(config);
     server
         .add_virtual_host(localhost_virtual_host)
-        .await;
+        .await?;
 
     server.run().await?;
 
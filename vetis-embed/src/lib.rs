@@ -0,0 +1,144 @@
+//! Embedded assets for VeTiS, re-exported by `vetis-macros`
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Path of the types of embedded assets in `vetis`
+const EMBEDDED: &str = "::vetis::server::virtual_host::path::static_files::embedded";
+
+/// Embed the files of a directory into the binary, as `EmbeddedAssets` served by
+/// `StaticPath::embedded`
+///
+/// The directory is relative to the manifest of the crate. Files are embedded with their path
+/// within the directory, such as `/assets/app.js`, and their modification date, which gives
+/// their `ETag` and `Last-Modified` headers.
+///
+/// Changed files are embedded again on the next build, added or removed files once the crate
+/// is rebuilt, such as with `cargo:rerun-if-changed` in a build script.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::server::virtual_host::path::static_files::embedded::EmbeddedAssets;
+///
+/// static ASSETS: EmbeddedAssets = vetis_macros::embed_assets!("ui/dist");
+/// ```
+#[proc_macro]
+pub fn embed_assets(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(tokens) => tokens,
+        Err(message) => format!("::core::compile_error!({:?})", message)
+            .parse()
+            .unwrap_or_default(),
+    }
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, String> {
+    let directory = parse_directory(input)?;
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").map_err(|e| e.to_string())?;
+    let directory = Path::new(&manifest_dir).join(directory);
+    if !directory.is_dir() {
+        return Err(format!("{} is not a directory", directory.display()));
+    }
+
+    let mut files = Vec::new();
+    walk(&directory, "", &mut files)?;
+    files.sort();
+
+    let mut assets = String::new();
+    for (path, file) in files {
+        let modified = std::fs::metadata(&file)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| {
+                modified
+                    .duration_since(UNIX_EPOCH)
+                    .ok()
+            })
+            .map_or("::core::option::Option::None".to_string(), |modified| {
+                format!(
+                    "::core::option::Option::Some(::core::time::Duration::new({}, {}))",
+                    modified.as_secs(),
+                    modified.subsec_nanos()
+                )
+            });
+        let file = file
+            .to_str()
+            .ok_or_else(|| format!("{} is not a valid UTF-8 path", file.display()))?;
+
+        let _ = write!(
+            assets,
+            "{}::EmbeddedAsset::new({:?}, ::core::include_bytes!({:?}), {}),",
+            EMBEDDED, path, file, modified
+        );
+    }
+
+    // Assets are borrowed by a constant, so the table is usable outside of statics
+    format!(
+        "{{ const ASSETS: {embedded}::EmbeddedAssets = {embedded}::EmbeddedAssets::new(&[{assets}]); ASSETS }}",
+        embedded = EMBEDDED,
+        assets = assets
+    )
+    .parse()
+    .map_err(|e| format!("{:?}", e))
+}
+
+/// Read the directory of the macro, a single string literal
+fn parse_directory(input: TokenStream) -> Result<String, String> {
+    let mut tokens = input.into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        // Literals passed through `macro_rules!` are wrapped in invisible groups
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::None => {
+            return parse_directory(group.stream());
+        }
+        _ => return Err("expected a directory, such as `embed_assets!(\"static\")`".to_string()),
+    };
+
+    let raw = literal
+        .strip_prefix('r')
+        .map(|raw| raw.trim_matches('#'));
+    let directory = raw
+        .unwrap_or(&literal)
+        .strip_prefix('"')
+        .and_then(|literal| literal.strip_suffix('"'))
+        .ok_or_else(|| format!("expected a string literal, found {}", literal))?;
+    if raw.is_none() && directory.contains('\\') {
+        return Err("escapes are not supported in the directory, use a raw string".to_string());
+    }
+
+    Ok(directory.to_string())
+}
+
+/// Collect the files below a directory with their path, such as `/assets/app.js`
+fn walk(directory: &Path, prefix: &str, files: &mut Vec<(String, PathBuf)>) -> Result<(), String> {
+    let entries = std::fs::read_dir(directory)
+        .map_err(|e| format!("Error reading directory {}: {}", directory.display(), e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let file = entry.path();
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|_| format!("{} is not a valid UTF-8 path", file.display()))?;
+        let path = format!("{}/{}", prefix, name);
+
+        // Links are followed, dangling ones are left out
+        let Ok(metadata) = std::fs::metadata(&file) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            walk(&file, &path, files)?;
+        } else if metadata.is_file() {
+            files.push((path, file));
+        }
+    }
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+pub use vetis_embed::embed_assets;
+
 #[macro_export]
 macro_rules! http {
     (hostname => $hostname:expr, root_directory => $root_directory:expr, port => $port:expr, interface => $interface:expr, handler => $handler:ident) => {
@@ -37,7 +39,7 @@ macro_rules! http {
 
             vetis
                 .add_virtual_host(virtual_host)
-                .await;
+                .await?;
 
             Ok::<Vetis, VetisError>(vetis)
         }
@@ -79,7 +81,7 @@ macro_rules! http {
 
             vetis
                 .add_virtual_host(virtual_host)
-                .await;
+                .await?;
 
             Ok::<Vetis, VetisError>(vetis)
         }
@@ -129,7 +131,7 @@ macro_rules! https {
 
         vetis
             .add_virtual_host(virtual_host)
-            .await;
+            .await?;
 
         Ok::<Vetis, VetisError>(vetis)
     };
@@ -41,3 +41,77 @@ async fn test_http() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_embed_assets() -> Result<(), Box<dyn std::error::Error>> {
+    use vetis::{
+        config::server::{
+            virtual_host::{path::static_files::StaticPathConfig, VirtualHostConfig},
+            ListenerConfig, ServerConfig,
+        },
+        server::virtual_host::{
+            path::static_files::{embedded::EmbeddedAssets, StaticPath},
+            VirtualHost,
+        },
+        Vetis,
+    };
+    use vetis_macros::embed_assets;
+
+    static ASSETS: EmbeddedAssets = embed_assets!("tests/assets");
+
+    let listener = ListenerConfig::builder()
+        .port(8081)
+        .interface("0.0.0.0")
+        .build()?;
+    let config = ServerConfig::builder()
+        .add_listener(listener)
+        .build()?;
+    let virtual_host_config = VirtualHostConfig::builder()
+        .hostname("localhost")
+        .root_directory("tests")
+        .port(8081)
+        .build()?;
+
+    let mut virtual_host = VirtualHost::new(virtual_host_config);
+    virtual_host.add_path(StaticPath::embedded(
+        StaticPathConfig::builder()
+            .uri("/")
+            .extensions("\\.(html|css)$")
+            .index_files(vec!["index.html".to_string()])
+            .build()?,
+        &ASSETS,
+    ));
+
+    let mut server = Vetis::new(config);
+    server
+        .add_virtual_host(virtual_host)
+        .await?;
+    server
+        .start()
+        .await?;
+
+    let client = deboa::Client::default();
+
+    let response = get("http://localhost:8081")?
+        .send_with(&client)
+        .await?;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response
+            .text()
+            .await?,
+        "<h1>Hello, World!</h1>\n"
+    );
+
+    let response = get("http://localhost:8081/css/site.css")?
+        .send_with(&client)
+        .await?;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "text/css; charset=utf-8");
+
+    server
+        .stop()
+        .await?;
+
+    Ok(())
+}
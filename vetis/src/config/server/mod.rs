@@ -38,7 +38,9 @@
 //!     .build()?;
 //! ```
 
-use serde::Deserialize;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
 
 use crate::errors::ConfigError;
 
@@ -186,7 +188,7 @@ impl ListenerConfigBuilder {
 ///
 /// println!("Listening on port {}", config.port());
 /// ```
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize)]
 pub struct ListenerConfig {
     port: u16,
     protocol: Protocol,
@@ -258,6 +260,7 @@ impl ListenerConfig {
 #[derive(Clone)]
 pub struct ServerConfigBuilder {
     listeners: Vec<ListenerConfig>,
+    grace_period: Duration,
 }
 
 impl ServerConfigBuilder {
@@ -282,6 +285,29 @@ impl ServerConfigBuilder {
         self
     }
 
+    /// Sets how long in-flight requests are allowed to complete during shutdown.
+    ///
+    /// When the server is stopped, listeners stop accepting new connections and
+    /// ask the open ones to close (`Connection: close` on HTTP/1, `GOAWAY` on
+    /// HTTP/2 and HTTP/3). Connections still open after the grace period are
+    /// forcefully closed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use std::time::Duration;
+    /// use vetis::config::{ServerConfig, ListenerConfig};
+    ///
+    /// let config = ServerConfig::builder()
+    ///     .add_listener(ListenerConfig::builder().port(8080).build())
+    ///     .grace_period(Duration::from_secs(10))
+    ///     .build();
+    /// ```
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
     /// Creates the `ServerConfig` with the configured listeners.
     pub fn build(self) -> Result<ServerConfig, ConfigError> {
         if self
@@ -291,7 +317,7 @@ impl ServerConfigBuilder {
             return Err(ConfigError::Server("No listeners configured".to_string()));
         }
 
-        Ok(ServerConfig { listeners: self.listeners })
+        Ok(ServerConfig { listeners: self.listeners, grace_period: self.grace_period })
     }
 }
 
@@ -313,9 +339,17 @@ impl ServerConfigBuilder {
 ///
 /// println!("Server has {} listeners", config.listeners().len());
 /// ```
-#[derive(Clone, Default, Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct ServerConfig {
     listeners: Vec<ListenerConfig>,
+    #[serde(default = "default_grace_period", deserialize_with = "deserialize_grace_period")]
+    grace_period: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self { listeners: Vec::new(), grace_period: default_grace_period() }
+    }
 }
 
 impl ServerConfig {
@@ -331,7 +365,7 @@ impl ServerConfig {
     ///     .build();
     /// ```
     pub fn builder() -> ServerConfigBuilder {
-        ServerConfigBuilder { listeners: vec![] }
+        ServerConfigBuilder { listeners: vec![], grace_period: default_grace_period() }
     }
 
     /// Returns a reference to all configured listeners.
@@ -352,4 +386,25 @@ impl ServerConfig {
     pub fn listeners(&self) -> &Vec<ListenerConfig> {
         &self.listeners
     }
+
+    /// Returns the shutdown grace period.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - How long in-flight requests may run once the server is stopping.
+    pub fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+}
+
+fn default_grace_period() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn deserialize_grace_period<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let seconds = u64::deserialize(deserializer)?;
+    Ok(Duration::from_secs(seconds))
 }
@@ -0,0 +1,213 @@
+use serde::Deserialize;
+
+use crate::errors::{ConfigError, VetisError};
+
+/// Default minimum size of compressed responses, 1 KiB.
+pub const DEFAULT_MIN_SIZE: u64 = 1024;
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_algorithms() -> Vec<Encoding> {
+    vec![Encoding::Brotli, Encoding::Zstd, Encoding::Gzip]
+}
+
+fn default_min_size() -> u64 {
+    DEFAULT_MIN_SIZE
+}
+
+fn default_mime_types() -> Vec<String> {
+    [
+        "text/*",
+        "application/json",
+        "application/javascript",
+        "application/xml",
+        "application/wasm",
+        "image/svg+xml",
+    ]
+    .iter()
+    .map(|mime_type| mime_type.to_string())
+    .collect()
+}
+
+/// Content coding applied by compression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// `br`
+    Brotli,
+    /// `gzip`
+    Gzip,
+    /// `zstd`
+    Zstd,
+}
+
+impl Encoding {
+    /// Returns the token of the content coding, as sent in `Content-Encoding`.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The content coding.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+            Encoding::Zstd => "zstd",
+        }
+    }
+}
+
+/// Builder for creating `CompressionConfig` instances.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::config::server::virtual_host::compression::{CompressionConfig, Encoding};
+///
+/// let compression = CompressionConfig::builder()
+///     .algorithms(vec![Encoding::Zstd, Encoding::Gzip])
+///     .min_size(512)
+///     .mime_types(vec!["text/*".to_string(), "application/json".to_string()])
+///     .build()?;
+/// ```
+pub struct CompressionConfigBuilder {
+    enabled: bool,
+    algorithms: Vec<Encoding>,
+    min_size: u64,
+    mime_types: Vec<String>,
+}
+
+impl CompressionConfigBuilder {
+    /// Allow enable or disable compression, such as for a path of a compressed virtual host.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Allow set the content codings, in order of preference when the client accepts several.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn algorithms(mut self, algorithms: Vec<Encoding>) -> Self {
+        self.algorithms = algorithms;
+        self
+    }
+
+    /// Allow set the minimum size of compressed responses, responses of unknown size are compressed.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn min_size(mut self, min_size: u64) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Allow set the compressed content types, such as `application/json` or `text/*`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn mime_types(mut self, mime_types: Vec<String>) -> Self {
+        self.mime_types = mime_types;
+        self
+    }
+
+    /// Build the `CompressionConfig` with the configured settings.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CompressionConfig, VetisError>` - The `CompressionConfig` with the configured settings.
+    pub fn build(self) -> Result<CompressionConfig, VetisError> {
+        if self.enabled
+            && self
+                .algorithms
+                .is_empty()
+        {
+            return Err(VetisError::Config(ConfigError::VirtualHost(
+                "Compression algorithms cannot be empty".to_string(),
+            )));
+        }
+
+        Ok(CompressionConfig {
+            enabled: self.enabled,
+            algorithms: self.algorithms,
+            min_size: self.min_size,
+            mime_types: self.mime_types,
+        })
+    }
+}
+
+/// Compression of responses, for a virtual host or a path.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompressionConfig {
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default = "default_algorithms")]
+    algorithms: Vec<Encoding>,
+    #[serde(default = "default_min_size")]
+    min_size: u64,
+    #[serde(default = "default_mime_types")]
+    mime_types: Vec<String>,
+}
+
+impl CompressionConfig {
+    /// Allow create a new `CompressionConfigBuilder` with default settings.
+    ///
+    /// Brotli, zstd and gzip are enabled for text, JSON, JavaScript, XML, WebAssembly and
+    /// SVG responses of at least `DEFAULT_MIN_SIZE` bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `CompressionConfigBuilder` - The builder.
+    pub fn builder() -> CompressionConfigBuilder {
+        CompressionConfigBuilder {
+            enabled: default_enabled(),
+            algorithms: default_algorithms(),
+            min_size: default_min_size(),
+            mime_types: default_mime_types(),
+        }
+    }
+
+    /// Returns enabled
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether responses are compressed.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns algorithms
+    ///
+    /// # Returns
+    ///
+    /// * `&[Encoding]` - The content codings, in order of preference.
+    pub fn algorithms(&self) -> &[Encoding] {
+        &self.algorithms
+    }
+
+    /// Returns min_size
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The minimum size of compressed responses.
+    pub fn min_size(&self) -> u64 {
+        self.min_size
+    }
+
+    /// Returns mime_types
+    ///
+    /// # Returns
+    ///
+    /// * `&[String]` - The compressed content types.
+    pub fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+}
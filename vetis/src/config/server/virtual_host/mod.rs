@@ -1,8 +1,10 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::{collections::HashMap, fs, path::Path, time::Duration};
 
 use log::error;
 use serde::{Deserialize, Deserializer};
 
+#[cfg(feature = "compression")]
+use crate::config::server::virtual_host::compression::CompressionConfig;
 #[cfg(feature = "interface")]
 use crate::config::server::virtual_host::path::interface::InterfacePathConfig;
 #[cfg(feature = "reverse-proxy")]
@@ -12,8 +14,32 @@ use crate::config::server::virtual_host::path::static_files::StaticPathConfig;
 
 use crate::errors::{ConfigError, VetisError};
 
+#[cfg(feature = "compression")]
+pub mod compression;
 pub mod path;
 
+/// Default maximum size of request bodies read with the `Request` body helpers, 2 MiB.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Default longest time to receive a request body read with the `Request` body helpers, 60 seconds.
+pub const DEFAULT_BODY_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn default_max_body_size() -> usize {
+    DEFAULT_MAX_BODY_SIZE
+}
+
+fn default_body_timeout() -> Duration {
+    DEFAULT_BODY_TIMEOUT
+}
+
+fn deserialize_seconds<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let seconds = u64::deserialize(deserializer)?;
+    Ok(Duration::from_secs(seconds))
+}
+
 /// Builder for creating `VirtualHostConfig` instances.
 ///
 /// Provides a fluent API for configuring virtual hosts,
@@ -37,12 +63,18 @@ pub mod path;
 /// ```
 pub struct VirtualHostConfigBuilder {
     hostname: String,
+    aliases: Option<Vec<String>>,
+    default: bool,
     port: u16,
     root_directory: String,
     default_headers: Option<Vec<(String, String)>>,
     security: Option<SecurityConfig>,
     status_pages: Option<HashMap<u16, String>>,
     enable_logging: bool,
+    max_body_size: usize,
+    body_timeout: Duration,
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionConfig>,
     #[cfg(feature = "static-files")]
     static_paths: Option<Vec<StaticPathConfig>>,
     #[cfg(feature = "reverse-proxy")]
@@ -70,6 +102,53 @@ impl VirtualHostConfigBuilder {
         self
     }
 
+    /// Adds an alias for the virtual host.
+    ///
+    /// Requests for an alias are served by this virtual host as if they were sent
+    /// to its hostname. Like hostnames, aliases can be wildcards such as `*.example.com`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::config::VirtualHostConfig;
+    ///
+    /// let config = VirtualHostConfig::builder()
+    ///     .hostname("example.com")
+    ///     .alias("www.example.com")
+    ///     .build()?;
+    /// ```
+    pub fn alias(mut self, alias: &str) -> Self {
+        match self.aliases {
+            None => {
+                self.aliases = Some(vec![alias.to_string()]);
+            }
+            Some(ref mut aliases) => {
+                aliases.push(alias.to_string());
+            }
+        }
+        self
+    }
+
+    /// Makes this virtual host the default one for its port.
+    ///
+    /// The default virtual host serves requests, and provides the certificate for
+    /// TLS handshakes, whose hostname does not match any other virtual host on the port.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::config::VirtualHostConfig;
+    ///
+    /// let config = VirtualHostConfig::builder()
+    ///     .hostname("example.com")
+    ///     .default(true)
+    ///     .build()?;
+    /// ```
+    pub fn default(mut self, default: bool) -> Self {
+        self.default = default;
+        self
+    }
+
     /// Sets the port for the virtual host.
     ///
     /// This should match one of the ports configured in the server listeners.
@@ -191,6 +270,61 @@ impl VirtualHostConfigBuilder {
         self
     }
 
+    /// Sets the maximum size of request bodies read with the `Request` body helpers.
+    ///
+    /// Larger bodies are answered with `413 Payload Too Large`. Defaults to 2 MiB.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::config::VirtualHostConfig;
+    ///
+    /// let config = VirtualHostConfig::builder()
+    ///     .max_body_size(10 * 1024 * 1024)
+    ///     .build()?;
+    /// ```
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Sets the longest time to receive request bodies read with the `Request` body helpers.
+    ///
+    /// Slower bodies are answered with `408 Request Timeout`. Defaults to 60 seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::config::VirtualHostConfig;
+    ///
+    /// let config = VirtualHostConfig::builder()
+    ///     .body_timeout(Duration::from_secs(10))
+    ///     .build()?;
+    /// ```
+    pub fn body_timeout(mut self, body_timeout: Duration) -> Self {
+        self.body_timeout = body_timeout;
+        self
+    }
+
+    #[cfg(feature = "compression")]
+    /// Sets the compression of the responses of the virtual host.
+    ///
+    /// Paths can override it with their own `CompressionConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::config::{server::virtual_host::compression::CompressionConfig, VirtualHostConfig};
+    ///
+    /// let config = VirtualHostConfig::builder()
+    ///     .compression(CompressionConfig::builder().min_size(512).build()?)
+    ///     .build()?;
+    /// ```
+    pub fn compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
     #[cfg(feature = "static-files")]
     /// Sets the status pages for the virtual host.
     ///
@@ -252,7 +386,8 @@ impl VirtualHostConfigBuilder {
     ///
     /// # Errors
     ///
-    /// Returns an error if the hostname is empty.
+    /// Returns an error if the hostname is empty or if the hostname or an alias
+    /// is an invalid wildcard.
     ///
     /// # Examples
     ///
@@ -275,6 +410,28 @@ impl VirtualHostConfigBuilder {
             )));
         }
 
+        let aliases = self
+            .aliases
+            .iter()
+            .flatten();
+        for name in std::iter::once(&self.hostname).chain(aliases) {
+            if name.is_empty() {
+                return Err(VetisError::Config(ConfigError::VirtualHost(
+                    "Empty alias".to_string(),
+                )));
+            }
+
+            let wildcard = name
+                .strip_prefix("*.")
+                .unwrap_or(name);
+            if wildcard.is_empty() || wildcard.contains('*') {
+                return Err(VetisError::Config(ConfigError::VirtualHost(format!(
+                    "Invalid wildcard hostname: {}, only a leading *. is allowed",
+                    name
+                ))));
+            }
+        }
+
         if self
             .root_directory
             .is_empty()
@@ -294,12 +451,18 @@ impl VirtualHostConfigBuilder {
 
         Ok(VirtualHostConfig {
             hostname: self.hostname,
+            aliases: self.aliases,
+            default: self.default,
             port: self.port,
             root_directory: self.root_directory,
             default_headers: self.default_headers,
             security: self.security,
             status_pages: self.status_pages,
             enable_logging: self.enable_logging,
+            max_body_size: self.max_body_size,
+            body_timeout: self.body_timeout,
+            #[cfg(feature = "compression")]
+            compression: self.compression,
             #[cfg(feature = "static-files")]
             static_paths: self.static_paths,
             #[cfg(feature = "reverse-proxy")]
@@ -333,6 +496,9 @@ impl VirtualHostConfigBuilder {
 #[derive(Clone, Deserialize)]
 pub struct VirtualHostConfig {
     hostname: String,
+    aliases: Option<Vec<String>>,
+    #[serde(default)]
+    default: bool,
     port: u16,
     root_directory: String,
     default_headers: Option<Vec<(String, String)>>,
@@ -340,7 +506,14 @@ pub struct VirtualHostConfig {
     security: Option<SecurityConfig>,
     status_pages: Option<HashMap<u16, String>>,
     enable_logging: bool,
+    #[serde(default = "default_max_body_size")]
+    max_body_size: usize,
+    #[serde(default = "default_body_timeout", deserialize_with = "deserialize_seconds")]
+    body_timeout: Duration,
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionConfig>,
     #[cfg(feature = "static-files")]
+    #[serde(default, deserialize_with = "deserialize_static_paths")]
     static_paths: Option<Vec<StaticPathConfig>>,
     #[cfg(feature = "reverse-proxy")]
     proxy_paths: Option<Vec<ProxyPathConfig>>,
@@ -369,12 +542,18 @@ impl VirtualHostConfig {
     pub fn builder() -> VirtualHostConfigBuilder {
         VirtualHostConfigBuilder {
             hostname: "localhost".to_string(),
+            aliases: None,
+            default: false,
             port: 80,
             root_directory: "/var/vetis/www".to_string(),
             default_headers: None,
             security: None,
             status_pages: None,
             enable_logging: true,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            body_timeout: DEFAULT_BODY_TIMEOUT,
+            #[cfg(feature = "compression")]
+            compression: None,
             #[cfg(feature = "static-files")]
             static_paths: None,
             #[cfg(feature = "reverse-proxy")]
@@ -393,6 +572,24 @@ impl VirtualHostConfig {
         &self.hostname
     }
 
+    /// Returns the aliases.
+    ///
+    /// # Returns
+    ///
+    /// * `&Option<Vec<String>>` - The aliases.
+    pub fn aliases(&self) -> &Option<Vec<String>> {
+        &self.aliases
+    }
+
+    /// Returns whether this is the default virtual host of its port.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if the virtual host catches requests for unknown hostnames.
+    pub fn is_default(&self) -> bool {
+        self.default
+    }
+
     /// Returns the port.
     ///
     /// # Returns
@@ -447,6 +644,34 @@ impl VirtualHostConfig {
         self.enable_logging
     }
 
+    /// Returns the maximum size of request bodies.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The maximum body size, in bytes.
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size
+    }
+
+    /// Returns the longest time to receive request bodies.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - The body timeout.
+    pub fn body_timeout(&self) -> Duration {
+        self.body_timeout
+    }
+
+    #[cfg(feature = "compression")]
+    /// Returns the compression of the responses.
+    ///
+    /// # Returns
+    ///
+    /// * `&Option<CompressionConfig>` - The compression settings.
+    pub fn compression(&self) -> &Option<CompressionConfig> {
+        &self.compression
+    }
+
     #[cfg(feature = "static-files")]
     /// Returns the static paths.
     ///
@@ -644,7 +869,9 @@ impl SecurityConfigBuilder {
 
     /// Sets whether client authentication is required.
     ///
-    /// When enabled, clients must present a valid certificate signed by the CA.
+    /// When enabled, clients must present a valid certificate signed by the CA,
+    /// requests without one are answered with `403 Forbidden`. A CA certificate
+    /// must be set.
     ///
     /// # Examples
     ///
@@ -676,6 +903,16 @@ impl SecurityConfigBuilder {
             return Err(VetisError::Config(ConfigError::Security("Missing key".to_string())));
         }
 
+        if self.client_auth
+            && self
+                .ca_cert
+                .is_none()
+        {
+            return Err(VetisError::Config(ConfigError::Security(
+                "Client authentication requires a CA certificate".to_string(),
+            )));
+        }
+
         Ok(SecurityConfig {
             cert: self.cert,
             key: self.key,
@@ -809,3 +1046,25 @@ where
         .map_err(serde::de::Error::custom)
         .map(Some)
 }
+
+/// Static paths read from a file are validated like the ones built with `StaticPathConfig::builder`
+#[cfg(feature = "static-files")]
+fn deserialize_static_paths<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<StaticPathConfig>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let static_paths = Option::<Vec<StaticPathConfig>>::deserialize(deserializer)?;
+
+    for static_path in static_paths
+        .iter()
+        .flatten()
+    {
+        static_path
+            .validate()
+            .map_err(serde::de::Error::custom)?;
+    }
+
+    Ok(static_paths)
+}
@@ -1,11 +1,29 @@
+use std::time::Duration;
+
 use serde::Deserialize;
 
-use crate::errors::{ConfigError, VetisError};
+#[cfg(feature = "compression")]
+use crate::config::server::virtual_host::compression::CompressionConfig;
+use crate::{
+    config::server::virtual_host::deserialize_seconds,
+    errors::{ConfigError, VetisError},
+};
+
+/// Default longest time to wait for the response of a proxied server, 60 seconds.
+pub const DEFAULT_PROXY_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn default_timeout() -> Duration {
+    DEFAULT_PROXY_TIMEOUT
+}
 
 #[derive(Deserialize)]
 pub struct ProxyPathConfigBuilder {
     uri: String,
     target: String,
+    #[serde(default = "default_timeout", deserialize_with = "deserialize_seconds")]
+    timeout: Duration,
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionConfig>,
 }
 
 #[cfg(feature = "reverse-proxy")]
@@ -30,6 +48,31 @@ impl ProxyPathConfigBuilder {
         self
     }
 
+    /// Allow set the longest time to wait for the response of the target.
+    ///
+    /// Slower targets are answered with `504 Gateway Timeout`. Defaults to 60 seconds.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    #[cfg(feature = "compression")]
+    /// Allow set the compression of the proxy path, overriding the one of the virtual host.
+    ///
+    /// Responses already encoded by the target are left as they are.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
     /// Build the `ProxyPathConfig` with the configured settings.
     ///
     /// # Returns
@@ -48,7 +91,13 @@ impl ProxyPathConfigBuilder {
             )));
         }
 
-        Ok(ProxyPathConfig { uri: self.uri, target: self.target })
+        Ok(ProxyPathConfig {
+            uri: self.uri,
+            target: self.target,
+            timeout: self.timeout,
+            #[cfg(feature = "compression")]
+            compression: self.compression,
+        })
     }
 }
 
@@ -57,6 +106,10 @@ impl ProxyPathConfigBuilder {
 pub struct ProxyPathConfig {
     uri: String,
     target: String,
+    #[serde(default = "default_timeout", deserialize_with = "deserialize_seconds")]
+    timeout: Duration,
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionConfig>,
     // TODO: Add custom proxy rules
 
     // TODO: Add support for custom headers
@@ -73,6 +126,9 @@ impl ProxyPathConfig {
         ProxyPathConfigBuilder {
             uri: "/test".to_string(),
             target: "http://localhost:8080".to_string(),
+            timeout: DEFAULT_PROXY_TIMEOUT,
+            #[cfg(feature = "compression")]
+            compression: None,
         }
     }
 
@@ -93,4 +149,23 @@ impl ProxyPathConfig {
     pub fn target(&self) -> &str {
         &self.target
     }
+
+    /// Returns the longest time to wait for the response of the target.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - The timeout of the proxy path.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    #[cfg(feature = "compression")]
+    /// Returns the compression of the proxy path.
+    ///
+    /// # Returns
+    ///
+    /// * `&Option<CompressionConfig>` - The compression of the proxy path.
+    pub fn compression(&self) -> &Option<CompressionConfig> {
+        &self.compression
+    }
 }
@@ -1,15 +1,607 @@
-use serde::Deserialize;
+use std::{collections::HashMap, time::Duration};
 
+use http::HeaderValue;
+use serde::{Deserialize, Deserializer};
+
+#[cfg(feature = "webdav")]
+use crate::config::server::virtual_host::deserialize_seconds;
 use crate::errors::{ConfigError, VetisError};
 
+#[cfg(feature = "compression")]
+use crate::config::server::virtual_host::compression::CompressionConfig;
 #[cfg(feature = "auth")]
 use crate::server::virtual_host::path::auth::AuthType;
 
+/// Default charset of text files, such as HTML, CSS and JavaScript.
+pub const DEFAULT_CHARSET: &str = "utf-8";
+
+fn default_charset() -> Option<String> {
+    Some(DEFAULT_CHARSET.to_string())
+}
+
+fn default_sendfile() -> bool {
+    true
+}
+
+/// How a static path serves symbolic links.
+///
+/// Files are always served from within the directory of the static path, links pointing
+/// outside of it are denied whatever the policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymlinkPolicy {
+    /// Follow links
+    #[default]
+    Follow,
+    /// Deny links
+    Deny,
+    /// Follow links owned by the owner of their target
+    OwnerMatch,
+}
+
+/// Default number of open files cached by a static path.
+pub const DEFAULT_FILE_CACHE_CAPACITY: usize = 100;
+
+/// Default time files stay cached, 60 seconds.
+pub const DEFAULT_FILE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Default size of the contents kept in memory by a static path, 16 MiB.
+pub const DEFAULT_CONTENT_CAPACITY: u64 = 16 * 1024 * 1024;
+
+fn default_file_cache_capacity() -> usize {
+    DEFAULT_FILE_CACHE_CAPACITY
+}
+
+fn default_file_cache_ttl() -> Option<Duration> {
+    Some(DEFAULT_FILE_CACHE_TTL)
+}
+
+fn default_watch() -> bool {
+    true
+}
+
+fn default_content_capacity() -> u64 {
+    DEFAULT_CONTENT_CAPACITY
+}
+
+fn deserialize_ttl<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let seconds = Option::<u64>::deserialize(deserializer)?;
+    Ok(seconds.map(Duration::from_secs))
+}
+
+/// Builder for creating `FileCacheConfig` instances.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use std::time::Duration;
+/// use vetis::config::server::virtual_host::path::static_files::FileCacheConfig;
+///
+/// // Keep files of up to 64 KiB in memory
+/// let file_cache = FileCacheConfig::builder()
+///     .capacity(1000)
+///     .ttl(Some(Duration::from_secs(300)))
+///     .max_content_size(64 * 1024)
+///     .build()?;
+/// ```
+pub struct FileCacheConfigBuilder {
+    capacity: usize,
+    ttl: Option<Duration>,
+    watch: bool,
+    max_content_size: u64,
+    content_capacity: u64,
+}
+
+impl FileCacheConfigBuilder {
+    /// Allow set the number of cached files, each holding an open file or its contents.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Allow set how long files stay cached, `None` to keep them until evicted or changed.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Allow watch the directory for changes, evicting changed files as soon as they change.
+    ///
+    /// Only supported on Linux, other platforms rely on the time to live.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn watch(mut self, watch: bool) -> Self {
+        self.watch = watch;
+        self
+    }
+
+    /// Allow set the size up to which the contents of files are kept in memory, `0` to disable it.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn max_content_size(mut self, max_content_size: u64) -> Self {
+        self.max_content_size = max_content_size;
+        self
+    }
+
+    /// Allow set the total size of the contents kept in memory.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn content_capacity(mut self, content_capacity: u64) -> Self {
+        self.content_capacity = content_capacity;
+        self
+    }
+
+    /// Build the `FileCacheConfig` with the configured settings.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<FileCacheConfig, VetisError>` - The `FileCacheConfig` with the configured settings.
+    pub fn build(self) -> Result<FileCacheConfig, VetisError> {
+        let config = FileCacheConfig {
+            capacity: self.capacity,
+            ttl: self.ttl,
+            watch: self.watch,
+            max_content_size: self.max_content_size,
+            content_capacity: self.content_capacity,
+        };
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+/// Cache of the files served by a static path.
+///
+/// Files are kept open, or in memory when small enough, until they are evicted by newer files,
+/// their time to live elapses or, on Linux, they change on disk.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileCacheConfig {
+    #[serde(default = "default_file_cache_capacity")]
+    capacity: usize,
+    #[serde(default = "default_file_cache_ttl", deserialize_with = "deserialize_ttl")]
+    ttl: Option<Duration>,
+    #[serde(default = "default_watch")]
+    watch: bool,
+    #[serde(default)]
+    max_content_size: u64,
+    #[serde(default = "default_content_capacity")]
+    content_capacity: u64,
+}
+
+impl Default for FileCacheConfig {
+    fn default() -> Self {
+        FileCacheConfig {
+            capacity: default_file_cache_capacity(),
+            ttl: default_file_cache_ttl(),
+            watch: default_watch(),
+            max_content_size: 0,
+            content_capacity: default_content_capacity(),
+        }
+    }
+}
+
+impl FileCacheConfig {
+    /// Allow create a new `FileCacheConfigBuilder` with default settings.
+    ///
+    /// Up to `DEFAULT_FILE_CACHE_CAPACITY` files are kept open for `DEFAULT_FILE_CACHE_TTL`,
+    /// and contents are not kept in memory.
+    ///
+    /// # Returns
+    ///
+    /// * `FileCacheConfigBuilder` - The builder.
+    pub fn builder() -> FileCacheConfigBuilder {
+        let config = FileCacheConfig::default();
+        FileCacheConfigBuilder {
+            capacity: config.capacity,
+            ttl: config.ttl,
+            watch: config.watch,
+            max_content_size: config.max_content_size,
+            content_capacity: config.content_capacity,
+        }
+    }
+
+    /// Returns capacity
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of cached files.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns ttl
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Duration>` - How long files stay cached.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Returns watch
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the directory is watched for changes.
+    pub fn watch(&self) -> bool {
+        self.watch
+    }
+
+    /// Returns max_content_size
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The size up to which contents are kept in memory.
+    pub fn max_content_size(&self) -> u64 {
+        self.max_content_size
+    }
+
+    /// Returns content_capacity
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The total size of the contents kept in memory.
+    pub fn content_capacity(&self) -> u64 {
+        self.content_capacity
+    }
+
+    fn validate(&self) -> Result<(), VetisError> {
+        if self.capacity == 0 {
+            return Err(VetisError::Config(ConfigError::Path(
+                "File cache capacity cannot be zero".to_string(),
+            )));
+        }
+        if self.max_content_size > self.content_capacity {
+            return Err(VetisError::Config(ConfigError::Path(
+                "File cache max content size cannot exceed its content capacity".to_string(),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Default size limit of the files uploaded to a static path, 100 MiB.
+#[cfg(feature = "webdav")]
+pub const DEFAULT_MAX_UPLOAD_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Default longest time a WebDAV lock is held without being refreshed, 1 hour.
+#[cfg(feature = "webdav")]
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(3600);
+
+#[cfg(feature = "webdav")]
+fn default_max_upload_size() -> u64 {
+    DEFAULT_MAX_UPLOAD_SIZE
+}
+
+#[cfg(feature = "webdav")]
+fn default_lock_timeout() -> Duration {
+    DEFAULT_LOCK_TIMEOUT
+}
+
+/// Builder for creating `WebDavConfig` instances.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::config::server::virtual_host::path::static_files::WebDavConfig;
+///
+/// // Accept uploads of up to 1 GiB
+/// let webdav = WebDavConfig::builder()
+///     .max_upload_size(1024 * 1024 * 1024)
+///     .build()?;
+/// ```
+#[cfg(feature = "webdav")]
+pub struct WebDavConfigBuilder {
+    max_upload_size: u64,
+    lock_timeout: Duration,
+}
+
+#[cfg(feature = "webdav")]
+impl WebDavConfigBuilder {
+    /// Allow set the size limit of uploaded files, larger uploads are answered with
+    /// `413 Payload Too Large`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn max_upload_size(mut self, max_upload_size: u64) -> Self {
+        self.max_upload_size = max_upload_size;
+        self
+    }
+
+    /// Allow set the longest time a lock is held without being refreshed, longer timeouts
+    /// requested by clients are shortened to it.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        self.lock_timeout = lock_timeout;
+        self
+    }
+
+    /// Build the `WebDavConfig` with the configured settings.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<WebDavConfig, VetisError>` - The `WebDavConfig` with the configured settings.
+    pub fn build(self) -> Result<WebDavConfig, VetisError> {
+        let config =
+            WebDavConfig { max_upload_size: self.max_upload_size, lock_timeout: self.lock_timeout };
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+/// Write access to the directory of a static path with WebDAV (RFC 4918, classes 1 and 2).
+///
+/// Enables `PUT`, `DELETE`, `MKCOL`, `COPY`, `MOVE`, `PROPFIND`, `PROPPATCH`, `LOCK` and
+/// `UNLOCK`, authenticated by the `auth` of the static path, which must be set.
+#[cfg(feature = "webdav")]
+#[derive(Clone, Deserialize)]
+pub struct WebDavConfig {
+    #[serde(default = "default_max_upload_size")]
+    max_upload_size: u64,
+    #[serde(default = "default_lock_timeout", deserialize_with = "deserialize_seconds")]
+    lock_timeout: Duration,
+}
+
+#[cfg(feature = "webdav")]
+impl WebDavConfig {
+    /// Allow create a new `WebDavConfigBuilder` with default settings.
+    ///
+    /// Uploads are limited to `DEFAULT_MAX_UPLOAD_SIZE` and locks to `DEFAULT_LOCK_TIMEOUT`.
+    ///
+    /// # Returns
+    ///
+    /// * `WebDavConfigBuilder` - The builder.
+    pub fn builder() -> WebDavConfigBuilder {
+        WebDavConfigBuilder {
+            max_upload_size: default_max_upload_size(),
+            lock_timeout: default_lock_timeout(),
+        }
+    }
+
+    /// Returns max_upload_size
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The size limit of uploaded files.
+    pub fn max_upload_size(&self) -> u64 {
+        self.max_upload_size
+    }
+
+    /// Returns lock_timeout
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - The longest time a lock is held without being refreshed.
+    pub fn lock_timeout(&self) -> Duration {
+        self.lock_timeout
+    }
+
+    fn validate(&self) -> Result<(), VetisError> {
+        if self.max_upload_size == 0 {
+            return Err(VetisError::Config(ConfigError::Path(
+                "WebDAV max upload size cannot be zero".to_string(),
+            )));
+        }
+        if self
+            .lock_timeout
+            .is_zero()
+        {
+            return Err(VetisError::Config(ConfigError::Path(
+                "WebDAV lock timeout cannot be zero".to_string(),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// `Cache-Control` rule of the files of a static path.
+///
+/// A rule applies to files with one of its extensions, or whose path within the directory of
+/// the static path matches its pattern, such as `/index.html` or `/assets/app.3f2a9c.js`.
+/// The first matching rule is used.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::config::server::virtual_host::path::static_files::CacheRule;
+///
+/// let hashed_assets = CacheRule::for_pattern(
+///     r"^/assets/.+\.[0-9a-f]{8}\.(js|css)$",
+///     "public, max-age=31536000, immutable",
+/// );
+/// let index = CacheRule::for_extensions(&["html"], "no-cache");
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct CacheRule {
+    #[serde(default)]
+    extensions: Vec<String>,
+    pattern: Option<String>,
+    cache_control: String,
+}
+
+impl CacheRule {
+    /// Create a rule for the files with the given extensions, without the leading dot.
+    ///
+    /// # Arguments
+    ///
+    /// * `extensions` - The extensions, such as `js` or `css`.
+    /// * `cache_control` - The `Cache-Control` header of the files.
+    ///
+    /// # Returns
+    ///
+    /// * `CacheRule` - The rule.
+    pub fn for_extensions(extensions: &[&str], cache_control: &str) -> Self {
+        CacheRule {
+            extensions: extensions
+                .iter()
+                .map(|extension| extension.to_string())
+                .collect(),
+            pattern: None,
+            cache_control: cache_control.to_string(),
+        }
+    }
+
+    /// Create a rule for the files whose path within the directory matches a regular expression.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - The regular expression, such as `^/index\.html$`.
+    /// * `cache_control` - The `Cache-Control` header of the files.
+    ///
+    /// # Returns
+    ///
+    /// * `CacheRule` - The rule.
+    pub fn for_pattern(pattern: &str, cache_control: &str) -> Self {
+        CacheRule {
+            extensions: Vec::new(),
+            pattern: Some(pattern.to_string()),
+            cache_control: cache_control.to_string(),
+        }
+    }
+
+    /// Returns extensions
+    ///
+    /// # Returns
+    ///
+    /// * `&[String]` - The extensions the rule applies to.
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Returns pattern
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - The regular expression the rule applies to.
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern
+            .as_deref()
+    }
+
+    /// Returns cache_control
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The `Cache-Control` header of the files.
+    pub fn cache_control(&self) -> &str {
+        &self.cache_control
+    }
+
+    fn validate(&self) -> Result<(), VetisError> {
+        let invalid = |reason: String| VetisError::Config(ConfigError::Path(reason));
+
+        if self
+            .extensions
+            .is_empty()
+            && self
+                .pattern
+                .is_none()
+        {
+            return Err(invalid("Cache rules need extensions or a pattern".to_string()));
+        }
+        if let Some(pattern) = &self.pattern {
+            regex::Regex::new(pattern)
+                .map_err(|e| invalid(format!("Invalid cache rule pattern {}: {}", pattern, e)))?;
+        }
+        if HeaderValue::from_str(&self.cache_control).is_err() {
+            return Err(invalid(format!("Invalid Cache-Control value {}", self.cache_control)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the status code of a `try_files` entry, such as `=404`
+///
+/// # Returns
+///
+/// * `Option<Result<http::StatusCode, VetisError>>` - `None` when the entry is a file
+pub(crate) fn try_files_status(entry: &str) -> Option<Result<http::StatusCode, VetisError>> {
+    let code = entry.strip_prefix('=')?;
+    let status = code
+        .parse::<u16>()
+        .ok()
+        .and_then(|code| http::StatusCode::from_u16(code).ok())
+        .filter(|status| status.is_client_error() || status.is_server_error());
+
+    Some(status.ok_or_else(|| {
+        VetisError::Config(ConfigError::Path(format!("Invalid try_files status {}", entry)))
+    }))
+}
+
+fn validate_try_files(try_files: &[String]) -> Result<(), VetisError> {
+    let Some((fallback, entries)) = try_files.split_last() else {
+        return Err(VetisError::Config(ConfigError::Path("Try files cannot be empty".to_string())));
+    };
+
+    for entry in entries {
+        if try_files_status(entry).is_some() {
+            return Err(VetisError::Config(ConfigError::Path(format!(
+                "Status {} must be the last of try_files",
+                entry
+            ))));
+        }
+    }
+    if let Some(status) = try_files_status(fallback) {
+        status?;
+    }
+
+    if let Some(entry) = try_files
+        .iter()
+        .find(|entry| entry.is_empty())
+    {
+        return Err(VetisError::Config(ConfigError::Path(format!(
+            "Invalid try_files entry {:?}",
+            entry
+        ))));
+    }
+
+    Ok(())
+}
+
 pub struct StaticPathConfigBuilder {
     uri: String,
     extensions: String,
     directory: String,
     index_files: Option<Vec<String>>,
+    try_files: Option<Vec<String>>,
+    autoindex: bool,
+    precompressed: bool,
+    mime_types: HashMap<String, String>,
+    charset: Option<String>,
+    cache_rules: Vec<CacheRule>,
+    symlinks: SymlinkPolicy,
+    dotfiles: bool,
+    file_cache: FileCacheConfig,
+    sendfile: bool,
+    #[cfg(feature = "webdav")]
+    webdav: Option<WebDavConfig>,
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionConfig>,
     #[cfg(feature = "auth")]
     auth: Option<AuthType>,
 }
@@ -55,6 +647,161 @@ impl StaticPathConfigBuilder {
         self
     }
 
+    /// Allow set the files tried in order for a request, replacing the default lookup.
+    ///
+    /// `$uri` is replaced by the request path within the static path, such as `/about`, and
+    /// entries ending with `/` match directories. The last entry is the fallback, served
+    /// without checking it exists, or a status code such as `=404`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// // Single-page application
+    /// let path = StaticPathConfig::builder()
+    ///     .try_files(vec!["$uri".to_string(), "$uri/".to_string(), "/index.html".to_string()])
+    ///     .build()?;
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn try_files(mut self, try_files: Vec<String>) -> Self {
+        self.try_files = Some(try_files);
+        self
+    }
+
+    /// Allow list the contents of directories without an index file.
+    ///
+    /// Listings are rendered as HTML, or as JSON when requested with `Accept: application/json`.
+    /// Dotfiles and files not matching the extensions are left out.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn autoindex(mut self, autoindex: bool) -> Self {
+        self.autoindex = autoindex;
+        self
+    }
+
+    /// Allow serve the precompressed variants of files accepted by the client.
+    ///
+    /// Variants are looked up next to the files, such as `app.js.br`, `app.js.zst` and
+    /// `app.js.gz`, and preferred in this order when the client accepts them equally.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn precompressed(mut self, precompressed: bool) -> Self {
+        self.precompressed = precompressed;
+        self
+    }
+
+    /// Allow map an extension, without the leading dot, to a content type.
+    ///
+    /// Mapped extensions take priority over the built-in content types.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn mime_type(mut self, extension: &str, content_type: &str) -> Self {
+        self.mime_types
+            .insert(
+                extension
+                    .trim_start_matches('.')
+                    .to_ascii_lowercase(),
+                content_type.to_string(),
+            );
+        self
+    }
+
+    /// Allow set the charset of text files, `None` to leave it out of their content type.
+    ///
+    /// Defaults to `DEFAULT_CHARSET`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn charset(mut self, charset: Option<&str>) -> Self {
+        self.charset = charset.map(str::to_string);
+        self
+    }
+
+    /// Allow add a `Cache-Control` rule, rules are tried in the order they were added.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn cache_rule(mut self, cache_rule: CacheRule) -> Self {
+        self.cache_rules
+            .push(cache_rule);
+        self
+    }
+
+    /// Allow set how symbolic links are served, links are followed by default.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Allow serve files and directories whose name starts with a dot, such as `.env`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn dotfiles(mut self, dotfiles: bool) -> Self {
+        self.dotfiles = dotfiles;
+        self
+    }
+
+    /// Allow set the cache of the served files.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn file_cache(mut self, file_cache: FileCacheConfig) -> Self {
+        self.file_cache = file_cache;
+        self
+    }
+
+    /// Allow send files with `sendfile(2)` on plaintext HTTP/1 connections, enabled by default.
+    ///
+    /// Only supported on Linux, files are read and written by the server otherwise.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn sendfile(mut self, sendfile: bool) -> Self {
+        self.sendfile = sendfile;
+        self
+    }
+
+    #[cfg(feature = "webdav")]
+    /// Allow write to the directory of the static path with WebDAV, which requires the
+    /// authentication of the static path.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn webdav(mut self, webdav: WebDavConfig) -> Self {
+        self.webdav = Some(webdav);
+        self
+    }
+
+    #[cfg(feature = "compression")]
+    /// Allow set the compression of the static path, overriding the one of the virtual host.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder.
+    pub fn compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
     #[cfg(feature = "auth")]
     /// Allow set the authentication of the static path.
     ///
@@ -72,34 +819,31 @@ impl StaticPathConfigBuilder {
     ///
     /// * `Result<StaticPathConfig, VetisError>` - The `StaticPathConfig` with the configured settings.
     pub fn build(self) -> Result<StaticPathConfig, VetisError> {
-        if self.uri.is_empty() {
-            return Err(VetisError::Config(ConfigError::Path("URI cannot be empty".to_string())));
-        }
-        if self
-            .extensions
-            .is_empty()
-        {
-            return Err(VetisError::Config(ConfigError::Path(
-                "Extensions cannot be empty".to_string(),
-            )));
-        }
-        if self
-            .directory
-            .is_empty()
-        {
-            return Err(VetisError::Config(ConfigError::Path(
-                "Directory cannot be empty".to_string(),
-            )));
-        }
-
-        Ok(StaticPathConfig {
+        let config = StaticPathConfig {
             uri: self.uri,
             extensions: self.extensions,
             directory: self.directory,
             index_files: self.index_files,
+            try_files: self.try_files,
+            autoindex: self.autoindex,
+            precompressed: self.precompressed,
+            mime_types: self.mime_types,
+            charset: self.charset,
+            cache_rules: self.cache_rules,
+            symlinks: self.symlinks,
+            dotfiles: self.dotfiles,
+            file_cache: self.file_cache,
+            sendfile: self.sendfile,
+            #[cfg(feature = "webdav")]
+            webdav: self.webdav,
+            #[cfg(feature = "compression")]
+            compression: self.compression,
             #[cfg(feature = "auth")]
             auth: self.auth,
-        })
+        };
+        config.validate()?;
+
+        Ok(config)
     }
 }
 
@@ -110,6 +854,29 @@ pub struct StaticPathConfig {
     extensions: String,
     directory: String,
     index_files: Option<Vec<String>>,
+    try_files: Option<Vec<String>>,
+    #[serde(default)]
+    autoindex: bool,
+    #[serde(default)]
+    precompressed: bool,
+    #[serde(default)]
+    mime_types: HashMap<String, String>,
+    #[serde(default = "default_charset")]
+    charset: Option<String>,
+    #[serde(default)]
+    cache_rules: Vec<CacheRule>,
+    #[serde(default)]
+    symlinks: SymlinkPolicy,
+    #[serde(default)]
+    dotfiles: bool,
+    #[serde(default)]
+    file_cache: FileCacheConfig,
+    #[serde(default = "default_sendfile")]
+    sendfile: bool,
+    #[cfg(feature = "webdav")]
+    webdav: Option<WebDavConfig>,
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionConfig>,
     #[cfg(feature = "auth")]
     auth: Option<AuthType>,
 }
@@ -127,11 +894,83 @@ impl StaticPathConfig {
             extensions: ".html".to_string(),
             directory: ".".to_string(),
             index_files: None,
+            try_files: None,
+            autoindex: false,
+            precompressed: false,
+            mime_types: HashMap::new(),
+            charset: default_charset(),
+            cache_rules: Vec::new(),
+            symlinks: SymlinkPolicy::default(),
+            dotfiles: false,
+            file_cache: FileCacheConfig::default(),
+            sendfile: default_sendfile(),
+            #[cfg(feature = "webdav")]
+            webdav: None,
+            #[cfg(feature = "compression")]
+            compression: None,
             #[cfg(feature = "auth")]
             auth: None,
         }
     }
 
+    /// Ensure the settings are consistent, such as WebDAV being authenticated
+    ///
+    /// Run by the builder, and when the virtual host configuration is deserialized.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), VetisError>` - An error describing the invalid setting.
+    pub(crate) fn validate(&self) -> Result<(), VetisError> {
+        if self.uri.is_empty() {
+            return Err(VetisError::Config(ConfigError::Path("URI cannot be empty".to_string())));
+        }
+        if self
+            .extensions
+            .is_empty()
+        {
+            return Err(VetisError::Config(ConfigError::Path(
+                "Extensions cannot be empty".to_string(),
+            )));
+        }
+        if self
+            .directory
+            .is_empty()
+        {
+            return Err(VetisError::Config(ConfigError::Path(
+                "Directory cannot be empty".to_string(),
+            )));
+        }
+        if let Some((extension, content_type)) = self
+            .mime_types
+            .iter()
+            .find(|(_, content_type)| HeaderValue::from_str(content_type).is_err())
+        {
+            return Err(VetisError::Config(ConfigError::Path(format!(
+                "Invalid content type {} for extension {}",
+                content_type, extension
+            ))));
+        }
+        for cache_rule in &self.cache_rules {
+            cache_rule.validate()?;
+        }
+        if let Some(try_files) = &self.try_files {
+            validate_try_files(try_files)?;
+        }
+        self.file_cache
+            .validate()?;
+        #[cfg(feature = "webdav")]
+        if let Some(webdav) = &self.webdav {
+            webdav.validate()?;
+            if self.auth.is_none() {
+                return Err(VetisError::Config(ConfigError::Path(
+                    "WebDAV requires the auth of the static path".to_string(),
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns uri
     ///
     /// # Returns
@@ -168,6 +1007,117 @@ impl StaticPathConfig {
         &self.index_files
     }
 
+    /// Returns try_files
+    ///
+    /// # Returns
+    ///
+    /// * `&Option<Vec<String>>` - The files tried in order, the last one being the fallback.
+    pub fn try_files(&self) -> &Option<Vec<String>> {
+        &self.try_files
+    }
+
+    /// Returns autoindex
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether directories without an index file are listed.
+    pub fn autoindex(&self) -> bool {
+        self.autoindex
+    }
+
+    /// Returns precompressed
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether precompressed variants of files are served.
+    pub fn precompressed(&self) -> bool {
+        self.precompressed
+    }
+
+    /// Returns mime_types
+    ///
+    /// # Returns
+    ///
+    /// * `&HashMap<String, String>` - The content types by extension.
+    pub fn mime_types(&self) -> &HashMap<String, String> {
+        &self.mime_types
+    }
+
+    /// Returns charset
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - The charset of text files.
+    pub fn charset(&self) -> Option<&str> {
+        self.charset
+            .as_deref()
+    }
+
+    /// Returns cache_rules
+    ///
+    /// # Returns
+    ///
+    /// * `&[CacheRule]` - The `Cache-Control` rules.
+    pub fn cache_rules(&self) -> &[CacheRule] {
+        &self.cache_rules
+    }
+
+    /// Returns symlinks
+    ///
+    /// # Returns
+    ///
+    /// * `SymlinkPolicy` - How symbolic links are served.
+    pub fn symlinks(&self) -> SymlinkPolicy {
+        self.symlinks
+    }
+
+    /// Returns dotfiles
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether files whose name starts with a dot are served.
+    pub fn dotfiles(&self) -> bool {
+        self.dotfiles
+    }
+
+    /// Returns file_cache
+    ///
+    /// # Returns
+    ///
+    /// * `&FileCacheConfig` - The cache of the served files.
+    pub fn file_cache(&self) -> &FileCacheConfig {
+        &self.file_cache
+    }
+
+    /// Returns sendfile
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether files are sent with `sendfile(2)` when possible.
+    pub fn sendfile(&self) -> bool {
+        self.sendfile
+    }
+
+    #[cfg(feature = "webdav")]
+    /// Returns webdav
+    ///
+    /// # Returns
+    ///
+    /// * `&Option<WebDavConfig>` - The WebDAV settings, when writes are allowed.
+    pub fn webdav(&self) -> &Option<WebDavConfig> {
+        &self.webdav
+    }
+
+    #[cfg(feature = "compression")]
+    /// Returns compression
+    ///
+    /// # Returns
+    ///
+    /// * `&Option<CompressionConfig>` - The compression.
+    pub fn compression(&self) -> &Option<CompressionConfig> {
+        &self.compression
+    }
+
     #[cfg(feature = "auth")]
     /// Returns auth
     ///
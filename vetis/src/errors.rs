@@ -77,6 +77,50 @@ pub enum VetisError {
     VirtualHost(#[from] VirtualHostError),
 }
 
+impl VetisError {
+    /// Returns the status code of the response served for this error.
+    ///
+    /// Errors caused by the request map to client error codes, errors of a proxied
+    /// server map to `502`, `503` or `504`, every other error maps to
+    /// `500 Internal Server Error`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::errors::{FileError, VetisError, VirtualHostError};
+    ///
+    /// let error = VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound));
+    /// assert_eq!(error.status_code(), http::StatusCode::NOT_FOUND);
+    /// ```
+    pub fn status_code(&self) -> http::StatusCode {
+        use http::StatusCode;
+
+        let VetisError::VirtualHost(error) = self else {
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        };
+
+        match error {
+            VirtualHostError::File(FileError::NotFound) => StatusCode::NOT_FOUND,
+            VirtualHostError::File(FileError::InvalidRange) => StatusCode::RANGE_NOT_SATISFIABLE,
+            VirtualHostError::File(FileError::Forbidden) => StatusCode::FORBIDDEN,
+            VirtualHostError::File(FileError::Status(status)) => *status,
+            VirtualHostError::Auth(_) => StatusCode::UNAUTHORIZED,
+            VirtualHostError::ClientCertificate(_) => StatusCode::FORBIDDEN,
+            VirtualHostError::WebSocket(_) => StatusCode::BAD_REQUEST,
+            VirtualHostError::Body(error) => match error {
+                BodyError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+                BodyError::ContentType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                BodyError::Timeout => StatusCode::REQUEST_TIMEOUT,
+                BodyError::Read(_) | BodyError::Invalid(_) => StatusCode::BAD_REQUEST,
+            },
+            VirtualHostError::Proxy(_) => StatusCode::BAD_GATEWAY,
+            VirtualHostError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            VirtualHostError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
 /// Configuration-related errors.
 ///
 /// These errors occur during the parsing and validation of
@@ -177,6 +221,30 @@ pub enum VirtualHostError {
 
     #[error("Auth error: {0}")]
     Auth(String),
+
+    /// Virtual host already added
+    #[error("Virtual host already exists: {0}")]
+    AlreadyExists(String),
+
+    /// Request body errors
+    #[error("Body error: {0}")]
+    Body(BodyError),
+
+    /// A backend, such as a proxied server, cannot be reached
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    /// A backend, such as a proxied server, did not answer in time
+    #[error("Gateway timeout: {0}")]
+    Timeout(String),
+
+    /// WebSocket handshake or connection errors
+    #[error("WebSocket error: {0}")]
+    WebSocket(String),
+
+    /// Missing or untrusted client certificate
+    #[error("Client certificate error: {0}")]
+    ClientCertificate(String),
 }
 
 #[derive(Debug, Clone, Error, PartialEq)]
@@ -198,4 +266,40 @@ pub enum FileError {
 
     #[error("Invalid range")]
     InvalidRange,
+
+    /// The file is outside of the served directory, or behind a denied symbolic link
+    #[error("Forbidden")]
+    Forbidden,
+
+    /// Answered with a status, such as the one of the `try_files` fallback or of a failed
+    /// WebDAV request
+    #[error("File status {0}")]
+    Status(http::StatusCode),
+}
+
+/// Request body errors.
+///
+/// These errors occur when reading or decoding a request body with the
+/// `Request` body helpers.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum BodyError {
+    /// The body is larger than the allowed size
+    #[error("Body larger than {0} bytes")]
+    TooLarge(usize),
+
+    /// The body could not be read from the connection
+    #[error("Cannot read body: {0}")]
+    Read(String),
+
+    /// The body does not have the expected content type
+    #[error("Unsupported content type: {0}")]
+    ContentType(String),
+
+    /// The body could not be decoded
+    #[error("Invalid body: {0}")]
+    Invalid(String),
+
+    /// The client did not send the body in time
+    #[error("Timed out reading body")]
+    Timeout,
 }
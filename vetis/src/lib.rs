@@ -76,7 +76,7 @@
 //!
 //!     // Create and run server
 //!     let mut server = Vetis::new(config);
-//!     server.add_virtual_host(localhost_virtual_host).await;
+//!     server.add_virtual_host(localhost_virtual_host).await?;
 //!     server.run().await?;
 //!
 //!     Ok(())
@@ -166,12 +166,16 @@ pub(crate) type VetisFile = File;
 
 pub(crate) type VetisRwLock<T> = RwLock<T>;
 
-pub(crate) type VetisVirtualHosts = Arc<VetisRwLock<HashMap<(Arc<str>, u16), VirtualHost>>>;
+pub(crate) type VetisVirtualHosts = Arc<VetisRwLock<HashMap<(Arc<str>, u16), Arc<VirtualHost>>>>;
 
 use crate::{
     config::server::ServerConfig,
     errors::{VetisError, VirtualHostError},
-    server::{virtual_host::VirtualHost, Server},
+    server::{
+        tls::SniResolver,
+        virtual_host::{check_conflicts, VirtualHost},
+        Server,
+    },
 };
 
 pub mod config;
@@ -210,6 +214,7 @@ pub static CONFIG: &str = "vetis.toml";
 pub struct Vetis {
     config: ServerConfig,
     virtual_hosts: VetisVirtualHosts,
+    cert_resolver: Arc<SniResolver>,
     instance: Option<server::http::HttpServer>,
 }
 
@@ -229,7 +234,12 @@ impl Vetis {
     /// let server = Vetis::new(config);
     /// ```
     pub fn new(config: ServerConfig) -> Vetis {
-        Vetis { config, virtual_hosts: Arc::new(VetisRwLock::new(HashMap::new())), instance: None }
+        Vetis {
+            config,
+            virtual_hosts: Arc::new(VetisRwLock::new(HashMap::new())),
+            cert_resolver: Arc::new(SniResolver::new()),
+            instance: None,
+        }
     }
 
     /// Adds a virtual host to the server.
@@ -237,10 +247,20 @@ impl Vetis {
     /// Virtual hosts allow you to host multiple domains on a single server instance.
     /// Each virtual host is identified by its hostname and port combination.
     ///
+    /// Virtual hosts can be added while the server is running, their certificates
+    /// are served starting with the next TLS handshake.
+    ///
     /// # Arguments
     ///
     /// * `virtual_host` - A type implementing the `VirtualHost` trait
     ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Another virtual host on the same port answers to one of its names
+    /// - It is a default virtual host and the port already has one
+    /// - The virtual host certificate or key is invalid
+    ///
     /// # Examples
     ///
     /// ```rust,ignore
@@ -269,15 +289,234 @@ impl Vetis {
     ///
     /// vhost.add_path(root_path);
     ///
-    /// server.add_virtual_host(vhost).await;
+    /// server.add_virtual_host(vhost).await?;
     /// ```
-    pub async fn add_virtual_host(&mut self, virtual_host: VirtualHost) {
+    pub async fn add_virtual_host(&mut self, virtual_host: VirtualHost) -> Result<(), VetisError> {
         let key = (Arc::from(virtual_host.hostname()), virtual_host.port());
 
-        self.virtual_hosts
+        let mut virtual_hosts = self
+            .virtual_hosts
             .write()
-            .await
-            .insert(key, virtual_host);
+            .await;
+
+        check_conflicts(virtual_hosts.values(), &virtual_host)?;
+
+        virtual_hosts.insert(key.clone(), Arc::new(virtual_host));
+
+        if let Err(e) = self
+            .cert_resolver
+            .reload(&virtual_hosts)
+        {
+            virtual_hosts.remove(&key);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Removes a virtual host from the server.
+    ///
+    /// Once removed, requests for the virtual host are answered with `404 Not Found`
+    /// and its certificate is no longer offered on new TLS handshakes. Requests
+    /// already being served by the virtual host are not interrupted.
+    ///
+    /// # Arguments
+    ///
+    /// * `hostname` - The hostname of the virtual host
+    /// * `port` - The port of the virtual host
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Arc<VirtualHost>>` - The removed virtual host, `None` if it was not found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// if let Some(vhost) = server.remove_virtual_host("example.com", 443).await? {
+    ///     println!("Removed {}", vhost.hostname());
+    /// }
+    /// ```
+    pub async fn remove_virtual_host(
+        &mut self,
+        hostname: &str,
+        port: u16,
+    ) -> Result<Option<Arc<VirtualHost>>, VetisError> {
+        let key = (Arc::from(hostname), port);
+
+        let mut virtual_hosts = self
+            .virtual_hosts
+            .write()
+            .await;
+
+        let removed = virtual_hosts.remove(&key);
+
+        if removed.is_some() {
+            if let Err(e) = self
+                .cert_resolver
+                .reload(&virtual_hosts)
+            {
+                if let Some(virtual_host) = removed {
+                    virtual_hosts.insert(key, virtual_host);
+                }
+                return Err(e);
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Replaces a virtual host, adding it if it does not exist yet.
+    ///
+    /// The virtual host and its certificate are swapped at once, requests received
+    /// afterwards are served by the new virtual host. The previous virtual host is
+    /// kept if the new one has an invalid certificate or key, or if one of its names
+    /// or its default flag conflicts with another virtual host on the same port.
+    ///
+    /// # Arguments
+    ///
+    /// * `virtual_host` - The virtual host replacing the one with the same hostname and port
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Arc<VirtualHost>>` - The replaced virtual host, `None` if it did not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let vhost_config = VirtualHostConfig::builder()
+    ///     .hostname("example.com")
+    ///     .port(443)
+    ///     .security(renewed_security_config)
+    ///     .build()?;
+    ///
+    /// server.replace_virtual_host(VirtualHost::new(vhost_config)).await?;
+    /// ```
+    pub async fn replace_virtual_host(
+        &mut self,
+        virtual_host: VirtualHost,
+    ) -> Result<Option<Arc<VirtualHost>>, VetisError> {
+        let key = (Arc::from(virtual_host.hostname()), virtual_host.port());
+
+        let mut virtual_hosts = self
+            .virtual_hosts
+            .write()
+            .await;
+
+        check_conflicts(
+            virtual_hosts
+                .iter()
+                .filter(|(other, _)| **other != key)
+                .map(|(_, other)| other),
+            &virtual_host,
+        )?;
+
+        let replaced = virtual_hosts.insert(key.clone(), Arc::new(virtual_host));
+
+        if let Err(e) = self
+            .cert_resolver
+            .reload(&virtual_hosts)
+        {
+            match replaced {
+                Some(previous) => virtual_hosts.insert(key, previous),
+                None => virtual_hosts.remove(&key),
+            };
+            return Err(e);
+        }
+
+        Ok(replaced)
+    }
+
+    /// Reloads the server with a new configuration and set of virtual hosts.
+    ///
+    /// The new configuration is validated before being applied, then:
+    /// 1. Virtual hosts and their certificates are swapped at once
+    /// 2. Listeners missing from the running server are bound
+    /// 3. Listeners no longer configured are closed once drained
+    ///
+    /// Listeners present in both configurations keep their open connections, and
+    /// requests already being served finish with the virtual hosts they were routed to.
+    /// If any step fails the current configuration is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The new server configuration
+    /// * `virtual_hosts` - The virtual hosts replacing the current ones
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - No virtual hosts are given, two of them share a name on the same port
+    ///   or a port has more than one default virtual host
+    /// - A virtual host certificate or key is invalid
+    /// - A new listener fails to bind
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let virtual_hosts = config
+    ///     .virtual_hosts
+    ///     .into_iter()
+    ///     .map(VirtualHost::new)
+    ///     .collect();
+    ///
+    /// if let Err(e) = server.reload(config.server, virtual_hosts).await {
+    ///     eprintln!("Keeping current configuration: {}", e);
+    /// }
+    /// ```
+    pub async fn reload(
+        &mut self,
+        config: ServerConfig,
+        virtual_hosts: Vec<VirtualHost>,
+    ) -> Result<(), VetisError> {
+        let mut reloaded = HashMap::new();
+        for virtual_host in virtual_hosts {
+            let virtual_host = Arc::new(virtual_host);
+            check_conflicts(reloaded.values(), &virtual_host)?;
+
+            let key = (Arc::from(virtual_host.hostname()), virtual_host.port());
+            reloaded.insert(key, virtual_host);
+        }
+
+        if reloaded.is_empty() {
+            error!("You must add at least one virtual host");
+            return Err(VetisError::VirtualHost(VirtualHostError::NoVirtualHosts));
+        }
+
+        // Only locked for the swap, requests being served keep the previous virtual hosts
+        let previous = {
+            let mut virtual_hosts = self
+                .virtual_hosts
+                .write()
+                .await;
+
+            self.cert_resolver
+                .reload(&reloaded)?;
+
+            std::mem::replace(&mut *virtual_hosts, reloaded)
+        };
+
+        if let Some(instance) = &mut self.instance {
+            if let Err(e) = instance
+                .reload(config.clone())
+                .await
+            {
+                let mut virtual_hosts = self
+                    .virtual_hosts
+                    .write()
+                    .await;
+
+                *virtual_hosts = previous;
+
+                self.cert_resolver
+                    .reload(&virtual_hosts)?;
+
+                return Err(e);
+            }
+        }
+
+        self.config = config;
+
+        Ok(())
     }
 
     /// Returns a reference to the server configuration.
@@ -398,12 +637,24 @@ impl Vetis {
             return Err(VetisError::VirtualHost(VirtualHostError::NoVirtualHosts));
         }
 
+        self.cert_resolver
+            .reload(
+                &*self
+                    .virtual_hosts
+                    .read()
+                    .await,
+            )?;
+
         let mut server = server::http::HttpServer::new(self.config.clone());
 
         server.set_virtual_hosts(
             self.virtual_hosts
                 .clone(),
         );
+        server.set_cert_resolver(
+            self.cert_resolver
+                .clone(),
+        );
 
         server
             .start()
@@ -1,5 +1,5 @@
-use clap::Parser;
-use log::error;
+use clap::{Parser, Subcommand};
+use log::{error, info};
 
 use serde::Deserialize;
 
@@ -15,7 +15,11 @@ use mimalloc::MiMalloc;
 #[cfg(target_env = "musl")]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-use std::{error::Error, fs::read_to_string, path::Path};
+use std::{
+    error::Error,
+    fs::{self, read_to_string},
+    path::Path,
+};
 use vetis::{
     config::server::{virtual_host::VirtualHostConfig, ServerConfig},
     server::virtual_host::VirtualHost,
@@ -27,6 +31,8 @@ pub struct VetisServerConfig {
     log_level: String,
     worker_threads: usize,
     max_blocking_threads: usize,
+    #[serde(default)]
+    pid_file: Option<String>,
     server: ServerConfig,
     virtual_hosts: Vec<VirtualHostConfig>,
 }
@@ -39,7 +45,10 @@ pub struct VetisServerConfig {
 vetis - a very tiny server
 
 Usage:
-    vetis [OPTIONS]
+    vetis [OPTIONS] [COMMAND]
+
+Commands:
+    reload           Reload the configuration of a running server
 
 Options:
     -h, --help       Print help information
@@ -51,73 +60,246 @@ Options:
 struct Args {
     #[arg(short, long, required = false, help = "Config file to use.")]
     config: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Reload the configuration of a running server
+    Reload,
+}
+
+enum ServerSignal {
+    Reload,
+    Shutdown,
+}
+
+/// Removes the pid file once the server exits
+struct PidFile {
+    path: String,
+}
+
+impl PidFile {
+    fn create(path: String) -> Result<Self, Box<dyn Error>> {
+        fs::write(&path, std::process::id().to_string())?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn load_config(path: &str) -> Result<VetisServerConfig, Box<dyn Error>> {
+    let file = read_to_string(path)?;
+    let config = serde_yaml_ng::from_str::<VetisServerConfig>(&file)?;
+    Ok(config)
+}
+
+#[cfg(unix)]
+fn send_reload(config: &VetisServerConfig) -> Result<(), Box<dyn Error>> {
+    let pid_file = match &config.pid_file {
+        Some(pid_file) => pid_file,
+        None => return Err("pid_file must be configured to reload a running server".into()),
+    };
+
+    let pid = read_to_string(pid_file)?
+        .trim()
+        .parse::<libc::pid_t>()?;
+
+    if unsafe { libc::kill(pid, libc::SIGHUP) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send_reload(_config: &VetisServerConfig) -> Result<(), Box<dyn Error>> {
+    Err("Reload is only supported on unix platforms".into())
+}
+
+#[cfg(all(feature = "tokio-rt", unix))]
+struct Signals {
+    hangup: tokio::signal::unix::Signal,
+    terminate: tokio::signal::unix::Signal,
 }
 
-async fn run(
-    server_config: ServerConfig,
-    virtual_hosts_config: Vec<VirtualHostConfig>,
-) -> Result<(), Box<dyn Error>> {
-    let mut server = Vetis::new(server_config);
+#[cfg(all(feature = "tokio-rt", not(unix)))]
+type Signals = ();
 
-    for virtual_host in virtual_hosts_config {
+#[cfg(feature = "smol-rt")]
+type Signals = async_signal::Signals;
+
+#[cfg(all(feature = "tokio-rt", unix))]
+fn listen_signals() -> Result<Signals, Box<dyn Error>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    Ok(Signals {
+        hangup: signal(SignalKind::hangup())?,
+        terminate: signal(SignalKind::terminate())?,
+    })
+}
+
+#[cfg(all(feature = "tokio-rt", not(unix)))]
+fn listen_signals() -> Result<Signals, Box<dyn Error>> {
+    Ok(())
+}
+
+#[cfg(feature = "smol-rt")]
+fn listen_signals() -> Result<Signals, Box<dyn Error>> {
+    use async_signal::Signal;
+
+    Ok(async_signal::Signals::new([Signal::Hup, Signal::Int, Signal::Quit, Signal::Term])?)
+}
+
+#[cfg(all(feature = "tokio-rt", unix))]
+async fn wait_for_signal(signals: &mut Signals) -> ServerSignal {
+    use futures_util::future::{select, Either};
+
+    let reload = Box::pin(
+        signals
+            .hangup
+            .recv(),
+    );
+    let shutdown = Box::pin(select(
+        Box::pin(tokio::signal::ctrl_c()),
+        Box::pin(
+            signals
+                .terminate
+                .recv(),
+        ),
+    ));
+
+    match select(reload, shutdown).await {
+        Either::Left(_) => ServerSignal::Reload,
+        Either::Right(_) => ServerSignal::Shutdown,
+    }
+}
+
+#[cfg(all(feature = "tokio-rt", not(unix)))]
+async fn wait_for_signal(_signals: &mut Signals) -> ServerSignal {
+    let _ = tokio::signal::ctrl_c().await;
+    ServerSignal::Shutdown
+}
+
+#[cfg(feature = "smol-rt")]
+async fn wait_for_signal(signals: &mut Signals) -> ServerSignal {
+    use async_signal::Signal;
+    use futures_lite::StreamExt;
+
+    match signals.next().await {
+        Some(Ok(Signal::Hup)) => ServerSignal::Reload,
+        _ => ServerSignal::Shutdown,
+    }
+}
+
+async fn reload(server: &mut Vetis, config_path: &str) {
+    info!("Reloading configuration from {}", config_path);
+
+    let config = match load_config(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Failed to reload config file, keeping current configuration: {}", e);
+            return;
+        }
+    };
+
+    let virtual_hosts = config
+        .virtual_hosts
+        .into_iter()
+        .map(VirtualHost::new)
+        .collect();
+
+    match server
+        .reload(config.server, virtual_hosts)
+        .await
+    {
+        Ok(_) => info!("Configuration reloaded"),
+        Err(e) => error!("Failed to apply config file, keeping current configuration: {}", e),
+    }
+}
+
+async fn run(config_path: String, config: VetisServerConfig) -> Result<(), Box<dyn Error>> {
+    let mut server = Vetis::new(config.server);
+
+    for virtual_host in config.virtual_hosts {
         let virtual_host = VirtualHost::new(virtual_host);
 
         server
             .add_virtual_host(virtual_host)
-            .await;
+            .await?;
     }
 
-    if let Err(e) = server.run().await {
+    if let Err(e) = server.start().await {
         error!("Failed to start server: {}", e);
+        return Ok(());
     }
 
+    for listener in server
+        .config()
+        .listeners()
+    {
+        info!("Server listening on port {}:{}", listener.interface(), listener.port());
+    }
+
+    let mut signals = listen_signals()?;
+
+    let _pid_file = config
+        .pid_file
+        .map(PidFile::create)
+        .transpose()?;
+
+    while let ServerSignal::Reload = wait_for_signal(&mut signals).await {
+        reload(&mut server, &config_path).await;
+    }
+
+    info!("Stopping server...");
+
+    server
+        .stop()
+        .await?;
+
     Ok(())
 }
 
 fn init_runtime() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
-    if let Some(config) = args.config {
-        if Path::exists(Path::new(&config)) {
-            let file = read_to_string(&config);
-            if let Err(e) = file {
-                return Err(e.into());
+    if let Some(config_path) = args.config {
+        if Path::exists(Path::new(&config_path)) {
+            let config = load_config(&config_path)?;
+
+            if let Some(Command::Reload) = args.command {
+                return send_reload(&config);
+            }
+
+            env_logger::Builder::from_env(
+                env_logger::Env::default().filter_or(
+                    "RUST_LOG",
+                    config
+                        .log_level
+                        .clone(),
+                ),
+            )
+            .format_module_path(false)
+            .init();
+
+            #[cfg(feature = "tokio-rt")]
+            {
+                let rt = tokio::runtime::Builder::new_multi_thread()
+                    .enable_all()
+                    .worker_threads(config.worker_threads)
+                    .max_blocking_threads(config.max_blocking_threads)
+                    .build()?;
+                rt.block_on(async { run(config_path, config).await })?;
             }
 
-            if let Ok(file) = file {
-                let config = serde_yaml_ng::from_str::<VetisServerConfig>(&file);
-                if let Err(e) = config {
-                    return Err(e.into());
-                }
-
-                if let Ok(config) = config {
-                    env_logger::Builder::from_env(
-                        env_logger::Env::default().filter_or("RUST_LOG", config.log_level),
-                    )
-                    .format_module_path(false)
-                    .init();
-
-                    #[cfg(feature = "tokio-rt")]
-                    {
-                        let rt = tokio::runtime::Builder::new_multi_thread()
-                            .enable_all()
-                            .worker_threads(config.worker_threads)
-                            .max_blocking_threads(config.max_blocking_threads)
-                            .build()?;
-                        rt.block_on(async { run(config.server, config.virtual_hosts).await })?;
-                    }
-
-                    #[cfg(feature = "smol-rt")]
-                    {
-                        smol::block_on(async { run(config.server, config.virtual_hosts).await })?;
-                    }
-                } else {
-                    eprintln!(
-                        "Failed to parse config file: {}",
-                        config
-                            .err()
-                            .unwrap()
-                    );
-                }
+            #[cfg(feature = "smol-rt")]
+            {
+                smol::block_on(async { run(config_path, config).await })?;
             }
         }
     }
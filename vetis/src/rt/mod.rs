@@ -2,3 +2,32 @@
 pub(crate) mod smol;
 #[cfg(all(feature = "tokio-rt", feature = "http2"))]
 pub(crate) mod tokio;
+
+use std::time::Duration;
+
+/// Wait for a duration with the timer of the enabled runtime
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "tokio-rt")]
+    ::tokio::time::sleep(duration).await;
+    #[cfg(feature = "smol-rt")]
+    ::smol::Timer::after(duration).await;
+}
+
+#[cfg(feature = "static-files")]
+/// Run blocking work, such as file system calls, on the blocking threads of the enabled runtime
+pub(crate) async fn unblock<F, T>(work: F) -> std::io::Result<T>
+where
+    F: FnOnce() -> std::io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(feature = "tokio-rt")]
+    {
+        ::tokio::task::spawn_blocking(work)
+            .await
+            .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+    }
+    #[cfg(feature = "smol-rt")]
+    {
+        ::smol::unblock(work).await
+    }
+}
@@ -0,0 +1,148 @@
+use std::{future::Future, pin::Pin, time::Duration};
+
+use crossfire::{
+    mpmc::{self, List},
+    waitgroup::{WaitGroup, WaitGroupGuard},
+    MAsyncRx, MTx,
+};
+use futures_util::future::{select, Either};
+
+/// Coordinates the graceful shutdown of the connections spawned by a listener.
+///
+/// Every connection holds a `Watch` obtained from `Drain::watch`, which keeps it
+/// registered until the connection finishes. Draining first asks connections to
+/// shut down gracefully, waits for them up to a grace period and then forces the
+/// remaining ones to close.
+pub(crate) struct Drain {
+    connections: WaitGroup<()>,
+    graceful: Option<MTx<List<()>>>,
+    force: Option<MTx<List<()>>>,
+    graceful_rx: MAsyncRx<List<()>>,
+    force_rx: MAsyncRx<List<()>>,
+}
+
+impl Drain {
+    /// Create a new drain coordinator
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new `Drain` instance.
+    pub(crate) fn new() -> Self {
+        let (graceful, graceful_rx) = mpmc::unbounded_async::<()>();
+        let (force, force_rx) = mpmc::unbounded_async::<()>();
+        Self {
+            connections: WaitGroup::new((), 0),
+            graceful: Some(graceful),
+            force: Some(force),
+            graceful_rx,
+            force_rx,
+        }
+    }
+
+    /// Register a new connection
+    ///
+    /// # Returns
+    ///
+    /// * `Watch` - A handle that must live as long as the connection.
+    pub(crate) fn watch(&self) -> Watch {
+        Watch {
+            _guard: self
+                .connections
+                .add_guard(),
+            graceful: self
+                .graceful_rx
+                .clone(),
+            force: self
+                .force_rx
+                .clone(),
+        }
+    }
+
+    /// Returns the number of connections still being served.
+    pub(crate) fn active(&self) -> usize {
+        self.connections
+            .get_left()
+    }
+
+    /// Ask all connections to shut down and wait for them to finish.
+    ///
+    /// Connections still running once `grace_period` elapses are forcefully closed.
+    ///
+    /// # Arguments
+    ///
+    /// * `grace_period` - How long to wait for in-flight requests to complete.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if every connection finished within the grace period.
+    pub(crate) async fn drain(mut self, grace_period: Duration) -> bool {
+        // Dropping the senders disconnects the channels, waking every watcher.
+        self.graceful.take();
+
+        let waiting = self
+            .connections
+            .wait_async_timeout(grace_period);
+        let drained = waiting
+            .await
+            .is_ok();
+
+        self.force.take();
+
+        drained
+    }
+}
+
+/// Connection side of a `Drain`.
+///
+/// Cloning a `Watch` registers one more connection on the originating `Drain`.
+#[derive(Clone)]
+pub(crate) struct Watch {
+    _guard: WaitGroupGuard<()>,
+    graceful: MAsyncRx<List<()>>,
+    force: MAsyncRx<List<()>>,
+}
+
+impl Watch {
+    /// Wait until a graceful shutdown is requested.
+    pub(crate) async fn shutdown(&self) {
+        let _ = self
+            .graceful
+            .recv()
+            .await;
+    }
+
+    /// Drive a connection future until it completes or the listener is drained.
+    ///
+    /// When a graceful shutdown is requested `on_shutdown` is invoked so the
+    /// protocol can notify the peer (e.g. `Connection: close` or `GOAWAY`), after
+    /// which the connection is awaited again until it completes or is forced to close.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - The pinned connection future.
+    /// * `on_shutdown` - Callback that starts the graceful shutdown of the connection.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<F::Output>` - The connection output, `None` if it was forcefully closed.
+    pub(crate) async fn watch<F, S>(
+        self,
+        mut conn: Pin<&mut F>,
+        on_shutdown: S,
+    ) -> Option<F::Output>
+    where
+        F: Future,
+        S: FnOnce(Pin<&mut F>),
+    {
+        match select(conn.as_mut(), Box::pin(self.shutdown())).await {
+            Either::Left((output, _)) => return Some(output),
+            Either::Right(_) => on_shutdown(conn.as_mut()),
+        }
+
+        let force = self.force.recv();
+        match select(conn, Box::pin(force)).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{future::Future, pin::Pin};
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
 
 #[cfg(any(feature = "http1", feature = "http2"))]
 use crate::server::conn::listener::tcp::TcpListener;
@@ -8,6 +8,7 @@ use crate::server::conn::listener::udp::UdpListener;
 use crate::{
     config::server::{ListenerConfig, Protocol},
     errors::VetisError,
+    server::tls::SniResolver,
     VetisVirtualHosts,
 };
 
@@ -24,11 +25,18 @@ pub trait Listener {
     where
         Self: Sized;
 
+    fn config(&self) -> &ListenerConfig;
+
     fn set_virtual_hosts(&mut self, virtual_hosts: VetisVirtualHosts);
 
+    fn set_cert_resolver(&mut self, cert_resolver: Arc<SniResolver>);
+
     fn listen(&mut self) -> ListenerResult<'_, ()>;
 
-    fn stop(&mut self) -> ListenerResult<'_, ()>;
+    /// Stop accepting connections and release the address, listening again is allowed
+    fn close(&mut self) -> ListenerResult<'_, ()>;
+
+    fn stop(&mut self, grace_period: Duration) -> ListenerResult<'_, ()>;
 }
 
 pub enum ServerListener {
@@ -54,6 +62,15 @@ impl Listener for ServerListener {
         }
     }
 
+    fn config(&self) -> &ListenerConfig {
+        match self {
+            #[cfg(any(feature = "http1", feature = "http2"))]
+            ServerListener::Tcp(tcp_listener) => tcp_listener.config(),
+            #[cfg(feature = "http3")]
+            ServerListener::Udp(udp_listener) => udp_listener.config(),
+        }
+    }
+
     fn set_virtual_hosts(&mut self, virtual_hosts: VetisVirtualHosts) {
         match self {
             #[cfg(any(feature = "http1", feature = "http2"))]
@@ -67,6 +84,19 @@ impl Listener for ServerListener {
         }
     }
 
+    fn set_cert_resolver(&mut self, cert_resolver: Arc<SniResolver>) {
+        match self {
+            #[cfg(any(feature = "http1", feature = "http2"))]
+            ServerListener::Tcp(tcp_listener) => {
+                tcp_listener.set_cert_resolver(cert_resolver);
+            }
+            #[cfg(feature = "http3")]
+            ServerListener::Udp(ref mut udp_listener) => {
+                udp_listener.set_cert_resolver(cert_resolver);
+            }
+        }
+    }
+
     fn listen(&mut self) -> ListenerResult<'_, ()> {
         Box::pin(async move {
             match self {
@@ -88,19 +118,39 @@ impl Listener for ServerListener {
         })
     }
 
-    fn stop(&mut self) -> ListenerResult<'_, ()> {
+    fn close(&mut self) -> ListenerResult<'_, ()> {
+        Box::pin(async move {
+            match self {
+                #[cfg(any(feature = "http1", feature = "http2"))]
+                ServerListener::Tcp(tcp_listener) => {
+                    tcp_listener
+                        .close()
+                        .await?
+                }
+                #[cfg(feature = "http3")]
+                ServerListener::Udp(ref mut udp_listener) => {
+                    udp_listener
+                        .close()
+                        .await?
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn stop(&mut self, grace_period: Duration) -> ListenerResult<'_, ()> {
         Box::pin(async move {
             match self {
                 #[cfg(any(feature = "http1", feature = "http2"))]
                 ServerListener::Tcp(tcp_listener) => {
                     tcp_listener
-                        .stop()
+                        .stop(grace_period)
                         .await?
                 }
                 #[cfg(feature = "http3")]
                 ServerListener::Udp(ref mut udp_listener) => {
                     udp_listener
-                        .stop()
+                        .stop(grace_period)
                         .await?
                 }
             }
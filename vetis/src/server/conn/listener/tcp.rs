@@ -1,15 +1,18 @@
 use std::{
     collections::HashMap,
     net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::pin,
     sync::Arc,
+    time::Duration,
 };
 
 use http::header;
 use hyper::{body::Incoming, service::service_fn};
 
 use hyper_body_utils::HttpBody;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 
+use crossfire::waitgroup::{WaitGroup, WaitGroupGuard};
 use rt_gate::{spawn_server, spawn_worker, GateTask};
 
 #[cfg(feature = "smol-rt")]
@@ -43,13 +46,25 @@ use futures_rustls::TlsAcceptor;
 #[cfg(all(feature = "smol-rt", any(feature = "http1", feature = "http2")))]
 use smol_hyper::rt::FuturesIo;
 
+#[cfg(all(
+    target_os = "linux",
+    feature = "tokio-rt",
+    feature = "http1",
+    feature = "static-files"
+))]
+use crate::server::conn::sendfile::{SendfileQueue, SendfileStream};
+
 use crate::{
     config::server::{ListenerConfig, Protocol},
     errors::VetisError,
     server::{
-        conn::listener::{Listener, ListenerResult},
-        http::{static_response, Request},
-        tls::TlsFactory,
+        conn::{
+            drain::{Drain, Watch},
+            listener::{Listener, ListenerResult},
+        },
+        http::{static_response, ConnectionInfo, Request, TlsInfo},
+        tls::{SniResolver, TlsFactory},
+        virtual_host::find_virtual_host,
     },
     VetisRwLock, VetisVirtualHosts,
 };
@@ -57,6 +72,8 @@ use crate::{
 #[cfg(feature = "tokio-rt")]
 type VetisTcpListener = tokio::net::TcpListener;
 #[cfg(feature = "tokio-rt")]
+type VetisTcpStream = tokio::net::TcpStream;
+#[cfg(feature = "tokio-rt")]
 type VetisTlsAcceptor = TlsAcceptor;
 #[cfg(feature = "tokio-rt")]
 type VetisIo<T> = TokioIo<T>;
@@ -66,15 +83,36 @@ type VetisExecutor = TokioExecutor;
 #[cfg(feature = "smol-rt")]
 type VetisTcpListener = smol::net::TcpListener;
 #[cfg(feature = "smol-rt")]
+type VetisTcpStream = smol::net::TcpStream;
+#[cfg(feature = "smol-rt")]
 type VetisTlsAcceptor = TlsAcceptor;
 #[cfg(feature = "smol-rt")]
 type VetisIo<T> = FuturesIo<T>;
 #[cfg(all(feature = "smol-rt", feature = "http2"))]
 type VetisExecutor = SmolExecutor;
 
+/// Listening socket of an accept task
+struct Accepting {
+    listener: VetisTcpListener,
+    /// Released after the socket, once the task is dropped
+    _closed: WaitGroupGuard<()>,
+}
+
+impl Accepting {
+    async fn accept(&self) -> std::io::Result<(VetisTcpStream, SocketAddr)> {
+        self.listener
+            .accept()
+            .await
+    }
+}
+
 /// TCP listener
 pub struct TcpListener {
     task: Option<GateTask>,
+    /// Waits for the listening socket to be closed, as cancelled tasks may be dropped later
+    closed: Option<WaitGroup<()>>,
+    drain: Option<Drain>,
+    cert_resolver: Option<Arc<SniResolver>>,
     config: ListenerConfig,
     virtual_hosts: VetisVirtualHosts,
 }
@@ -90,7 +128,19 @@ impl Listener for TcpListener {
     ///
     /// * `Self` - A new `TcpListener` instance.
     fn new(config: ListenerConfig) -> Self {
-        Self { task: None, config, virtual_hosts: Arc::new(VetisRwLock::new(HashMap::new())) }
+        Self {
+            task: None,
+            closed: None,
+            drain: None,
+            cert_resolver: None,
+            config,
+            virtual_hosts: Arc::new(VetisRwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the listener configuration
+    fn config(&self) -> &ListenerConfig {
+        &self.config
     }
 
     /// Set the virtual hosts
@@ -102,6 +152,17 @@ impl Listener for TcpListener {
         self.virtual_hosts = virtual_hosts;
     }
 
+    /// Set the certificate resolver shared with other listeners
+    ///
+    /// When not set, certificates are loaded from the virtual hosts once the listener starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_resolver` - A `SniResolver` instance following virtual host changes.
+    fn set_cert_resolver(&mut self, cert_resolver: Arc<SniResolver>) {
+        self.cert_resolver = Some(cert_resolver);
+    }
+
     /// Listen for incoming connections
     ///
     /// # Returns
@@ -130,19 +191,30 @@ impl Listener for TcpListener {
             let listener = VetisTcpListener::bind(addr)
                 .await
                 .map_err(|e| VetisError::Bind(e.to_string()))?;
+            let closed = WaitGroup::new((), 0);
+            let accepting = Accepting { listener, _closed: closed.add_guard() };
+
+            // Connections accepted before the listener was closed are drained along with new ones
+            let drain = self
+                .drain
+                .take()
+                .unwrap_or_else(Drain::new);
 
             let task = self
                 .handle_connections(
                     self.config
                         .protocol()
                         .clone(),
-                    listener,
+                    accepting,
                     self.virtual_hosts
                         .clone(),
+                    drain.watch(),
                 )
-                .await?;
+                .await;
 
-            self.task = Some(task);
+            self.closed = Some(closed);
+            self.drain = Some(drain);
+            self.task = Some(task?);
 
             Ok(())
         };
@@ -150,16 +222,60 @@ impl Listener for TcpListener {
         Box::pin(future)
     }
 
-    /// Stop the listener
+    /// Close the listener
+    ///
+    /// New connections are no longer accepted and the address is released, open connections
+    /// keep being served until the listener is stopped.
     ///
     /// # Returns
     ///
     /// * `ListenerResult<'_, ()>` - A `ListenerResult` instance containing the result of the listener.
-    fn stop(&mut self) -> ListenerResult<'_, ()> {
+    fn close(&mut self) -> ListenerResult<'_, ()> {
         let future = async move {
             if let Some(mut task) = self.task.take() {
                 task.cancel().await;
             }
+            if let Some(closed) = self.closed.take() {
+                closed
+                    .wait_async()
+                    .await;
+            }
+
+            Ok(())
+        };
+
+        Box::pin(future)
+    }
+
+    /// Stop the listener
+    ///
+    /// New connections are no longer accepted, open connections are asked to close
+    /// once their in-flight requests complete and are forcefully closed after the grace period.
+    ///
+    /// # Arguments
+    ///
+    /// * `grace_period` - How long to wait for in-flight requests to complete.
+    ///
+    /// # Returns
+    ///
+    /// * `ListenerResult<'_, ()>` - A `ListenerResult` instance containing the result of the listener.
+    fn stop(&mut self, grace_period: Duration) -> ListenerResult<'_, ()> {
+        let future = async move {
+            self.close().await?;
+
+            if let Some(drain) = self.drain.take() {
+                debug!("Draining {} connection(s) on port {}", drain.active(), self.config.port());
+                if !drain
+                    .drain(grace_period)
+                    .await
+                {
+                    warn!(
+                        "Grace period expired, closing remaining connections on port {}",
+                        self.config.port()
+                    );
+                }
+            }
+
             Ok(())
         };
 
@@ -172,8 +288,9 @@ impl TcpListener {
     async fn handle_connections(
         &mut self,
         protocol: Protocol,
-        listener: VetisTcpListener,
+        accepting: Accepting,
         virtual_hosts: VetisVirtualHosts,
+        watch: Watch,
     ) -> Result<GateTask, VetisError> {
         let alpn = vec![
             #[cfg(feature = "http1")]
@@ -183,8 +300,13 @@ impl TcpListener {
             #[cfg(feature = "http3")]
             b"h3".to_vec(),
         ];
-        let tls_config = TlsFactory::create_tls_config(virtual_hosts.clone(), alpn).await?;
         let port = Arc::new(self.config.port());
+        let tls_config = match &self.cert_resolver {
+            Some(cert_resolver) => {
+                TlsFactory::create_tls_config_with_resolver(cert_resolver.clone(), *port, alpn)?
+            }
+            None => TlsFactory::create_tls_config(virtual_hosts.clone(), *port, alpn).await?,
+        };
         let tls_config = match tls_config {
             Some(config) => config,
             None => {
@@ -195,7 +317,7 @@ impl TcpListener {
         let tls_acceptor = VetisTlsAcceptor::from(Arc::new(tls_config));
         let future = async move {
             loop {
-                let result = listener
+                let result = accepting
                     .accept()
                     .await;
 
@@ -209,6 +331,15 @@ impl TcpListener {
 
                 // TODO: Check ACL before proceeding
 
+                let local_addr = match stream.local_addr() {
+                    Ok(local_addr) => local_addr,
+                    Err(e) => {
+                        error!("Cannot read local address: {:?}", e);
+                        continue;
+                    }
+                };
+                let connection = ConnectionInfo::new(client_addr, local_addr);
+
                 let mut peekable = AsyncPeekable::from(stream);
 
                 let mut peeked = [0; 2];
@@ -236,6 +367,10 @@ impl TcpListener {
                         }
                     };
 
+                    let (_, tls_connection) = tls_stream.get_ref();
+                    let connection =
+                        Arc::new(connection.with_tls(TlsInfo::from_connection(tls_connection)));
+
                     let io = VetisIo::new(tls_stream);
                     match protocol {
                         #[cfg(feature = "http1")]
@@ -244,7 +379,14 @@ impl TcpListener {
                                 port.clone(),
                                 io,
                                 virtual_hosts.clone(),
-                                client_addr,
+                                connection,
+                                watch.clone(),
+                                #[cfg(all(
+                                    target_os = "linux",
+                                    feature = "tokio-rt",
+                                    feature = "static-files"
+                                ))]
+                                None,
                             );
                         }
                         #[cfg(feature = "http2")]
@@ -253,7 +395,8 @@ impl TcpListener {
                                 port.clone(),
                                 io,
                                 virtual_hosts.clone(),
-                                client_addr,
+                                connection,
+                                watch.clone(),
                             );
                         }
                         #[cfg(feature = "http3")]
@@ -265,6 +408,29 @@ impl TcpListener {
                         }
                     }
                 } else {
+                    let connection = Arc::new(connection);
+
+                    // Static files are sent with sendfile on plaintext HTTP/1 connections
+                    #[cfg(all(
+                        target_os = "linux",
+                        feature = "tokio-rt",
+                        feature = "http1",
+                        feature = "static-files"
+                    ))]
+                    if matches!(protocol, Protocol::Http1) {
+                        let queue = SendfileQueue::default();
+                        let io = VetisIo::new(SendfileStream::new(peekable, queue.clone()));
+                        let _ = handle_http1_request(
+                            port.clone(),
+                            io,
+                            virtual_hosts.clone(),
+                            connection,
+                            watch.clone(),
+                            Some(queue),
+                        );
+                        continue;
+                    }
+
                     let io = VetisIo::new(peekable);
                     match protocol {
                         #[cfg(feature = "http1")]
@@ -273,7 +439,14 @@ impl TcpListener {
                                 port.clone(),
                                 io,
                                 virtual_hosts.clone(),
-                                client_addr,
+                                connection,
+                                watch.clone(),
+                                #[cfg(all(
+                                    target_os = "linux",
+                                    feature = "tokio-rt",
+                                    feature = "static-files"
+                                ))]
+                                None,
                             );
                         }
                         #[cfg(feature = "http2")]
@@ -282,7 +455,8 @@ impl TcpListener {
                                 port.clone(),
                                 io,
                                 virtual_hosts.clone(),
-                                client_addr,
+                                connection,
+                                watch.clone(),
                             );
                         }
                         #[cfg(feature = "http3")]
@@ -307,7 +481,7 @@ async fn process_request(
     req: http::Request<Incoming>,
     virtual_hosts: VetisVirtualHosts,
     port: Arc<u16>,
-    client_addr: SocketAddr,
+    connection: Arc<ConnectionInfo>,
 ) -> Result<http::Response<HttpBody>, VetisError> {
     let host = req
         .headers()
@@ -336,16 +510,24 @@ async fn process_request(
 
     if let Some(host) = host {
         debug!("Serving request for host: {}", host);
-        let virtual_hosts = virtual_hosts
-            .read()
-            .await;
-
-        let virtual_host = virtual_hosts.get(&(host.into(), *port.clone()));
+        let hostname = host.to_ascii_lowercase();
+
+        // Released before routing, so virtual hosts can be replaced while requests are served
+        let virtual_host = find_virtual_host(
+            &*virtual_hosts
+                .read()
+                .await,
+            &hostname,
+            *port,
+        )
+        .cloned();
 
         if let Some(virtual_host) = virtual_host {
-            // TODO: Save client_addr in request, grab url from request for logging
+            let client_addr = connection.client_addr();
+
             let (parts, body) = req.into_parts();
-            let request = Request::from_parts(parts, HttpBody::from_incoming(body));
+            let mut request = Request::from_parts(parts, HttpBody::from_incoming(body));
+            request.set_connection(connection);
 
             let method = request
                 .method()
@@ -415,23 +597,57 @@ fn handle_http1_request<T>(
     port: Arc<u16>,
     io: VetisIo<T>,
     virtual_hosts: VetisVirtualHosts,
-    client_addr: SocketAddr,
+    connection: Arc<ConnectionInfo>,
+    watch: Watch,
+    #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "static-files"))]
+    sendfile: Option<SendfileQueue>,
 ) -> Result<(), VetisError>
 where
     T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
 {
+    let client_addr = connection.client_addr();
+    #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "static-files"))]
+    let writev = sendfile.is_some();
     let service_fn = service_fn(move |req| {
         let value = virtual_hosts.clone();
         let port = port.clone();
-        async move { process_request(req, value, port, client_addr).await }
+        let connection = connection.clone();
+        #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "static-files"))]
+        let sendfile = sendfile.clone();
+        async move {
+            #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "static-files"))]
+            let is_head = req.method() == http::Method::HEAD;
+
+            #[allow(unused_mut)]
+            let mut response = process_request(req, value, port, connection).await;
+
+            #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "static-files"))]
+            if let (Ok(response), Some(sendfile)) = (&mut response, &sendfile) {
+                sendfile.prepare(response, is_head);
+            }
+
+            response
+        }
     });
 
     let future = async move {
-        if let Err(err) = http1::Builder::new()
+        #[allow(unused_mut)]
+        let mut builder = http1::Builder::new();
+        // Placeholder frames must reach the socket as their own slices
+        #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "static-files"))]
+        if writev {
+            builder.writev(true);
+        }
+        let conn = pin!(builder
             .serve_connection(io, service_fn)
+            .with_upgrades());
+        match watch
+            .watch(conn, |conn| conn.graceful_shutdown())
             .await
         {
-            error!("Error serving connection: {:?}", err);
+            Some(Err(err)) => error!("Error serving connection: {:?}", err),
+            Some(Ok(_)) => {}
+            None => debug!("Connection from {} closed on shutdown", client_addr),
         }
     };
 
@@ -445,23 +661,32 @@ pub fn handle_http2_request<T>(
     port: Arc<u16>,
     io: VetisIo<T>,
     virtual_hosts: VetisVirtualHosts,
-    client_addr: SocketAddr,
+    connection: Arc<ConnectionInfo>,
+    watch: Watch,
 ) -> Result<(), VetisError>
 where
     T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
 {
+    let client_addr = connection.client_addr();
     let service_fn = service_fn(move |req| {
         let value = virtual_hosts.clone();
         let port = port.clone();
-        async move { process_request(req, value, port, client_addr).await }
+        let connection = connection.clone();
+        async move { process_request(req, value, port, connection).await }
     });
 
     let future = async move {
-        if let Err(err) = http2::Builder::new(VetisExecutor::new())
-            .serve_connection(io, service_fn)
+        let mut builder = http2::Builder::new(VetisExecutor::new());
+        #[cfg(feature = "websocket")]
+        builder.enable_connect_protocol();
+        let conn = pin!(builder.serve_connection(io, service_fn));
+        match watch
+            .watch(conn, |conn| conn.graceful_shutdown())
             .await
         {
-            error!("Error serving connection: {:?}", err);
+            Some(Err(err)) => error!("Error serving connection: {:?}", err),
+            Some(Ok(_)) => {}
+            None => debug!("Connection from {} closed on shutdown", client_addr),
         }
     };
 
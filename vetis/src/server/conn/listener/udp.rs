@@ -1,28 +1,41 @@
 use std::{
     collections::HashMap,
     net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::pin,
     sync::Arc,
+    time::Duration,
 };
 
 use bytes::Bytes;
-use futures_util::StreamExt;
+use futures_util::{
+    future::{select, Either},
+    StreamExt,
+};
 use h3::server::{Connection, RequestResolver};
 use h3_quinn::{
-    quinn::{self, crypto::rustls::QuicServerConfig},
+    quinn::{
+        self,
+        crypto::rustls::{HandshakeData, QuicServerConfig},
+    },
     Connection as QuinnConnection,
 };
 
 use hyper_body_utils::HttpBody;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use rt_gate::{spawn_server, spawn_worker, GateTask};
+use rustls::pki_types::CertificateDer;
 
 use crate::{
     config::server::ListenerConfig,
     errors::{StartError::Tls, VetisError},
     server::{
-        conn::listener::{Listener, ListenerResult},
-        http::{static_response, Request},
-        tls::TlsFactory,
+        conn::{
+            drain::{Drain, Watch},
+            listener::{Listener, ListenerResult},
+        },
+        http::{static_response, ConnectionInfo, Request, TlsInfo},
+        tls::{SniResolver, TlsFactory},
+        virtual_host::find_virtual_host,
     },
     VetisRwLock, VetisVirtualHosts,
 };
@@ -31,6 +44,9 @@ use crate::{
 pub struct UdpListener {
     config: ListenerConfig,
     task: Option<GateTask>,
+    endpoint: Option<quinn::Endpoint>,
+    drain: Option<Drain>,
+    cert_resolver: Option<Arc<SniResolver>>,
     virtual_hosts: VetisVirtualHosts,
 }
 
@@ -45,7 +61,19 @@ impl Listener for UdpListener {
     ///
     /// * `Self` - A new `UdpListener` instance.
     fn new(config: ListenerConfig) -> Self {
-        Self { config, task: None, virtual_hosts: Arc::new(VetisRwLock::new(HashMap::new())) }
+        Self {
+            config,
+            task: None,
+            endpoint: None,
+            drain: None,
+            cert_resolver: None,
+            virtual_hosts: Arc::new(VetisRwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the listener configuration
+    fn config(&self) -> &ListenerConfig {
+        &self.config
     }
 
     /// Allow set virtual hosts
@@ -57,6 +85,17 @@ impl Listener for UdpListener {
         self.virtual_hosts = virtual_hosts;
     }
 
+    /// Set the certificate resolver shared with other listeners
+    ///
+    /// When not set, certificates are loaded from the virtual hosts once the listener starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_resolver` - A `SniResolver` instance following virtual host changes.
+    fn set_cert_resolver(&mut self, cert_resolver: Arc<SniResolver>) {
+        self.cert_resolver = Some(cert_resolver);
+    }
+
     /// Listen for incoming connections
     ///
     /// # Returns
@@ -82,12 +121,23 @@ impl Listener for UdpListener {
                 }
             };
 
-            let tls_config = TlsFactory::create_tls_config(
-                self.virtual_hosts
-                    .clone(),
-                vec![b"h3".to_vec()],
-            )
-            .await?;
+            let alpn = vec![b"h3".to_vec()];
+            let tls_config = match &self.cert_resolver {
+                Some(cert_resolver) => TlsFactory::create_tls_config_with_resolver(
+                    cert_resolver.clone(),
+                    self.config.port(),
+                    alpn,
+                )?,
+                None => {
+                    TlsFactory::create_tls_config(
+                        self.virtual_hosts
+                            .clone(),
+                        self.config.port(),
+                        alpn,
+                    )
+                    .await?
+                }
+            };
 
             if let Some(tls_config) = tls_config {
                 let quic_config = QuicServerConfig::try_from(tls_config)
@@ -98,15 +148,20 @@ impl Listener for UdpListener {
                 let endpoint = quinn::Endpoint::server(server_config, addr)
                     .map_err(|e| VetisError::Bind(e.to_string()))?;
 
+                let drain = Drain::new();
+
                 let server_task = self
                     .handle_connections(
-                        endpoint,
+                        endpoint.clone(),
                         self.virtual_hosts
                             .clone(),
+                        drain.watch(),
                     )
                     .await?;
 
                 self.task = Some(server_task);
+                self.endpoint = Some(endpoint);
+                self.drain = Some(drain);
             }
 
             Ok(())
@@ -116,14 +171,48 @@ impl Listener for UdpListener {
 
     /// Stop the listener
     ///
+    /// New connections are refused, open connections receive a `GOAWAY` and are
+    /// closed once their in-flight requests complete or the grace period expires.
+    ///
+    /// # Arguments
+    ///
+    /// * `grace_period` - How long to wait for in-flight requests to complete.
+    ///
     /// # Returns
     ///
     /// * `ListenerResult<'_, ()>` - A `ListenerResult` instance containing the result of the listener.
-    fn stop(&mut self) -> ListenerResult<'_, ()> {
+    fn close(&mut self) -> ListenerResult<'_, ()> {
+        // QUIC connections share the socket of the endpoint, releasing it closes them too
+        self.stop(Duration::ZERO)
+    }
+
+    fn stop(&mut self, grace_period: Duration) -> ListenerResult<'_, ()> {
         Box::pin(async move {
+            if let Some(endpoint) = &self.endpoint {
+                endpoint.set_server_config(None);
+            }
+
             if let Some(mut task) = self.task.take() {
                 task.cancel().await;
             }
+
+            if let Some(drain) = self.drain.take() {
+                debug!("Draining {} connection(s) on port {}", drain.active(), self.config.port());
+                if !drain
+                    .drain(grace_period)
+                    .await
+                {
+                    warn!(
+                        "Grace period expired, closing remaining connections on port {}",
+                        self.config.port()
+                    );
+                }
+            }
+
+            if let Some(endpoint) = self.endpoint.take() {
+                endpoint.close(0u32.into(), b"shutdown");
+            }
+
             Ok(())
         })
     }
@@ -134,8 +223,12 @@ impl UdpListener {
         &mut self,
         endpoint: quinn::Endpoint,
         virtual_hosts: VetisVirtualHosts,
+        watch: Watch,
     ) -> Result<GateTask, VetisError> {
         let port = self.config.port();
+        let local_addr = endpoint
+            .local_addr()
+            .map_err(|e| VetisError::Bind(e.to_string()))?;
         let task = spawn_server(async move {
             while let Some(new_conn) = endpoint
                 .accept()
@@ -143,10 +236,17 @@ impl UdpListener {
             {
                 let virtual_hosts = virtual_hosts.clone();
                 let addr = new_conn.remote_address();
+                let watch = watch.clone();
                 spawn_worker(async move {
                     match new_conn.await {
                         Ok(conn) => {
-                            let mut h3_conn: Connection<QuinnConnection, Bytes> =
+                            let connection = Arc::new(
+                                ConnectionInfo::new(addr, local_addr).with_tls(tls_info(&conn)),
+                            );
+
+                            // Handle closing the QUIC connection once the grace period expires
+                            let quic_conn = conn.clone();
+                            let h3_conn: Connection<QuinnConnection, Bytes> =
                                 match Connection::new(QuinnConnection::new(conn)).await {
                                     Ok(conn) => conn,
                                     Err(err) => {
@@ -155,31 +255,20 @@ impl UdpListener {
                                     }
                                 };
 
-                            loop {
-                                match h3_conn
-                                    .accept()
-                                    .await
-                                {
-                                    Ok(Some(resolver)) => {
-                                        let result = handle_http_request(
-                                            port,
-                                            resolver,
-                                            virtual_hosts.clone(),
-                                            addr,
-                                        );
-
-                                        if let Err(err) = result {
-                                            error!("Error handling HTTP request: {:?}", err);
-                                        }
-                                    }
-                                    Ok(None) => {
-                                        break;
-                                    }
-                                    Err(err) => {
-                                        error!("Cannot accept connection: {:?}", err);
-                                        break;
-                                    }
-                                }
+                            // Connections send their own GOAWAY once a shutdown is requested
+                            let serving = pin!(serve_connection(
+                                port,
+                                h3_conn,
+                                virtual_hosts,
+                                connection,
+                                watch.clone(),
+                            ));
+                            if watch
+                                .watch(serving, |_| {})
+                                .await
+                                .is_none()
+                            {
+                                quic_conn.close(0u32.into(), b"shutdown");
                             }
                         }
                         Err(err) => {
@@ -198,14 +287,86 @@ impl UdpListener {
     }
 }
 
+/// Serve the requests of a QUIC connection until the client closes it
+///
+/// Once a graceful shutdown is requested a `GOAWAY` is sent, requests already
+/// received are still served.
+async fn serve_connection(
+    port: u16,
+    mut h3_conn: Connection<QuinnConnection, Bytes>,
+    virtual_hosts: VetisVirtualHosts,
+    connection: Arc<ConnectionInfo>,
+    watch: Watch,
+) {
+    let mut draining = false;
+    loop {
+        let accepted = if draining {
+            h3_conn
+                .accept()
+                .await
+        } else {
+            let accepted = {
+                let accept = pin!(h3_conn.accept());
+                let shutdown = pin!(watch.shutdown());
+                match select(accept, shutdown).await {
+                    Either::Left((accepted, _)) => Some(accepted),
+                    Either::Right(_) => None,
+                }
+            };
+
+            match accepted {
+                Some(accepted) => accepted,
+                None => {
+                    draining = true;
+                    // Send GOAWAY, requests already received are still served
+                    if let Err(err) = h3_conn
+                        .shutdown(0)
+                        .await
+                    {
+                        error!("Cannot shutdown connection: {:?}", err);
+                        break;
+                    }
+                    continue;
+                }
+            }
+        };
+
+        match accepted {
+            Ok(Some(resolver)) => {
+                let result = handle_http_request(
+                    port,
+                    resolver,
+                    virtual_hosts.clone(),
+                    connection.clone(),
+                    watch.clone(),
+                );
+
+                if let Err(err) = result {
+                    error!("Error handling HTTP request: {:?}", err);
+                }
+            }
+            Ok(None) => {
+                break;
+            }
+            Err(err) => {
+                error!("Cannot accept connection: {:?}", err);
+                break;
+            }
+        }
+    }
+}
+
 fn handle_http_request(
     port: u16,
     resolver: RequestResolver<QuinnConnection, Bytes>,
     virtual_hosts: VetisVirtualHosts,
-    client_addr: SocketAddr,
+    connection: Arc<ConnectionInfo>,
+    watch: Watch,
 ) -> Result<(), VetisError> {
     let virtual_hosts = virtual_hosts.clone();
     spawn_worker(async move {
+        // Keeps the listener draining until the response has been sent
+        let _watch = watch;
         let result = resolver
             .resolve_request()
             .await;
@@ -216,6 +377,7 @@ fn handle_http_request(
             let uri = parts.uri.clone();
             let body = HttpBody::from_quic_server(recv_stream);
             let request = http::Request::from_parts(parts, body);
+            let client_addr = connection.client_addr();
 
             let host = request
                 .uri()
@@ -224,15 +386,24 @@ fn handle_http_request(
             let virtual_hosts = virtual_hosts.clone();
             let response = if let Some(host) = host {
                 debug!("Serving request for host: {}", host);
-                let virtual_host = virtual_hosts
-                    .read()
-                    .await;
-
-                let virtual_host = virtual_host.get(&(host.host().into(), port));
+                let hostname = host
+                    .host()
+                    .to_ascii_lowercase();
+
+                // Released before routing, so virtual hosts can be replaced while requests are served
+                let virtual_host = find_virtual_host(
+                    &*virtual_hosts
+                        .read()
+                        .await,
+                    &hostname,
+                    port,
+                )
+                .cloned();
 
                 let response = if let Some(virtual_host) = virtual_host {
                     let (parts, body) = request.into_parts();
-                    let request = Request::from_parts(parts, body);
+                    let mut request = Request::from_parts(parts, body);
+                    request.set_connection(connection);
 
                     let vetis_response = virtual_host
                         .route(request)
@@ -352,3 +523,33 @@ fn handle_http_request(
 
     Ok(())
 }
+
+/// TLS session details of a QUIC connection, always secured with TLS 1.3
+fn tls_info(conn: &quinn::Connection) -> TlsInfo {
+    let handshake = conn
+        .handshake_data()
+        .and_then(|data| {
+            data.downcast::<HandshakeData>()
+                .ok()
+        });
+
+    let peer_certificates = conn
+        .peer_identity()
+        .and_then(|identity| {
+            identity
+                .downcast::<Vec<CertificateDer<'static>>>()
+                .ok()
+        })
+        .map(|certificates| *certificates)
+        .unwrap_or_default();
+
+    match handshake {
+        Some(handshake) => TlsInfo::new(
+            handshake.server_name,
+            handshake.protocol,
+            Some("TLSv1.3"),
+            peer_certificates,
+        ),
+        None => TlsInfo::new(None, None, Some("TLSv1.3"), peer_certificates),
+    }
+}
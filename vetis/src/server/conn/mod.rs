@@ -1 +1,4 @@
+pub(crate) mod drain;
 pub(crate) mod listener;
+#[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "http1", feature = "static-files"))]
+pub(crate) mod sendfile;
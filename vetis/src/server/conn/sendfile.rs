@@ -0,0 +1,282 @@
+//! Zero-copy delivery of static files with `sendfile(2)`
+//!
+//! hyper writes response bodies itself, so files cannot be handed to it directly. Instead, a
+//! static path describes the file range of its response with a `SendFile` extension, and the
+//! connection replaces the body with placeholder frames of the same length. With vectored
+//! writes, hyper passes each frame to the socket as its own slice, which `SendfileStream`
+//! recognizes by its address and sends from the file instead, in the order responses are
+//! written.
+//!
+//! Only plaintext HTTP/1 connections are served this way, other connections and responses
+//! transformed by a middleware, such as compressed ones, keep the regular body.
+
+use std::{
+    collections::VecDeque,
+    io::IoSlice,
+    os::fd::AsRawFd,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::stream;
+use http::{header, HeaderValue, StatusCode};
+use http_body_util::{BodyExt, StreamBody};
+use hyper::body::Frame;
+use hyper_body_utils::HttpBody;
+use peekable::tokio::AsyncPeekable;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, Interest, ReadBuf},
+    net::TcpStream,
+};
+
+use crate::server::http::body::SyncBody;
+
+/// Size of the placeholder frames, the most sent by a single call
+const PLACEHOLDER_SIZE: usize = 512 * 1024;
+
+/// Memory the placeholder frames point to, never sent itself
+static PLACEHOLDER: [u8; PLACEHOLDER_SIZE] = [0; PLACEHOLDER_SIZE];
+
+/// File range of a response body, which may be sent with `sendfile(2)`
+#[derive(Clone)]
+pub(crate) struct SendFile {
+    file: Arc<std::fs::File>,
+    offset: u64,
+    len: u64,
+    encoding: Option<&'static str>,
+}
+
+impl SendFile {
+    /// Describe the body of a response sending `len` bytes of a file from `offset`
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file
+    /// * `offset` - The first byte of the body
+    /// * `len` - The length of the body
+    /// * `encoding` - The content coding of the file, for precompressed variants
+    pub(crate) fn new(
+        file: Arc<std::fs::File>,
+        offset: u64,
+        len: u64,
+        encoding: Option<&'static str>,
+    ) -> Self {
+        SendFile { file, offset, len, encoding }
+    }
+
+    /// Whether the response still sends the file as is
+    fn matches(&self, response: &http::Response<HttpBody>) -> bool {
+        let status = response.status();
+        if status != StatusCode::OK && status != StatusCode::PARTIAL_CONTENT {
+            return false;
+        }
+
+        let headers = response.headers();
+        let content_length = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .parse::<u64>()
+                    .ok()
+            });
+        let encoding = headers
+            .get(header::CONTENT_ENCODING)
+            .map(HeaderValue::as_bytes);
+
+        self.len > 0
+            && content_length == Some(self.len)
+            && encoding
+                == self
+                    .encoding
+                    .map(str::as_bytes)
+            && !headers.contains_key(header::TRANSFER_ENCODING)
+    }
+}
+
+/// Range of a file left to send
+struct Job {
+    file: Arc<std::fs::File>,
+    offset: u64,
+    remaining: u64,
+}
+
+/// Files to send on a connection, in the order of their responses
+#[derive(Clone, Default)]
+pub(crate) struct SendfileQueue(Arc<Mutex<VecDeque<Job>>>);
+
+impl SendfileQueue {
+    /// Replace the body of a response with placeholder frames when it can be sent with `sendfile(2)`
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The response, whose `SendFile` extension is removed
+    /// * `is_head` - Whether the response answers a `HEAD` request, which has no body
+    pub(crate) fn prepare(&self, response: &mut http::Response<HttpBody>, is_head: bool) {
+        let Some(send_file) = response
+            .extensions_mut()
+            .remove::<SendFile>()
+        else {
+            return;
+        };
+        if is_head || !send_file.matches(response) {
+            return;
+        }
+        let Ok(mut jobs) = self.0.lock() else {
+            return;
+        };
+
+        jobs.push_back(Job {
+            file: send_file.file,
+            offset: send_file.offset,
+            remaining: send_file.len,
+        });
+        *response.body_mut() = placeholder_body(send_file.len);
+    }
+}
+
+/// Body of `len` bytes of placeholder frames
+fn placeholder_body(len: u64) -> HttpBody {
+    let frames = (0..len)
+        .step_by(PLACEHOLDER_SIZE)
+        .map(move |start| {
+            let size = (len - start).min(PLACEHOLDER_SIZE as u64) as usize;
+            Ok::<_, std::io::Error>(Frame::data(Bytes::from_static(&PLACEHOLDER[..size])))
+        });
+
+    HttpBody::Stream(SyncBody::new(StreamBody::new(stream::iter(frames))).boxed())
+}
+
+/// Whether a slice written by hyper is a placeholder frame
+fn is_placeholder(buf: &[u8]) -> bool {
+    let start = PLACEHOLDER.as_ptr() as usize;
+    let address = buf.as_ptr() as usize;
+
+    !buf.is_empty() && address >= start && address + buf.len() <= start + PLACEHOLDER_SIZE
+}
+
+/// Plaintext TCP stream sending placeholder frames from the files of its queue
+pub(crate) struct SendfileStream {
+    inner: AsyncPeekable<TcpStream>,
+    queue: SendfileQueue,
+}
+
+impl SendfileStream {
+    /// Wrap a plaintext stream
+    pub(crate) fn new(inner: AsyncPeekable<TcpStream>, queue: SendfileQueue) -> Self {
+        SendfileStream { inner, queue }
+    }
+
+    /// The socket, written to directly as peeked bytes only matter to reads
+    fn stream(&mut self) -> &mut TcpStream {
+        let (_, stream) = self.inner.get_mut();
+        stream
+    }
+
+    /// Send up to `max` bytes of the next file of the queue
+    fn poll_sendfile(&mut self, cx: &mut Context<'_>, max: usize) -> Poll<std::io::Result<usize>> {
+        let (_, stream) = self.inner.get_ref();
+
+        loop {
+            ready!(stream.poll_write_ready(cx))?;
+
+            let mut jobs = self
+                .queue
+                .0
+                .lock()
+                .map_err(|_| std::io::Error::other("sendfile queue poisoned"))?;
+            let Some(job) = jobs.front_mut() else {
+                return Poll::Ready(Err(std::io::Error::other("placeholder without a file")));
+            };
+
+            let count = job
+                .remaining
+                .min(max as u64) as usize;
+            let result = stream.try_io(Interest::WRITABLE, || {
+                let mut offset = job.offset as libc::off_t;
+                let sent = unsafe {
+                    libc::sendfile(stream.as_raw_fd(), job.file.as_raw_fd(), &mut offset, count)
+                };
+                if sent < 0 {
+                    Err(std::io::Error::last_os_error())
+                } else {
+                    Ok(sent as usize)
+                }
+            });
+
+            match result {
+                // The file was truncated since its response was sent
+                Ok(0) => {
+                    return Poll::Ready(Err(std::io::Error::from(
+                        std::io::ErrorKind::UnexpectedEof,
+                    )))
+                }
+                Ok(sent) => {
+                    job.offset += sent as u64;
+                    job.remaining -= sent as u64;
+                    if job.remaining == 0 {
+                        jobs.pop_front();
+                    }
+                    return Poll::Ready(Ok(sent));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+}
+
+impl AsyncRead for SendfileStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for SendfileStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        if is_placeholder(buf) {
+            return self.poll_sendfile(cx, buf.len());
+        }
+        Pin::new(self.stream()).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        // Bytes before a placeholder are written first, hyper calls again with the rest
+        match bufs
+            .iter()
+            .position(|buf| is_placeholder(buf))
+        {
+            Some(0) => self.poll_sendfile(cx, bufs[0].len()),
+            Some(placeholder) => {
+                Pin::new(self.stream()).poll_write_vectored(cx, &bufs[..placeholder])
+            }
+            None => Pin::new(self.stream()).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(self.stream()).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(self.stream()).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    pin::Pin,
+    sync::{Mutex, PoisonError},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_util::future::{select, Either};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use hyper::body::{Body, Frame, SizeHint};
+use hyper_body_utils::HttpBody;
+
+use crate::{
+    errors::{BodyError, VetisError, VirtualHostError},
+    rt::sleep,
+};
+
+pub(crate) fn body_error(error: BodyError) -> VetisError {
+    VetisError::VirtualHost(VirtualHostError::Body(error))
+}
+
+/// Collect a body, failing once more than `limit` bytes were received or once `timeout` elapsed.
+pub(crate) async fn collect(
+    body: HttpBody,
+    limit: usize,
+    timeout: Duration,
+) -> Result<Bytes, VetisError> {
+    let collecting = Limited::new(body, limit).collect();
+    let collected = match select(Box::pin(collecting), Box::pin(sleep(timeout))).await {
+        Either::Left((collected, _)) => collected,
+        Either::Right(_) => return Err(body_error(BodyError::Timeout)),
+    };
+    let collected = collected.map_err(|e| {
+        if e.is::<LengthLimitError>() {
+            body_error(BodyError::TooLarge(limit))
+        } else {
+            body_error(BodyError::Read(e.to_string()))
+        }
+    })?;
+
+    Ok(collected.to_bytes())
+}
+
+/// Returns whether a content type has the given essence, such as `application/json`.
+pub(crate) fn has_media_type(content_type: &str, media_type: &str) -> bool {
+    content_type
+        .split(';')
+        .next()
+        .map(str::trim)
+        .is_some_and(|essence| essence.eq_ignore_ascii_case(media_type))
+}
+
+/// Returns whether a content type is JSON, including `+json` suffixed types.
+pub(crate) fn is_json(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .map(str::trim)
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+/// Returns the quality of a content coding in an `Accept-Encoding` header, from 0 to 1000.
+///
+/// Codings not listed get the quality of `*`, or 0 without it. `x-gzip` is an alias of `gzip`.
+#[cfg(any(feature = "static-files", feature = "compression"))]
+pub(crate) fn encoding_quality(accept_encoding: &str, encoding: &str) -> u16 {
+    let mut wildcard = None;
+
+    for item in accept_encoding.split(',') {
+        let mut params = item.split(';');
+        let coding = params
+            .next()
+            .unwrap_or_default()
+            .trim();
+        let q = params
+            .filter_map(|param| {
+                param
+                    .trim()
+                    .strip_prefix("q=")
+            })
+            .next()
+            .and_then(|q| {
+                q.parse::<f32>()
+                    .ok()
+            })
+            .map_or(1000, |q| (q.clamp(0.0, 1.0) * 1000.0).round() as u16);
+
+        if coding.eq_ignore_ascii_case(encoding)
+            || (encoding == "gzip" && coding.eq_ignore_ascii_case("x-gzip"))
+        {
+            return q;
+        }
+        if coding == "*" {
+            wildcard = Some(q);
+        }
+    }
+
+    wildcard.unwrap_or(0)
+}
+
+/// Body adapter for bodies that are not `Sync`, such as streams or axum bodies
+pub(crate) struct SyncBody<B>(Mutex<Pin<Box<B>>>);
+
+impl<B> SyncBody<B> {
+    pub(crate) fn new(body: B) -> Self {
+        Self(Mutex::new(Box::pin(body)))
+    }
+}
+
+impl<B: Body> Body for SyncBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.get_mut()
+            .0
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+            .poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .size_hint()
+    }
+}
+
+#[cfg(feature = "multipart")]
+fn multipart_error(error: multer::Error) -> VetisError {
+    match error {
+        multer::Error::StreamSizeExceeded { limit }
+        | multer::Error::FieldSizeExceeded { limit, .. } => {
+            body_error(BodyError::TooLarge(limit as usize))
+        }
+        multer::Error::StreamReadFailed(e) => body_error(BodyError::Read(e.to_string())),
+        e => body_error(BodyError::Invalid(e.to_string())),
+    }
+}
+
+/// Streaming reader of a `multipart/form-data` body.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::Request;
+///
+/// async fn upload(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+///     let mut multipart = request.multipart()?;
+///     while let Some(mut field) = multipart.next_field().await? {
+///         let name = field.name().unwrap_or_default().to_string();
+///         while let Some(chunk) = field.chunk().await? {
+///             // Write chunk somewhere...
+///         }
+///     }
+///     Ok(/* response */)
+/// }
+/// ```
+#[cfg(feature = "multipart")]
+pub struct Multipart {
+    inner: multer::Multipart<'static>,
+}
+
+#[cfg(feature = "multipart")]
+impl Multipart {
+    pub(crate) fn new(body: HttpBody, boundary: String, limit: usize) -> Self {
+        let constraints = multer::Constraints::new()
+            .size_limit(multer::SizeLimit::new().whole_stream(limit as u64));
+
+        Self {
+            inner: multer::Multipart::with_constraints(
+                body.into_data_stream(),
+                boundary,
+                constraints,
+            ),
+        }
+    }
+
+    /// Returns the next field, `None` once every field was read.
+    ///
+    /// The previous field must be dropped before reading the next one.
+    pub async fn next_field(&mut self) -> Result<Option<Field>, VetisError> {
+        let field = self
+            .inner
+            .next_field()
+            .await
+            .map_err(multipart_error)?;
+
+        Ok(field.map(|inner| Field { inner }))
+    }
+}
+
+/// A field of a `multipart/form-data` body.
+#[cfg(feature = "multipart")]
+pub struct Field {
+    inner: multer::Field<'static>,
+}
+
+#[cfg(feature = "multipart")]
+impl Field {
+    /// Returns the field name.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    /// Returns the name of the uploaded file.
+    pub fn file_name(&self) -> Option<&str> {
+        self.inner
+            .file_name()
+    }
+
+    /// Returns the content type of the field.
+    pub fn content_type(&self) -> Option<&str> {
+        self.inner
+            .content_type()
+            .map(|mime| mime.as_ref())
+    }
+
+    /// Returns the field headers.
+    pub fn headers(&self) -> &http::HeaderMap {
+        self.inner.headers()
+    }
+
+    /// Returns the next chunk of the field content, `None` once it was fully read.
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, VetisError> {
+        self.inner
+            .chunk()
+            .await
+            .map_err(multipart_error)
+    }
+
+    /// Reads the whole field content.
+    pub async fn bytes(self) -> Result<Bytes, VetisError> {
+        self.inner
+            .bytes()
+            .await
+            .map_err(multipart_error)
+    }
+
+    /// Reads the whole field content as text.
+    pub async fn text(self) -> Result<String, VetisError> {
+        self.inner
+            .text()
+            .await
+            .map_err(multipart_error)
+    }
+}
@@ -0,0 +1,142 @@
+use std::net::SocketAddr;
+
+use rustls::pki_types::CertificateDer;
+
+/// Information about the connection a request was received on.
+///
+/// Listeners attach it to every request, handlers read it with `Request::connection`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::Request;
+///
+/// async fn handler(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+///     if let Some(connection) = request.connection() {
+///         log::info!("{} via {}", connection.client_addr(), connection.local_addr());
+///     }
+///     Ok(/* response */)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    client_addr: SocketAddr,
+    local_addr: SocketAddr,
+    tls: Option<TlsInfo>,
+}
+
+impl ConnectionInfo {
+    pub(crate) fn new(client_addr: SocketAddr, local_addr: SocketAddr) -> Self {
+        Self { client_addr, local_addr, tls: None }
+    }
+
+    pub(crate) fn with_tls(mut self, tls: TlsInfo) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Returns the address of the client.
+    pub fn client_addr(&self) -> SocketAddr {
+        self.client_addr
+    }
+
+    /// Returns the local address the connection was accepted on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns whether the connection is encrypted.
+    pub fn is_tls(&self) -> bool {
+        self.tls.is_some()
+    }
+
+    /// Returns the TLS session details, `None` for plain text connections.
+    pub fn tls(&self) -> Option<&TlsInfo> {
+        self.tls.as_ref()
+    }
+}
+
+/// TLS session details negotiated with the client.
+#[derive(Debug, Clone, Default)]
+pub struct TlsInfo {
+    server_name: Option<String>,
+    alpn_protocol: Option<Vec<u8>>,
+    protocol_version: Option<&'static str>,
+    peer_certificates: Vec<CertificateDer<'static>>,
+}
+
+impl TlsInfo {
+    pub(crate) fn new(
+        server_name: Option<String>,
+        alpn_protocol: Option<Vec<u8>>,
+        protocol_version: Option<&'static str>,
+        peer_certificates: Vec<CertificateDer<'static>>,
+    ) -> Self {
+        Self { server_name, alpn_protocol, protocol_version, peer_certificates }
+    }
+
+    /// Collect the session details of a rustls server connection.
+    ///
+    /// QUIC connections collect theirs from the handshake data of the connection.
+    #[cfg(any(feature = "http1", feature = "http2"))]
+    pub(crate) fn from_connection(connection: &rustls::ServerConnection) -> Self {
+        let protocol_version = connection
+            .protocol_version()
+            .and_then(|version| match version {
+                rustls::ProtocolVersion::TLSv1_3 => Some("TLSv1.3"),
+                rustls::ProtocolVersion::TLSv1_2 => Some("TLSv1.2"),
+                _ => None,
+            });
+
+        let peer_certificates = connection
+            .peer_certificates()
+            .map(|certificates| {
+                certificates
+                    .iter()
+                    .map(|certificate| {
+                        certificate
+                            .clone()
+                            .into_owned()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self::new(
+            connection
+                .server_name()
+                .map(str::to_string),
+            connection
+                .alpn_protocol()
+                .map(<[u8]>::to_vec),
+            protocol_version,
+            peer_certificates,
+        )
+    }
+
+    /// Returns the server name sent by the client (SNI).
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name
+            .as_deref()
+    }
+
+    /// Returns the application protocol negotiated with ALPN, such as `h2`.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.alpn_protocol
+            .as_deref()
+    }
+
+    /// Returns the TLS protocol version, such as `TLSv1.3`.
+    pub fn protocol_version(&self) -> Option<&'static str> {
+        self.protocol_version
+    }
+
+    /// Returns the certificate chain presented by the client, empty when the client
+    /// did not authenticate.
+    ///
+    /// Certificates are only requested on ports with a virtual host requiring client
+    /// authentication, see `SecurityConfigBuilder::client_auth`.
+    pub fn peer_certificates(&self) -> &[CertificateDer<'static>] {
+        &self.peer_certificates
+    }
+}
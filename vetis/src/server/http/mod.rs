@@ -1,28 +1,49 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, net::IpAddr, sync::Arc, time::Duration};
 
+use futures_util::future::join_all;
 use http::HeaderMap;
 
 use hyper_body_utils::HttpBody;
+use log::{error, info};
+use rt_gate::spawn_worker;
 
 use crate::{
-    config::server::{Protocol, ServerConfig},
+    config::server::{ListenerConfig, Protocol, ServerConfig},
     errors::VetisError,
     server::{
         conn::listener::{Listener, ServerListener},
+        tls::SniResolver,
         Server,
     },
     VetisRwLock, VetisVirtualHosts,
 };
 
+pub(crate) mod body;
+mod connection;
+mod cookie;
 mod request;
 mod response;
+mod sse;
+#[cfg(feature = "websocket")]
+pub(crate) mod websocket;
 
-pub use crate::server::http::{request::Request, response::Response};
+#[cfg(feature = "multipart")]
+pub use crate::server::http::body::{Field, Multipart};
+#[cfg(feature = "websocket")]
+pub use crate::server::http::websocket::{CloseFrame, Message, WebSocket};
+pub use crate::server::http::{
+    connection::{ConnectionInfo, TlsInfo},
+    cookie::{Cookie, SameSite},
+    request::Request,
+    response::{Response, ResponseBuilder},
+    sse::{Event, DEFAULT_KEEP_ALIVE},
+};
 
 pub struct HttpServer {
     config: ServerConfig,
     listeners: Vec<ServerListener>,
     virtual_hosts: VetisVirtualHosts,
+    cert_resolver: Option<Arc<SniResolver>>,
 }
 
 impl Server for HttpServer {
@@ -40,6 +61,7 @@ impl Server for HttpServer {
             config,
             listeners: Vec::new(),
             virtual_hosts: Arc::new(VetisRwLock::new(HashMap::new())),
+            cert_resolver: None,
         }
     }
 
@@ -52,6 +74,15 @@ impl Server for HttpServer {
         self.virtual_hosts = virtual_hosts;
     }
 
+    /// Set the certificate resolver shared by the listeners.
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_resolver` - A `SniResolver` instance rebuilt on virtual host changes.
+    fn set_cert_resolver(&mut self, cert_resolver: Arc<SniResolver>) {
+        self.cert_resolver = Some(cert_resolver);
+    }
+
     /// Start the server.
     ///
     /// # Returns
@@ -62,38 +93,7 @@ impl Server for HttpServer {
             .config
             .listeners()
             .iter()
-            .map(|listener_config| match listener_config.protocol() {
-                #[cfg(feature = "http1")]
-                Protocol::Http1 => {
-                    let mut listener = ServerListener::new(listener_config.clone());
-                    listener.set_virtual_hosts(
-                        self.virtual_hosts
-                            .clone(),
-                    );
-                    listener
-                }
-                #[cfg(feature = "http2")]
-                Protocol::Http2 => {
-                    let mut listener = ServerListener::new(listener_config.clone());
-                    listener.set_virtual_hosts(
-                        self.virtual_hosts
-                            .clone(),
-                    );
-                    listener
-                }
-                #[cfg(feature = "http3")]
-                Protocol::Http3 => {
-                    let mut listener = ServerListener::new(listener_config.clone());
-                    listener.set_virtual_hosts(
-                        self.virtual_hosts
-                            .clone(),
-                    );
-                    listener
-                }
-                _ => {
-                    panic!("Unsupported protocol");
-                }
-            })
+            .map(|listener_config| self.create_listener(listener_config))
             .collect();
 
         for listener in listeners.iter_mut() {
@@ -109,22 +109,184 @@ impl Server for HttpServer {
 
     /// Stop the server.
     ///
+    /// All listeners are drained concurrently, giving in-flight requests up to the
+    /// configured grace period to complete.
+    ///
     /// # Returns
     ///
     /// * `Result<(), VetisError>` - A result containing `()` if the server stopped successfully, or a `VetisError` if the server failed to stop.
     async fn stop(&mut self) -> Result<(), VetisError> {
-        for listener in self
+        let grace_period = self
+            .config
+            .grace_period();
+
+        let results = join_all(
+            self.listeners
+                .iter_mut()
+                .map(|listener| listener.stop(grace_period)),
+        )
+        .await;
+
+        self.listeners
+            .clear();
+
+        results
+            .into_iter()
+            .collect()
+    }
+
+    /// Apply a new configuration to the running server.
+    ///
+    /// Listeners missing from the running server are bound first, running listeners
+    /// sharing their address are closed beforehand. If any of them fails to bind the
+    /// closed listeners accept connections again and the running configuration is kept.
+    /// Listeners no longer configured stop accepting connections and are drained in the
+    /// background.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - A `ServerConfig` instance containing the new server configuration.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), VetisError>` - A result containing `()` if the configuration was applied, or a `VetisError` if a new listener failed to start.
+    async fn reload(&mut self, config: ServerConfig) -> Result<(), VetisError> {
+        let added: Vec<&ListenerConfig> = config
+            .listeners()
+            .iter()
+            .filter(|listener_config| {
+                !self
+                    .listeners
+                    .iter()
+                    .any(|listener| listener.config() == *listener_config)
+            })
+            .collect();
+
+        let mut closed: Vec<usize> = Vec::new();
+        for (index, listener) in self
             .listeners
             .iter_mut()
+            .enumerate()
         {
-            listener
-                .stop()
-                .await?;
+            let replaced = !config
+                .listeners()
+                .contains(listener.config());
+            if replaced
+                && added
+                    .iter()
+                    .any(|added| overlaps(added, listener.config()))
+            {
+                listener
+                    .close()
+                    .await?;
+                closed.push(index);
+            }
+        }
+
+        let mut started: Vec<ServerListener> = Vec::new();
+        for listener_config in added {
+            let mut listener = self.create_listener(listener_config);
+            if let Err(e) = listener
+                .listen()
+                .await
+            {
+                for listener in started.iter_mut() {
+                    let _ = listener
+                        .stop(Duration::ZERO)
+                        .await;
+                }
+                for index in closed {
+                    if let Err(e) = self.listeners[index]
+                        .listen()
+                        .await
+                    {
+                        error!("Failed to reopen listener: {}", e);
+                    }
+                }
+                return Err(e);
+            }
+
+            info!(
+                "Server listening on port {}:{}",
+                listener_config.interface(),
+                listener_config.port()
+            );
+            started.push(listener);
+        }
+
+        let (kept, mut removed): (Vec<_>, Vec<_>) = self
+            .listeners
+            .drain(..)
+            .partition(|listener| {
+                config
+                    .listeners()
+                    .contains(listener.config())
+            });
+
+        self.listeners = kept;
+        self.listeners
+            .extend(started);
+
+        if !removed.is_empty() {
+            let grace_period = config.grace_period();
+            spawn_worker(async move {
+                for listener in removed.iter_mut() {
+                    info!(
+                        "Closing listener on port {}:{}",
+                        listener
+                            .config()
+                            .interface(),
+                        listener
+                            .config()
+                            .port()
+                    );
+                    if let Err(e) = listener
+                        .stop(grace_period)
+                        .await
+                    {
+                        error!("Failed to stop listener: {}", e);
+                    }
+                }
+            });
         }
+
+        self.config = config;
+
         Ok(())
     }
 }
 
+impl HttpServer {
+    fn create_listener(&self, listener_config: &ListenerConfig) -> ServerListener {
+        let mut listener = ServerListener::new(listener_config.clone());
+        listener.set_virtual_hosts(
+            self.virtual_hosts
+                .clone(),
+        );
+        if let Some(cert_resolver) = &self.cert_resolver {
+            listener.set_cert_resolver(cert_resolver.clone());
+        }
+        listener
+    }
+}
+
+/// Whether two listeners would bind the same address
+///
+/// Interfaces that are not IP addresses are bound to every interface.
+fn overlaps(a: &ListenerConfig, b: &ListenerConfig) -> bool {
+    let is_udp = |config: &ListenerConfig| *config.protocol() == Protocol::Http3;
+    let is_any = |config: &ListenerConfig| {
+        config
+            .interface()
+            .parse::<IpAddr>()
+            .map_or(true, |ip| ip.is_unspecified())
+    };
+
+    a.port() == b.port()
+        && is_udp(a) == is_udp(b)
+        && (a.interface() == b.interface() || is_any(a) || is_any(b))
+}
+
 // TODO: Move to utils, try make it more flexible
 pub fn static_response(
     status: http::StatusCode,
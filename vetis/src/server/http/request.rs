@@ -1,4 +1,43 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use http::header;
 use hyper_body_utils::HttpBody;
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "multipart")]
+use crate::server::http::body::Multipart;
+#[cfg(feature = "websocket")]
+use crate::server::http::{websocket, Response, WebSocket};
+use crate::{
+    config::server::virtual_host::{DEFAULT_BODY_TIMEOUT, DEFAULT_MAX_BODY_SIZE},
+    errors::{BodyError, VetisError},
+    server::{
+        http::{
+            body::{body_error, collect, has_media_type, is_json},
+            connection::{ConnectionInfo, TlsInfo},
+        },
+        virtual_host::router::PathParams,
+    },
+};
+
+static NO_PARAMS: PathParams = PathParams::new();
+
+/// Name of the virtual host serving the request
+#[derive(Clone)]
+struct VirtualHostName(Arc<str>);
+
+/// Uri of the path serving the request
+#[derive(Clone)]
+struct MatchedPath(Arc<str>);
+
+/// Maximum size of the request body
+#[derive(Clone, Copy)]
+struct MaxBodySize(usize);
+
+/// Longest time to receive the request body
+#[derive(Clone, Copy)]
+struct BodyTimeout(Duration);
 
 /// HTTP request wrapper supporting multiple protocols.
 ///
@@ -119,6 +158,410 @@ impl Request {
         }
     }
 
+    /// Returns the parameters captured from the request path.
+    ///
+    /// Parameters are captured by handler paths with a pattern such as
+    /// `/users/:id/posts/*rest`, other paths capture no parameter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Request;
+    ///
+    /// async fn handler(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+    ///     for (name, value) in request.params().iter() {
+    ///         println!("{} = {}", name, value);
+    ///     }
+    ///     Ok(/* response */)
+    /// }
+    /// ```
+    pub fn params(&self) -> &PathParams {
+        match &self.inner {
+            Some(req) => req
+                .extensions()
+                .get::<PathParams>()
+                .unwrap_or(&NO_PARAMS),
+            None => panic!("No request"),
+        }
+    }
+
+    /// Returns a parameter captured from the request path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Request;
+    ///
+    /// // Handler registered for "/users/:id"
+    /// async fn handler(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+    ///     let id = request.param("id").unwrap_or_default();
+    ///     Ok(/* response */)
+    /// }
+    /// ```
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params()
+            .get(name)
+    }
+
+    /// Returns the HTTP version.
+    pub fn version(&self) -> http::Version {
+        match &self.inner {
+            Some(req) => req.version(),
+            None => panic!("No request"),
+        }
+    }
+
+    /// Returns the connection the request was received on.
+    ///
+    /// Requests built outside of a listener, such as in tests, carry no connection.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Request;
+    ///
+    /// async fn handler(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+    ///     if let Some(tls) = request.connection().and_then(|connection| connection.tls()) {
+    ///         let sni = tls.server_name();
+    ///     }
+    ///     Ok(/* response */)
+    /// }
+    /// ```
+    pub fn connection(&self) -> Option<&ConnectionInfo> {
+        match &self.inner {
+            Some(req) => req
+                .extensions()
+                .get::<Arc<ConnectionInfo>>()
+                .map(Arc::as_ref),
+            None => panic!("No request"),
+        }
+    }
+
+    /// Returns the address of the client.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Request;
+    ///
+    /// async fn handler(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+    ///     if let Some(addr) = request.client_addr() {
+    ///         log::info!("Request from {}", addr.ip());
+    ///     }
+    ///     Ok(/* response */)
+    /// }
+    /// ```
+    pub fn client_addr(&self) -> Option<SocketAddr> {
+        self.connection()
+            .map(ConnectionInfo::client_addr)
+    }
+
+    /// Returns the local address the request was received on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.connection()
+            .map(ConnectionInfo::local_addr)
+    }
+
+    /// Returns whether the request was received over TLS.
+    pub fn is_tls(&self) -> bool {
+        self.connection()
+            .is_some_and(ConnectionInfo::is_tls)
+    }
+
+    /// Returns the TLS session details of the connection.
+    pub fn tls(&self) -> Option<&TlsInfo> {
+        self.connection()
+            .and_then(ConnectionInfo::tls)
+    }
+
+    /// Returns the hostname of the virtual host serving the request.
+    pub fn virtual_host(&self) -> Option<&str> {
+        match &self.inner {
+            Some(req) => req
+                .extensions()
+                .get::<VirtualHostName>()
+                .map(|name| name.0.as_ref()),
+            None => panic!("No request"),
+        }
+    }
+
+    /// Returns the uri of the path serving the request, such as `/api` or `/users/:id`.
+    pub fn matched_path(&self) -> Option<&str> {
+        match &self.inner {
+            Some(req) => req
+                .extensions()
+                .get::<MatchedPath>()
+                .map(|path| path.0.as_ref()),
+            None => panic!("No request"),
+        }
+    }
+
+    /// Returns the maximum size of the body read by the body helpers.
+    ///
+    /// Defaults to the `max_body_size` of the virtual host serving the request.
+    pub fn max_body_size(&self) -> usize {
+        match &self.inner {
+            Some(req) => req
+                .extensions()
+                .get::<MaxBodySize>()
+                .map_or(DEFAULT_MAX_BODY_SIZE, |limit| limit.0),
+            None => panic!("No request"),
+        }
+    }
+
+    /// Sets the maximum size of the body read by the body helpers.
+    ///
+    /// Middleware can use it to allow larger bodies on some paths only.
+    pub fn set_max_body_size(&mut self, max_body_size: usize) {
+        match &mut self.inner {
+            Some(req) => {
+                req.extensions_mut()
+                    .insert(MaxBodySize(max_body_size));
+            }
+            None => panic!("No request"),
+        }
+    }
+
+    /// Returns the longest time to receive the body read by the body helpers.
+    ///
+    /// Defaults to the `body_timeout` of the virtual host serving the request.
+    pub fn body_timeout(&self) -> Duration {
+        match &self.inner {
+            Some(req) => req
+                .extensions()
+                .get::<BodyTimeout>()
+                .map_or(DEFAULT_BODY_TIMEOUT, |timeout| timeout.0),
+            None => panic!("No request"),
+        }
+    }
+
+    /// Sets the longest time to receive the body read by the body helpers.
+    ///
+    /// Middleware can use it to allow slower uploads on some paths only.
+    pub fn set_body_timeout(&mut self, body_timeout: Duration) {
+        match &mut self.inner {
+            Some(req) => {
+                req.extensions_mut()
+                    .insert(BodyTimeout(body_timeout));
+            }
+            None => panic!("No request"),
+        }
+    }
+
+    /// Reads the whole request body.
+    ///
+    /// Bodies larger than `max_body_size` are rejected with a `BodyError::TooLarge`
+    /// error, answered with `413 Payload Too Large`. Bodies not received within
+    /// `body_timeout` are rejected with a `BodyError::Timeout` error, answered with
+    /// `408 Request Timeout`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Request;
+    ///
+    /// async fn handler(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+    ///     let body = request.bytes().await?;
+    ///     Ok(/* response */)
+    /// }
+    /// ```
+    pub async fn bytes(self) -> Result<Bytes, VetisError> {
+        let limit = self.max_body_size();
+        let timeout = self.body_timeout();
+        let content_length = self
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .parse::<usize>()
+                    .ok()
+            });
+        if content_length.is_some_and(|length| length > limit) {
+            return Err(body_error(BodyError::TooLarge(limit)));
+        }
+
+        let (_, body) = self.into_parts();
+        collect(body, limit, timeout).await
+    }
+
+    /// Reads the whole request body as UTF-8 text.
+    pub async fn text(self) -> Result<String, VetisError> {
+        let bytes = self.bytes().await?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|e| body_error(BodyError::Invalid(e.to_string())))
+    }
+
+    /// Decodes a JSON request body.
+    ///
+    /// The request must have an `application/json` or `+json` content type, other
+    /// requests are answered with `415 Unsupported Media Type`, and invalid documents
+    /// with `400 Bad Request`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Request;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct NewUser {
+    ///     name: String,
+    /// }
+    ///
+    /// async fn handler(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+    ///     let user: NewUser = request.json().await?;
+    ///     Ok(/* response */)
+    /// }
+    /// ```
+    pub async fn json<T: DeserializeOwned>(self) -> Result<T, VetisError> {
+        self.expect_content_type(is_json)?;
+
+        let bytes = self.bytes().await?;
+
+        serde_json::from_slice(&bytes).map_err(|e| body_error(BodyError::Invalid(e.to_string())))
+    }
+
+    /// Decodes an `application/x-www-form-urlencoded` request body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Request;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Login {
+    ///     username: String,
+    ///     password: String,
+    /// }
+    ///
+    /// async fn handler(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+    ///     let login: Login = request.form().await?;
+    ///     Ok(/* response */)
+    /// }
+    /// ```
+    pub async fn form<T: DeserializeOwned>(self) -> Result<T, VetisError> {
+        self.expect_content_type(|content_type| {
+            has_media_type(content_type, "application/x-www-form-urlencoded")
+        })?;
+
+        let bytes = self.bytes().await?;
+
+        serde_urlencoded::from_bytes(&bytes)
+            .map_err(|e| body_error(BodyError::Invalid(e.to_string())))
+    }
+
+    /// Streams a `multipart/form-data` request body.
+    ///
+    /// The whole body is limited to `max_body_size`.
+    #[cfg(feature = "multipart")]
+    pub fn multipart(self) -> Result<Multipart, VetisError> {
+        let content_type = self.expect_content_type(|content_type| {
+            has_media_type(content_type, "multipart/form-data")
+        })?;
+        let boundary = multer::parse_boundary(content_type)
+            .map_err(|e| body_error(BodyError::Invalid(e.to_string())))?;
+
+        let limit = self.max_body_size();
+        let (_, body) = self.into_parts();
+
+        Ok(Multipart::new(body, boundary, limit))
+    }
+
+    /// Accepts a WebSocket handshake, running `on_connect` once the connection is upgraded.
+    ///
+    /// Both HTTP/1.1 upgrades and HTTP/2 extended CONNECT requests (RFC 8441) are
+    /// accepted. The returned response must be sent for the upgrade to happen.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::server::http::Message;
+    ///
+    /// async fn chat(mut request: Request) -> Result<vetis::Response, vetis::VetisError> {
+    ///     request.upgrade_websocket(|mut socket| async move {
+    ///         while let Ok(Some(Message::Text(text))) = socket.recv().await {
+    ///             let _ = socket.send(Message::Text(text)).await;
+    ///         }
+    ///     })
+    /// }
+    /// ```
+    #[cfg(feature = "websocket")]
+    pub fn upgrade_websocket<F, Fut>(&mut self, on_connect: F) -> Result<Response, VetisError>
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let (response, on_upgrade) = websocket::accept(self)?;
+        websocket::spawn(on_upgrade, on_connect);
+
+        Ok(response)
+    }
+
+    /// Returns the id of the last Server-Sent Event received by a reconnecting client.
+    ///
+    /// It is read from the `Last-Event-ID` header, to resume an event stream where
+    /// the client left off.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.headers()
+            .get("last-event-id")
+            .and_then(|value| value.to_str().ok())
+    }
+
+    /// Returns the content type, if accepted by `accept`.
+    fn expect_content_type(&self, accept: impl Fn(&str) -> bool) -> Result<&str, VetisError> {
+        let content_type = self
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        if accept(content_type) {
+            Ok(content_type)
+        } else {
+            Err(body_error(BodyError::ContentType(content_type.to_string())))
+        }
+    }
+
+    pub(crate) fn set_connection(&mut self, connection: Arc<ConnectionInfo>) {
+        match &mut self.inner {
+            Some(req) => {
+                req.extensions_mut()
+                    .insert(connection);
+            }
+            None => panic!("No request"),
+        }
+    }
+
+    pub(crate) fn set_virtual_host(&mut self, name: &str) {
+        match &mut self.inner {
+            Some(req) => {
+                req.extensions_mut()
+                    .insert(VirtualHostName(Arc::from(name)));
+            }
+            None => panic!("No request"),
+        }
+    }
+
+    pub(crate) fn set_matched_path(&mut self, path: &str) {
+        match &mut self.inner {
+            Some(req) => {
+                req.extensions_mut()
+                    .insert(MatchedPath(Arc::from(path)));
+            }
+            None => panic!("No request"),
+        }
+    }
+
+    pub(crate) fn set_params(&mut self, params: PathParams) {
+        match &mut self.inner {
+            Some(req) => {
+                req.extensions_mut()
+                    .insert(params);
+            }
+            None => panic!("No request"),
+        }
+    }
+
     pub fn into_parts(self) -> (http::request::Parts, HttpBody) {
         match self.inner {
             Some(req) => {
@@ -1,4 +1,22 @@
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
+use http::{header, HeaderValue};
+use http_body_util::{BodyExt, StreamBody};
+use hyper::body::Frame;
 use hyper_body_utils::HttpBody;
+use log::error;
+use serde::Serialize;
+
+#[cfg(feature = "static-files")]
+use crate::{errors::FileError, server::virtual_host::path::static_files::file_headers, VetisFile};
+use crate::{
+    errors::{HandlerError, VetisError, VirtualHostError},
+    server::http::{
+        body::SyncBody,
+        cookie::Cookie,
+        sse::{Event, EventStream, DEFAULT_KEEP_ALIVE},
+    },
+};
 
 /// Builder for creating HTTP responses.
 ///
@@ -118,8 +136,57 @@ impl ResponseBuilder {
         self
     }
 
+    /// Adds a `Set-Cookie` header to the response.
+    ///
+    /// Cookies with an invalid name or value are logged and skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::server::http::{Cookie, Response};
+    ///
+    /// let response = Response::builder()
+    ///     .cookie(Cookie::new("theme", "dark").path("/"))
+    ///     .cookie(Cookie::removal("session"))
+    ///     .text("Preferences saved");
+    /// ```
+    pub fn cookie(self, cookie: Cookie) -> Self {
+        if !cookie.is_valid() {
+            error!("Invalid cookie: {}", cookie.name());
+            return self;
+        }
+
+        match HeaderValue::from_str(&cookie.to_string()) {
+            Ok(value) => self.header(header::SET_COOKIE, value),
+            Err(_) => {
+                error!("Invalid cookie: {}", cookie.name());
+                self
+            }
+        }
+    }
+
+    /// Sets a header unless it was already set.
+    fn default_header(mut self, key: header::HeaderName, value: HeaderValue) -> Self {
+        let headers = self
+            .headers
+            .get_or_insert_with(http::HeaderMap::new);
+        if !headers.contains_key(&key) {
+            headers.insert(key, value);
+        }
+        self
+    }
+
+    /// Sets a body of known content, with its `Content-Type` and `Content-Length`.
+    fn sized(self, content_type: &'static str, content: &[u8]) -> Response {
+        self.default_header(header::CONTENT_TYPE, HeaderValue::from_static(content_type))
+            .default_header(header::CONTENT_LENGTH, HeaderValue::from(content.len()))
+            .body(HttpBody::from_bytes(content))
+    }
+
     /// Sets the body from a text string and creates the final `Response`.
     ///
+    /// Sets `Content-Type` to `text/plain; charset=utf-8` unless already set.
+    ///
     /// # Arguments
     ///
     /// * `text` - The response body as a text slice
@@ -133,11 +200,203 @@ impl ResponseBuilder {
     ///     .text("Hello, World!");
     /// ```    
     pub fn text(self, text: &str) -> Response {
-        self.body(HttpBody::from_text(text))
+        self.sized("text/plain; charset=utf-8", text.as_bytes())
+    }
+
+    /// Sets an HTML body and creates the final `Response`.
+    ///
+    /// Sets `Content-Type` to `text/html; charset=utf-8` unless already set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Response;
+    ///
+    /// let response = Response::builder()
+    ///     .html("<h1>Hello, World!</h1>");
+    /// ```
+    pub fn html(self, html: &str) -> Response {
+        self.sized("text/html; charset=utf-8", html.as_bytes())
+    }
+
+    /// Serializes a value as the JSON body and creates the final `Response`.
+    ///
+    /// Sets `Content-Type` to `application/json` unless already set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Response;
+    ///
+    /// #[derive(serde::Serialize)]
+    /// struct User {
+    ///     id: u64,
+    /// }
+    ///
+    /// let response = Response::builder()
+    ///     .status(http::StatusCode::CREATED)
+    ///     .json(&User { id: 1 })?;
+    /// ```
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Result<Response, VetisError> {
+        let json = serde_json::to_vec(value).map_err(|e| {
+            VetisError::VirtualHost(VirtualHostError::Handler(HandlerError::Handler(e.to_string())))
+        })?;
+
+        Ok(self.sized("application/json", &json))
+    }
+
+    /// Creates a redirection to another location.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The redirection status, such as `303 See Other` or `308 Permanent Redirect`
+    /// * `location` - The target uri
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Response;
+    ///
+    /// let response = Response::builder()
+    ///     .redirect(http::StatusCode::SEE_OTHER, "/login")?;
+    /// ```
+    pub fn redirect(
+        self,
+        status: http::StatusCode,
+        location: &str,
+    ) -> Result<Response, VetisError> {
+        let location = HeaderValue::from_str(location).map_err(|_| {
+            VetisError::VirtualHost(VirtualHostError::Handler(HandlerError::Uri(format!(
+                "Invalid redirect location: {}",
+                location
+            ))))
+        })?;
+
+        Ok(self
+            .status(status)
+            .header(header::LOCATION, location)
+            .default_header(header::CONTENT_LENGTH, HeaderValue::from(0))
+            .body(HttpBody::from_bytes(&[])))
+    }
+
+    /// Streams the body from chunks and creates the final `Response`.
+    ///
+    /// The response has no `Content-Length`, it is sent chunked over HTTP/1.1.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use bytes::Bytes;
+    /// use vetis::Response;
+    ///
+    /// let chunks = futures_util::stream::iter(vec![Bytes::from("Hello, "), Bytes::from("World!")]);
+    /// let response = Response::builder()
+    ///     .stream(chunks);
+    /// ```
+    pub fn stream<S>(self, stream: S) -> Response
+    where
+        S: Stream<Item = Bytes> + Send + 'static,
+    {
+        let body = StreamBody::new(stream.map(|bytes| Ok::<_, std::io::Error>(Frame::data(bytes))));
+
+        self.body(HttpBody::Stream(SyncBody::new(body).boxed()))
+    }
+
+    /// Streams Server-Sent Events and creates the final `Response`.
+    ///
+    /// Sets `Content-Type` to `text/event-stream` unless already set, disables caching
+    /// and proxy buffering, and sends a keep-alive comment when no event was sent for
+    /// `DEFAULT_KEEP_ALIVE`. Reconnecting clients send the id of the last event they
+    /// received, available with `Request::last_event_id`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::server::http::{Event, Response};
+    ///
+    /// let start = request.last_event_id().and_then(|id| id.parse().ok()).unwrap_or(0);
+    /// let events = futures_util::stream::iter(start..)
+    ///     .map(|id: u64| Event::new().id(&id.to_string()).data("tick"));
+    /// let response = Response::builder()
+    ///     .sse(events);
+    /// ```
+    pub fn sse<S>(self, events: S) -> Response
+    where
+        S: Stream<Item = Event> + Send + 'static,
+    {
+        self.sse_with_keep_alive(events, Some(DEFAULT_KEEP_ALIVE))
+    }
+
+    /// Streams Server-Sent Events like `sse`, with a custom keep-alive interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The events to send
+    /// * `keep_alive` - How long the stream can stay idle before a keep-alive comment is sent, `None` to never send them
+    pub fn sse_with_keep_alive<S>(
+        self,
+        events: S,
+        keep_alive: Option<std::time::Duration>,
+    ) -> Response
+    where
+        S: Stream<Item = Event> + Send + 'static,
+    {
+        self.default_header(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"))
+            .default_header(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"))
+            .default_header(
+                header::HeaderName::from_static("x-accel-buffering"),
+                HeaderValue::from_static("no"),
+            )
+            .stream(EventStream::new(events, keep_alive))
+    }
+
+    /// Sets a file as the body and creates the final `Response`.
+    ///
+    /// `Content-Type`, `Content-Length` and `Last-Modified` are set from the file unless
+    /// already set. Missing files are answered with the `404` status page.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Response;
+    ///
+    /// let response = Response::builder()
+    ///     .file("/var/www/reports/latest.pdf")
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "static-files")]
+    pub async fn file(mut self, path: impl AsRef<std::path::Path>) -> Result<Response, VetisError> {
+        let path = path.as_ref();
+        let file = VetisFile::open(path)
+            .await
+            .map_err(|e| {
+                error!("Error opening file {}: {}", path.display(), e);
+                VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound))
+            })?;
+
+        let metadata = file
+            .metadata()
+            .await
+            .map_err(|_| {
+                VetisError::VirtualHost(VirtualHostError::File(FileError::InvalidMetadata))
+            })?;
+        if metadata.is_dir() {
+            return Err(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)));
+        }
+
+        for (key, value) in file_headers(path, &metadata)? {
+            if let Some(key) = key {
+                self = self.default_header(key, value);
+            }
+        }
+
+        Ok(self.body(HttpBody::from_file(file)))
     }
 
     /// Sets the body with bytes and creates the final `Response`.
     ///
+    /// Sets `Content-Type` to `application/octet-stream` unless already set.
+    ///
     /// # Arguments
     ///
     /// * `bytes` - The response body as a `Bytes`
@@ -151,7 +410,7 @@ impl ResponseBuilder {
     ///     .bytes(b"Hello, World!");
     /// ```
     pub fn bytes(self, bytes: &[u8]) -> Response {
-        self.body(HttpBody::from_bytes(bytes))
+        self.sized("application/octet-stream", bytes)
     }
 
     /// Sets the body and creates the final `Response`.
@@ -233,6 +492,74 @@ impl Response {
         }
     }
 
+    /// Returns the response status code.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Response;
+    ///
+    /// let response = Response::builder()
+    ///     .status(http::StatusCode::CREATED)
+    ///     .text("Created");
+    /// assert_eq!(response.status(), http::StatusCode::CREATED);
+    /// ```
+    pub fn status(&self) -> http::StatusCode {
+        self.inner.status()
+    }
+
+    /// Returns the response status code (mutable).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Response;
+    ///
+    /// let mut response = Response::builder()
+    ///     .text("Gone");
+    /// *response.status_mut() = http::StatusCode::GONE;
+    /// ```
+    pub fn status_mut(&mut self) -> &mut http::StatusCode {
+        self.inner
+            .status_mut()
+    }
+
+    /// Returns the response headers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Response;
+    ///
+    /// let response = Response::builder()
+    ///     .header("content-type", "text/plain".parse().unwrap())
+    ///     .text("Hello");
+    /// let content_type = response.headers().get("content-type");
+    /// ```
+    pub fn headers(&self) -> &http::HeaderMap {
+        self.inner.headers()
+    }
+
+    /// Returns the response headers (mutable).
+    ///
+    /// Middleware use it to add headers to responses produced by handlers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::Response;
+    ///
+    /// let mut response = Response::builder()
+    ///     .text("Hello");
+    /// response
+    ///     .headers_mut()
+    ///     .insert("x-powered-by", http::HeaderValue::from_static("vetis"));
+    /// ```
+    pub fn headers_mut(&mut self) -> &mut http::HeaderMap {
+        self.inner
+            .headers_mut()
+    }
+
     /// Converts the response into the underlying `http::Response`.
     ///
     /// This is useful when you need to work with the standard library HTTP types
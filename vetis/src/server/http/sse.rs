@@ -0,0 +1,187 @@
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_util::Stream;
+use serde::Serialize;
+
+use crate::{
+    errors::{HandlerError, VetisError, VirtualHostError},
+    rt::sleep,
+};
+
+/// Interval of the keep-alive comments sent by `ResponseBuilder::sse`
+pub const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Event sent to the client of a `text/event-stream` response.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use std::time::Duration;
+/// use vetis::server::http::Event;
+///
+/// let event = Event::new()
+///     .event("price")
+///     .id("42")
+///     .data("{\"symbol\":\"VTS\",\"price\":12.5}")
+///     .retry(Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    comment: Option<String>,
+    event: Option<String>,
+    id: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// Creates an empty event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the event data, sent as one `data` field per line.
+    pub fn data(mut self, data: &str) -> Self {
+        self.data = Some(data.to_string());
+        self
+    }
+
+    /// Serializes a value as the event data.
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Result<Self, VetisError> {
+        let json = serde_json::to_string(value).map_err(|e| {
+            VetisError::VirtualHost(VirtualHostError::Handler(HandlerError::Handler(e.to_string())))
+        })?;
+
+        Ok(self.data(&json))
+    }
+
+    /// Sets the event type, dispatched to the matching `addEventListener` on the client.
+    pub fn event(mut self, event: &str) -> Self {
+        self.event = Some(single_line(event));
+        self
+    }
+
+    /// Sets the event id, sent back by reconnecting clients as `Last-Event-ID`.
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = Some(single_line(id).replace('\0', ""));
+        self
+    }
+
+    /// Sets how long clients wait before reconnecting, in whole milliseconds.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Sets a comment, ignored by clients.
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_string());
+        self
+    }
+}
+
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], "")
+}
+
+/// Split a value on the line terminators of the event stream format
+fn lines(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split('\n')
+        .map(|line| {
+            line.strip_suffix('\r')
+                .unwrap_or(line)
+        })
+        .flat_map(|line| line.split('\r'))
+}
+
+impl fmt::Display for Event {
+    /// Formats the event in the `text/event-stream` format, ending with a blank line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(comment) = &self.comment {
+            for line in lines(comment) {
+                writeln!(f, ": {}", line)?;
+            }
+        }
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {}", event)?;
+        }
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {}", id)?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {}", retry.as_millis())?;
+        }
+        if let Some(data) = &self.data {
+            for line in lines(data) {
+                writeln!(f, "data: {}", line)?;
+            }
+        }
+        writeln!(f)
+    }
+}
+
+/// Stream of formatted events, with a keep-alive comment once idle for the interval
+pub(crate) struct EventStream<S> {
+    events: Pin<Box<S>>,
+    keep_alive: Option<Duration>,
+    timer: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl<S> EventStream<S> {
+    pub(crate) fn new(events: S, keep_alive: Option<Duration>) -> Self {
+        let mut stream = Self { events: Box::pin(events), keep_alive, timer: None };
+        stream.reset_timer();
+        stream
+    }
+
+    fn reset_timer(&mut self) {
+        self.timer = self
+            .keep_alive
+            .map(|interval| Box::pin(sleep(interval)) as Pin<Box<dyn Future<Output = ()> + Send>>);
+    }
+}
+
+impl<S: Stream<Item = Event>> Stream for EventStream<S> {
+    type Item = Bytes;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match this
+            .events
+            .as_mut()
+            .poll_next(cx)
+        {
+            Poll::Ready(Some(event)) => {
+                this.reset_timer();
+                return Poll::Ready(Some(Bytes::from(event.to_string())));
+            }
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => {}
+        }
+
+        let elapsed = this
+            .timer
+            .as_mut()
+            .is_some_and(|timer| {
+                timer
+                    .as_mut()
+                    .poll(cx)
+                    .is_ready()
+            });
+        if elapsed {
+            this.reset_timer();
+            return Poll::Ready(Some(Bytes::from_static(b":\n\n")));
+        }
+
+        Poll::Pending
+    }
+}
@@ -0,0 +1,336 @@
+//! WebSocket connections accepted from HTTP/1.1 upgrades and HTTP/2 extended CONNECT
+
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use async_tungstenite::{
+    tungstenite::{
+        self,
+        handshake::derive_accept_key,
+        protocol::{frame::coding::CloseCode, Role},
+    },
+    WebSocketStream,
+};
+use bytes::Bytes;
+use futures_util::{
+    io::{AsyncRead, AsyncWrite},
+    SinkExt, StreamExt,
+};
+use http::{header, HeaderValue, Method, StatusCode, Version};
+use hyper::upgrade::{OnUpgrade, Upgraded};
+use hyper_body_utils::HttpBody;
+use log::error;
+use rt_gate::spawn_worker;
+
+use crate::{
+    errors::{VetisError, VirtualHostError},
+    server::http::{Request, Response},
+};
+
+fn websocket_error(message: impl ToString) -> VetisError {
+    VetisError::VirtualHost(VirtualHostError::WebSocket(message.to_string()))
+}
+
+/// Message sent or received on a WebSocket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// UTF-8 text message
+    Text(String),
+    /// Binary message
+    Binary(Bytes),
+    /// Ping, answered automatically with a pong carrying the same payload
+    Ping(Bytes),
+    /// Pong, received in answer to a ping
+    Pong(Bytes),
+    /// Close handshake, answered automatically when received
+    Close(Option<CloseFrame>),
+}
+
+/// Status code and reason of a close handshake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    /// Close code, `1000` for a normal closure
+    pub code: u16,
+    /// Close reason
+    pub reason: String,
+}
+
+impl From<tungstenite::protocol::CloseFrame> for CloseFrame {
+    fn from(frame: tungstenite::protocol::CloseFrame) -> Self {
+        Self {
+            code: frame.code.into(),
+            reason: frame
+                .reason
+                .to_string(),
+        }
+    }
+}
+
+impl From<CloseFrame> for tungstenite::protocol::CloseFrame {
+    fn from(frame: CloseFrame) -> Self {
+        Self { code: CloseCode::from(frame.code), reason: frame.reason.into() }
+    }
+}
+
+impl From<Message> for tungstenite::Message {
+    fn from(message: Message) -> Self {
+        match message {
+            Message::Text(text) => tungstenite::Message::Text(text.into()),
+            Message::Binary(bytes) => tungstenite::Message::Binary(bytes),
+            Message::Ping(bytes) => tungstenite::Message::Ping(bytes),
+            Message::Pong(bytes) => tungstenite::Message::Pong(bytes),
+            Message::Close(frame) => tungstenite::Message::Close(frame.map(Into::into)),
+        }
+    }
+}
+
+/// WebSocket connection accepted from a request.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::server::http::{Message, WebSocket};
+///
+/// async fn echo(mut socket: WebSocket) {
+///     while let Ok(Some(message)) = socket.recv().await {
+///         if let Message::Text(text) = message {
+///             if socket.send(Message::Text(text)).await.is_err() {
+///                 break;
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub struct WebSocket {
+    inner: WebSocketStream<UpgradedIo>,
+}
+
+impl WebSocket {
+    async fn from_upgraded(upgraded: Upgraded) -> Self {
+        Self {
+            inner: WebSocketStream::from_raw_socket(UpgradedIo(upgraded), Role::Server, None).await,
+        }
+    }
+
+    /// Returns the next message, `None` once the connection is closed.
+    ///
+    /// Pings are answered and close handshakes are completed while receiving, they
+    /// are still returned to let handlers know about them.
+    pub async fn recv(&mut self) -> Result<Option<Message>, VetisError> {
+        loop {
+            let message = match self
+                .inner
+                .next()
+                .await
+            {
+                Some(Ok(message)) => message,
+                Some(Err(
+                    tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed,
+                ))
+                | None => return Ok(None),
+                Some(Err(e)) => return Err(websocket_error(e)),
+            };
+
+            let message = match message {
+                tungstenite::Message::Text(text) => Message::Text(text.to_string()),
+                tungstenite::Message::Binary(bytes) => Message::Binary(bytes),
+                tungstenite::Message::Ping(bytes) => {
+                    // Send the queued pong before the handler writes anything else
+                    self.inner
+                        .flush()
+                        .await
+                        .map_err(websocket_error)?;
+                    Message::Ping(bytes)
+                }
+                tungstenite::Message::Pong(bytes) => Message::Pong(bytes),
+                tungstenite::Message::Close(frame) => Message::Close(frame.map(Into::into)),
+                // Raw frames are only used to send messages
+                tungstenite::Message::Frame(_) => continue,
+            };
+
+            return Ok(Some(message));
+        }
+    }
+
+    /// Sends a message.
+    pub async fn send(&mut self, message: Message) -> Result<(), VetisError> {
+        self.inner
+            .send(message.into())
+            .await
+            .map_err(websocket_error)
+    }
+
+    /// Sends a ping, the client answers with a pong carrying the same payload.
+    pub async fn ping(&mut self, payload: Bytes) -> Result<(), VetisError> {
+        self.send(Message::Ping(payload))
+            .await
+    }
+
+    /// Starts the close handshake, `recv` returns `None` once the client answered.
+    pub async fn close(&mut self, frame: Option<CloseFrame>) -> Result<(), VetisError> {
+        match self
+            .inner
+            .close(frame.map(Into::into))
+            .await
+        {
+            Ok(()) | Err(tungstenite::Error::ConnectionClosed) => Ok(()),
+            Err(e) => Err(websocket_error(e)),
+        }
+    }
+}
+
+/// Validate a WebSocket handshake and build the response accepting it.
+///
+/// HTTP/1.1 requests are upgraded with `101 Switching Protocols`, HTTP/2 extended
+/// CONNECT requests (RFC 8441) are accepted with `200 OK`.
+///
+/// # Returns
+///
+/// * `Result<(Response, OnUpgrade), VetisError>` - The response to send and the upgraded connection.
+pub(crate) fn accept(request: &mut Request) -> Result<(Response, OnUpgrade), VetisError> {
+    let headers = request.headers();
+
+    let version = headers
+        .get(header::SEC_WEBSOCKET_VERSION)
+        .and_then(|value| value.to_str().ok());
+    if version.is_some_and(|version| version.trim() != "13") {
+        return Err(websocket_error("unsupported version"));
+    }
+
+    let response = if request.version() == Version::HTTP_2 {
+        accept_extended_connect(request)?
+    } else {
+        if request.method() != Method::GET {
+            return Err(websocket_error("upgrade requires a GET request"));
+        }
+
+        let has_token = |name: header::HeaderName, token: &str| {
+            headers
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .any(|value| {
+                    value
+                        .trim()
+                        .eq_ignore_ascii_case(token)
+                })
+        };
+
+        if !has_token(header::CONNECTION, "upgrade") || !has_token(header::UPGRADE, "websocket") {
+            return Err(websocket_error("not a websocket upgrade"));
+        }
+
+        let Some(key) = headers.get(header::SEC_WEBSOCKET_KEY) else {
+            return Err(websocket_error("missing Sec-WebSocket-Key"));
+        };
+        if version.is_none() {
+            return Err(websocket_error("missing Sec-WebSocket-Version"));
+        }
+
+        let accept_key =
+            HeaderValue::from_str(&derive_accept_key(key.as_bytes())).map_err(websocket_error)?;
+
+        Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(header::CONNECTION, HeaderValue::from_static("upgrade"))
+            .header(header::UPGRADE, HeaderValue::from_static("websocket"))
+            .header(header::SEC_WEBSOCKET_ACCEPT, accept_key)
+            .body(HttpBody::from_bytes(&[]))
+    };
+
+    let on_upgrade = request
+        .inner
+        .as_mut()
+        .and_then(|request| {
+            request
+                .extensions_mut()
+                .remove::<OnUpgrade>()
+        })
+        .ok_or_else(|| websocket_error("connection cannot be upgraded"))?;
+
+    Ok((response, on_upgrade))
+}
+
+#[cfg(feature = "http2")]
+fn accept_extended_connect(request: &Request) -> Result<Response, VetisError> {
+    let is_websocket = request
+        .inner
+        .as_ref()
+        .and_then(|request| {
+            request
+                .extensions()
+                .get::<hyper::ext::Protocol>()
+        })
+        .is_some_and(|protocol| {
+            protocol
+                .as_str()
+                .eq_ignore_ascii_case("websocket")
+        });
+
+    if request.method() != Method::CONNECT || !is_websocket {
+        return Err(websocket_error("not a websocket extended CONNECT"));
+    }
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(HttpBody::from_bytes(&[])))
+}
+
+#[cfg(not(feature = "http2"))]
+fn accept_extended_connect(_request: &Request) -> Result<Response, VetisError> {
+    Err(websocket_error("HTTP/2 support is disabled"))
+}
+
+/// Run `on_connect` with the WebSocket once the connection is upgraded.
+pub(crate) fn spawn<F, Fut>(on_upgrade: OnUpgrade, on_connect: F)
+where
+    F: FnOnce(WebSocket) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    spawn_worker(async move {
+        match on_upgrade.await {
+            Ok(upgraded) => on_connect(WebSocket::from_upgraded(upgraded).await).await,
+            Err(e) => error!("WebSocket upgrade failed: {}", e),
+        }
+    });
+}
+
+/// Upgraded connection exposed with the futures io traits used by tungstenite
+struct UpgradedIo(Upgraded);
+
+impl AsyncRead for UpgradedIo {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut read_buf = hyper::rt::ReadBuf::new(buf);
+        ready!(hyper::rt::Read::poll_read(Pin::new(&mut self.0), cx, read_buf.unfilled()))?;
+        Poll::Ready(Ok(read_buf
+            .filled()
+            .len()))
+    }
+}
+
+impl AsyncWrite for UpgradedIo {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        hyper::rt::Write::poll_write(Pin::new(&mut self.0), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        hyper::rt::Write::poll_flush(Pin::new(&mut self.0), cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        hyper::rt::Write::poll_shutdown(Pin::new(&mut self.0), cx)
+    }
+}
@@ -33,9 +33,11 @@
 //! }));
 //! ```
 
-use std::future::Future;
+use std::{future::Future, sync::Arc};
 
-use crate::{config::server::ServerConfig, errors::VetisError, VetisVirtualHosts};
+use crate::{
+    config::server::ServerConfig, errors::VetisError, server::tls::SniResolver, VetisVirtualHosts,
+};
 
 pub mod conn;
 pub mod http;
@@ -81,6 +83,16 @@ pub trait Server {
     /// * `virtual_hosts` - Arc containing the virtual host registry
     fn set_virtual_hosts(&mut self, virtual_hosts: VetisVirtualHosts);
 
+    /// Sets the certificate resolver shared by all listeners.
+    ///
+    /// The resolver is rebuilt whenever virtual hosts change, letting listeners
+    /// serve new certificates without a restart.
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_resolver` - Arc containing the SNI certificate resolver
+    fn set_cert_resolver(&mut self, cert_resolver: Arc<SniResolver>);
+
     /// Starts the server and begins accepting connections.
     ///
     /// # Errors
@@ -98,4 +110,15 @@ pub trait Server {
     ///
     /// Returns an error if the server fails to stop properly.
     fn stop(&mut self) -> impl Future<Output = Result<(), VetisError>>;
+
+    /// Applies a new configuration to the running server.
+    ///
+    /// New listeners are bound and listeners no longer configured are closed,
+    /// listeners present in both configurations keep serving connections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a new listener fails to start, in which case
+    /// the running listeners are left untouched.
+    fn reload(&mut self, config: ServerConfig) -> impl Future<Output = Result<(), VetisError>>;
 }
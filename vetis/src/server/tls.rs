@@ -1,36 +1,294 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock},
+};
 
 use crate::{
     errors::{StartError::Tls, VetisError},
+    server::virtual_host::{match_hostname, VirtualHost},
     VetisVirtualHosts,
 };
 
 use rustls::{
-    pki_types::{CertificateDer, PrivateKeyDer},
-    server::ResolvesServerCertUsingSni,
+    client::danger::HandshakeSignatureValid,
+    crypto::{
+        verify_tls12_signature, verify_tls13_signature, CryptoProvider, WebPkiSupportedAlgorithms,
+    },
+    pki_types::{CertificateDer, PrivateKeyDer, UnixTime},
+    server::{
+        danger::{ClientCertVerified, ClientCertVerifier},
+        ClientHello, ResolvesServerCert, WebPkiClientVerifier,
+    },
     sign::CertifiedKey,
-    ServerConfig,
+    DigitallySignedStruct, DistinguishedName, RootCertStore, ServerConfig, SignatureScheme,
 };
 
+/// Certificates of the virtual hosts, indexed by name and port.
+#[derive(Debug, Default)]
+struct Certificates {
+    names: HashMap<(String, u16), Arc<CertifiedKey>>,
+    wildcards: Vec<(String, u16, Arc<CertifiedKey>)>,
+    defaults: HashMap<u16, Arc<CertifiedKey>>,
+    /// Verifiers of the ports with virtual hosts requiring client authentication,
+    /// trusting the CA certificates of those virtual hosts.
+    client_verifiers: HashMap<u16, Arc<dyn ClientCertVerifier>>,
+}
+
+impl Certificates {
+    /// Certificate for a server name received on a port.
+    ///
+    /// Names on the port are tried first, then the default virtual host of the port,
+    /// and finally names on other ports, so virtual hosts without a certificate can
+    /// still be reached over TLS.
+    fn resolve(&self, port: u16, server_name: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        let server_name = server_name.map(str::to_ascii_lowercase);
+        let server_name = server_name.as_deref();
+
+        self.find(server_name, Some(port))
+            .or_else(|| {
+                self.defaults
+                    .get(&port)
+                    .cloned()
+            })
+            .or_else(|| self.find(server_name, None))
+    }
+
+    fn find(&self, server_name: Option<&str>, port: Option<u16>) -> Option<Arc<CertifiedKey>> {
+        let server_name = server_name?;
+        let on_port = |name_port: u16| port.map_or(true, |port| port == name_port);
+
+        let exact = self
+            .names
+            .iter()
+            .find(|((name, name_port), _)| name == server_name && on_port(*name_port));
+        if let Some((_, certified_key)) = exact {
+            return Some(certified_key.clone());
+        }
+
+        self.wildcards
+            .iter()
+            .filter(|(_, name_port, _)| on_port(*name_port))
+            .filter_map(|(name, _, certified_key)| {
+                match_hostname(name, server_name).map(|host_match| (host_match, certified_key))
+            })
+            .max_by_key(|(host_match, _)| *host_match)
+            .map(|(_, certified_key)| certified_key.clone())
+    }
+}
+
+/// Certificate resolver selecting the virtual host certificate by SNI.
+///
+/// A single resolver is shared by every listener of a server and can be rebuilt
+/// while the server is running, so virtual hosts added, removed or replaced at
+/// runtime take effect on the next TLS handshake.
+///
+/// Exact hostnames and aliases take priority over wildcards, the certificate of
+/// the default virtual host of the port is used when nothing else matches or the
+/// client does not send a server name.
+#[derive(Debug)]
+pub struct SniResolver {
+    certificates: RwLock<Arc<Certificates>>,
+}
+
+impl Default for SniResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SniResolver {
+    /// Create an empty resolver
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new `SniResolver` without certificates.
+    pub fn new() -> Self {
+        Self { certificates: RwLock::new(Arc::new(Certificates::default())) }
+    }
+
+    /// Rebuild the resolver from the given virtual hosts.
+    ///
+    /// The current certificates are kept if any of the virtual hosts has an invalid
+    /// security configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `virtual_hosts` - The virtual hosts to load certificates from.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), VetisError>` - An error if a certificate or key could not be loaded.
+    pub fn reload(
+        &self,
+        virtual_hosts: &HashMap<(Arc<str>, u16), Arc<VirtualHost>>,
+    ) -> Result<(), VetisError> {
+        let certificates = TlsFactory::load_certificates(virtual_hosts)?;
+
+        *self
+            .certificates
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Arc::new(certificates);
+
+        Ok(())
+    }
+
+    fn resolve(&self, port: u16, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let certificates = self
+            .certificates
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        certificates.resolve(port, client_hello.server_name())
+    }
+
+    fn client_verifier(&self, port: u16) -> Option<Arc<dyn ClientCertVerifier>> {
+        self.certificates
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .client_verifiers
+            .get(&port)
+            .cloned()
+    }
+}
+
+/// Resolves the certificates of the virtual hosts bound to a listener port.
+#[derive(Debug)]
+struct PortResolver {
+    resolver: Arc<SniResolver>,
+    port: u16,
+}
+
+impl ResolvesServerCert for PortResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.resolver
+            .resolve(self.port, client_hello)
+    }
+}
+
+/// Verifies the client certificates received on a listener port.
+///
+/// Certificates are only requested on ports with virtual hosts requiring client
+/// authentication, and are optional so other virtual hosts of the port stay
+/// reachable. Virtual hosts reject requests without a valid certificate themselves.
+#[derive(Debug)]
+struct PortClientVerifier {
+    resolver: Arc<SniResolver>,
+    port: u16,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ClientCertVerifier for PortClientVerifier {
+    fn offer_client_auth(&self) -> bool {
+        self.resolver
+            .client_verifier(self.port)
+            .is_some()
+    }
+
+    fn client_auth_mandatory(&self) -> bool {
+        false
+    }
+
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        match self
+            .resolver
+            .client_verifier(self.port)
+        {
+            Some(verifier) => verifier.verify_client_cert(end_entity, intermediates, now),
+            None => Err(rustls::Error::General("Client authentication is disabled".to_string())),
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms
+            .supported_schemes()
+    }
+}
+
 pub struct TlsFactory {}
 
 impl TlsFactory {
+    /// Create a TLS config with certificates loaded from the given virtual hosts.
+    ///
+    /// Certificates are loaded once, use `create_tls_config_with_resolver` to
+    /// follow virtual host changes.
     pub async fn create_tls_config(
         virtual_hosts: VetisVirtualHosts,
+        port: u16,
         alpn_protocols: Vec<Vec<u8>>,
     ) -> Result<Option<ServerConfig>, VetisError> {
-        let virtual_hosts = virtual_hosts.clone();
-        #[cfg(feature = "__rustls_awc_lc_rs")]
-        let provider = rustls::crypto::aws_lc_rs::default_provider();
-        #[cfg(feature = "__rustls_ring")]
-        let provider = rustls::crypto::ring::default_provider();
-        #[cfg(feature = "__rustls_rustcrypto")]
-        let provider = rustls_rustcrypto::provider();
-        let mut resolver = ResolvesServerCertUsingSni::new();
-        let virtual_hosts = virtual_hosts
-            .read()
-            .await;
-        for (hostname, virtual_host) in virtual_hosts.iter() {
+        let resolver = SniResolver::new();
+        resolver.reload(
+            &*virtual_hosts
+                .read()
+                .await,
+        )?;
+
+        Self::create_tls_config_with_resolver(Arc::new(resolver), port, alpn_protocols)
+    }
+
+    /// Create a TLS config using a shared certificate resolver.
+    ///
+    /// Only certificates of virtual hosts bound to `port` are offered to clients.
+    pub fn create_tls_config_with_resolver(
+        resolver: Arc<SniResolver>,
+        port: u16,
+        alpn_protocols: Vec<Vec<u8>>,
+    ) -> Result<Option<ServerConfig>, VetisError> {
+        let provider = Self::provider();
+        let client_verifier = PortClientVerifier {
+            resolver: resolver.clone(),
+            port,
+            algorithms: provider.signature_verification_algorithms,
+        };
+        let builder = rustls::ServerConfig::builder_with_provider(Arc::new(provider))
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(|e| VetisError::Start(Tls(e.to_string())))?;
+
+        let mut tls_config = builder
+            .with_client_cert_verifier(Arc::new(client_verifier))
+            .with_cert_resolver(Arc::new(PortResolver { resolver, port }));
+
+        tls_config.max_early_data_size = u32::MAX;
+        tls_config.alpn_protocols = alpn_protocols;
+
+        Ok(Some(tls_config))
+    }
+
+    fn load_certificates(
+        virtual_hosts: &HashMap<(Arc<str>, u16), Arc<VirtualHost>>,
+    ) -> Result<Certificates, VetisError> {
+        let provider = Self::provider();
+        let mut certificates = Certificates::default();
+        let mut client_cas: HashMap<u16, Vec<&[u8]>> = HashMap::new();
+        for virtual_host in virtual_hosts.values() {
             if let Some(security) = virtual_host
                 .config()
                 .security()
@@ -50,25 +308,83 @@ impl TlsFactory {
                 let certified_key = CertifiedKey::from_der(chain, key, &provider)
                     .map_err(|e| Tls(format!("Failed to create certified key: {}", e)))?;
 
-                let hostname = hostname.0.clone();
+                let port = virtual_host.port();
+                let certified_key = Arc::new(certified_key);
+                for name in virtual_host.names() {
+                    let name = name.to_ascii_lowercase();
+                    if name.starts_with("*.") {
+                        certificates
+                            .wildcards
+                            .push((name, port, certified_key.clone()));
+                    } else {
+                        certificates
+                            .names
+                            .insert((name, port), certified_key.clone());
+                    }
+                }
 
-                resolver
-                    .add(&hostname, certified_key)
-                    .map_err(|e| Tls(e.to_string()))?;
+                if virtual_host.is_default() {
+                    certificates
+                        .defaults
+                        .insert(port, certified_key);
+                }
+
+                if security.client_auth() {
+                    if let Some(ca_cert) = security.ca_cert() {
+                        client_cas
+                            .entry(port)
+                            .or_default()
+                            .push(ca_cert);
+                    }
+                }
             }
         }
 
-        let builder = rustls::ServerConfig::builder_with_provider(Arc::new(provider))
-            .with_protocol_versions(&[&rustls::version::TLS13])
-            .map_err(|e| VetisError::Start(Tls(e.to_string())))?;
+        for (port, ca_certs) in client_cas {
+            let client_verifier = Self::client_verifier(ca_certs)?;
+            certificates
+                .client_verifiers
+                .insert(port, client_verifier);
+        }
 
-        let mut tls_config = builder
-            .with_no_client_auth()
-            .with_cert_resolver(Arc::new(resolver));
+        Ok(certificates)
+    }
 
-        tls_config.max_early_data_size = u32::MAX;
-        tls_config.alpn_protocols = alpn_protocols;
+    /// Create a verifier of client certificates signed by the given CA certificates.
+    ///
+    /// Clients without a certificate are accepted, callers requiring one must check
+    /// that a certificate was presented.
+    ///
+    /// # Arguments
+    ///
+    /// * `ca_certs` - The trusted CA certificates, in DER format.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Arc<dyn ClientCertVerifier>, VetisError>` - An error if a CA certificate is invalid.
+    pub(crate) fn client_verifier<'a>(
+        ca_certs: impl IntoIterator<Item = &'a [u8]>,
+    ) -> Result<Arc<dyn ClientCertVerifier>, VetisError> {
+        let mut roots = RootCertStore::empty();
+        for ca_cert in ca_certs {
+            roots
+                .add(CertificateDer::from(ca_cert.to_vec()))
+                .map_err(|e| Tls(format!("Failed to add CA certificate: {}", e)))?;
+        }
 
-        Ok(Some(tls_config))
+        WebPkiClientVerifier::builder_with_provider(Arc::new(roots), Arc::new(Self::provider()))
+            .allow_unauthenticated()
+            .build()
+            .map_err(|e| VetisError::Start(Tls(format!("Failed to create client verifier: {}", e))))
+    }
+
+    fn provider() -> CryptoProvider {
+        #[cfg(feature = "__rustls_awc_lc_rs")]
+        let provider = rustls::crypto::aws_lc_rs::default_provider();
+        #[cfg(feature = "__rustls_ring")]
+        let provider = rustls::crypto::ring::default_provider();
+        #[cfg(feature = "__rustls_rustcrypto")]
+        let provider = rustls_rustcrypto::provider();
+        provider
     }
 }
@@ -0,0 +1,369 @@
+//! On-the-fly compression of responses
+//!
+//! Compression is configured on a `VirtualHostConfig`, which installs the `Compression`
+//! middleware around every request of the virtual host, and can be overridden for a single
+//! path by wrapping it with its own `Compression` middleware. Bodies are compressed as they
+//! are streamed, so large responses are never buffered.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use vetis::{
+//!     config::server::virtual_host::compression::CompressionConfig,
+//!     server::virtual_host::compression::Compression,
+//! };
+//!
+//! // Leave the downloads of a compressed virtual host as they are
+//! let path = HandlerPath::builder()
+//!     .uri("/downloads")
+//!     .handler(handler_fn(download))
+//!     .build()?
+//!     .middleware(Compression::new(CompressionConfig::builder().enabled(false).build()?));
+//! ```
+
+use std::io::Write;
+
+use bytes::Bytes;
+use futures_util::{stream, TryStreamExt};
+use http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use http_body_util::{BodyExt, StreamBody};
+use hyper::body::Frame;
+use hyper_body_utils::HttpBody;
+
+use crate::{
+    config::server::virtual_host::compression::{CompressionConfig, Encoding},
+    server::{
+        http::{
+            body::{encoding_quality, has_media_type, SyncBody},
+            Request, Response,
+        },
+        virtual_host::middleware::{Middleware, MiddlewareFuture, Next},
+    },
+};
+
+/// Quality of brotli, lower than the default as responses are compressed per request
+const BROTLI_QUALITY: u32 = 5;
+
+/// Window size of brotli, as a power of two
+const BROTLI_WINDOW: u32 = 22;
+
+/// Buffer size of the brotli encoder
+const BROTLI_BUFFER: usize = 4096;
+
+/// Level of zstd
+const ZSTD_LEVEL: i32 = 3;
+
+/// Marks responses whose compression was already decided, so an outer middleware, such as
+/// the one of the virtual host around a path with its own compression, leaves them as is
+#[derive(Debug, Clone, Copy)]
+struct Decided;
+
+/// Middleware compressing the responses accepted by the client
+pub struct Compression {
+    config: CompressionConfig,
+}
+
+impl Compression {
+    /// Create a new compression middleware
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - A `CompressionConfig` instance containing the compression settings.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new `Compression` instance.
+    pub fn new(config: CompressionConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether a response is worth compressing, regardless of the client
+    fn is_compressible(&self, response: &Response) -> bool {
+        let status = response.status();
+        if !status.is_success()
+            || status == StatusCode::NO_CONTENT
+            || status == StatusCode::PARTIAL_CONTENT
+        {
+            return false;
+        }
+
+        let headers = response.headers();
+        if headers.contains_key(header::CONTENT_ENCODING)
+            || headers.contains_key(header::CONTENT_RANGE)
+        {
+            return false;
+        }
+
+        let no_transform = headers
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|directive| {
+                directive
+                    .trim()
+                    .eq_ignore_ascii_case("no-transform")
+            });
+        if no_transform {
+            return false;
+        }
+
+        let is_small = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .parse::<u64>()
+                    .ok()
+            })
+            .is_some_and(|length| {
+                length
+                    < self
+                        .config
+                        .min_size()
+            });
+        if is_small {
+            return false;
+        }
+
+        headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| self.accepts_type(content_type))
+    }
+
+    /// Whether a content type is in the allowlist, event streams are never compressed
+    fn accepts_type(&self, content_type: &str) -> bool {
+        if has_media_type(content_type, "text/event-stream") {
+            return false;
+        }
+
+        self.config
+            .mime_types()
+            .iter()
+            .any(|mime_type| match mime_type.strip_suffix("/*") {
+                Some(top_level) => content_type
+                    .split('/')
+                    .next()
+                    .is_some_and(|kind| {
+                        kind.trim()
+                            .eq_ignore_ascii_case(top_level)
+                    }),
+                None => has_media_type(content_type, mime_type),
+            })
+    }
+
+    /// Select the accepted encoding with the highest quality, the configured order breaking ties
+    fn select(&self, accept_encoding: &str) -> Option<Encoding> {
+        let mut selected: Option<(Encoding, u16)> = None;
+        for encoding in self
+            .config
+            .algorithms()
+        {
+            let q = encoding_quality(accept_encoding, encoding.as_str());
+            if q > 0
+                && selected
+                    .as_ref()
+                    .map_or(true, |(_, best)| q > *best)
+            {
+                selected = Some((*encoding, q));
+            }
+        }
+
+        selected.map(|(encoding, _)| encoding)
+    }
+}
+
+impl Middleware for Compression {
+    /// Compress the response of the request when the client accepts it
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to handle
+    /// * `next` - The rest of the chain
+    ///
+    /// # Returns
+    ///
+    /// * `MiddlewareFuture<'a>` - The future that will produce the response
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+        Box::pin(async move {
+            let accept_encoding = request
+                .headers()
+                .get(header::ACCEPT_ENCODING)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let is_head = request.method() == Method::HEAD;
+
+            let mut response = next
+                .run(request)
+                .await?;
+
+            let extensions = response
+                .inner
+                .extensions_mut();
+            if extensions
+                .get::<Decided>()
+                .is_some()
+            {
+                return Ok(response);
+            }
+            extensions.insert(Decided);
+
+            if !self
+                .config
+                .enabled()
+                || !self.is_compressible(&response)
+            {
+                return Ok(response);
+            }
+
+            let varies = response
+                .headers()
+                .get_all(header::VARY)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .any(|name| {
+                    name.trim()
+                        .eq_ignore_ascii_case("accept-encoding")
+                });
+            if !varies {
+                response
+                    .headers_mut()
+                    .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+            }
+
+            let Some(encoding) = accept_encoding
+                .as_deref()
+                .and_then(|accept_encoding| self.select(accept_encoding))
+            else {
+                return Ok(response);
+            };
+
+            Ok(compress(response, encoding, is_head))
+        })
+    }
+}
+
+/// Update the headers of a compressed response
+fn compressed_headers(headers: &mut HeaderMap, encoding: Encoding) {
+    headers.remove(header::CONTENT_LENGTH);
+    headers.remove(header::ACCEPT_RANGES);
+    headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding.as_str()));
+
+    // The compressed representation is not byte for byte the same
+    let weak_etag = headers
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .and_then(|etag| HeaderValue::from_str(&format!("W/{}", etag)).ok());
+    if let Some(etag) = weak_etag {
+        headers.insert(header::ETAG, etag);
+    }
+}
+
+/// Compress the body of a response as it is streamed
+fn compress(response: Response, encoding: Encoding, is_head: bool) -> Response {
+    let (mut parts, body) = response
+        .inner
+        .into_parts();
+    compressed_headers(&mut parts.headers, encoding);
+
+    let body = if is_head { HttpBody::from_bytes(&[]) } else { compressed_body(body, encoding) };
+
+    Response { inner: http::Response::from_parts(parts, body) }
+}
+
+/// Streaming encoder of a content coding, writing into a buffer drained after each chunk
+enum Encoder {
+    Brotli(Box<brotli::CompressorWriter<Vec<u8>>>),
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+}
+
+impl Encoder {
+    fn new(encoding: Encoding) -> std::io::Result<Self> {
+        Ok(match encoding {
+            Encoding::Brotli => Encoder::Brotli(Box::new(brotli::CompressorWriter::new(
+                Vec::new(),
+                BROTLI_BUFFER,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW,
+            ))),
+            Encoding::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            )),
+            Encoding::Zstd => {
+                Encoder::Zstd(zstd::stream::write::Encoder::new(Vec::new(), ZSTD_LEVEL)?)
+            }
+        })
+    }
+
+    /// Compress a chunk, returning the output produced so far
+    fn write(&mut self, chunk: &[u8]) -> std::io::Result<Bytes> {
+        let output = match self {
+            Encoder::Brotli(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.get_mut()
+            }
+            Encoder::Gzip(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.get_mut()
+            }
+            Encoder::Zstd(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.get_mut()
+            }
+        };
+
+        Ok(Bytes::from(std::mem::take(output)))
+    }
+
+    /// Finish the stream, returning the remaining output
+    fn finish(self) -> std::io::Result<Bytes> {
+        let output = match self {
+            Encoder::Brotli(encoder) => encoder.into_inner(),
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            Encoder::Zstd(encoder) => encoder.finish()?,
+        };
+
+        Ok(Bytes::from(output))
+    }
+}
+
+/// Compress the data frames of a body, trailers are dropped
+fn compressed_body(body: HttpBody, encoding: Encoding) -> HttpBody {
+    let chunks = stream::try_unfold(Some((body, None)), move |state| async move {
+        let Some((mut body, encoder)) = state else {
+            return Ok(None);
+        };
+        let mut encoder = match encoder {
+            Some(encoder) => encoder,
+            None => Encoder::new(encoding)?,
+        };
+
+        loop {
+            match body.frame().await {
+                Some(frame) => {
+                    let Ok(data) = frame?.into_data() else {
+                        continue;
+                    };
+                    let output = encoder.write(&data)?;
+                    if !output.is_empty() {
+                        return Ok(Some((output, Some((body, Some(encoder))))));
+                    }
+                }
+                None => return Ok(Some((encoder.finish()?, None))),
+            }
+        }
+    });
+
+    let body = StreamBody::new(
+        chunks
+            .try_filter(|chunk| std::future::ready(!chunk.is_empty()))
+            .map_ok(Frame::data),
+    );
+
+    HttpBody::Stream(SyncBody::new(body).boxed())
+}
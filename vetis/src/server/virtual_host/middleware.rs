@@ -0,0 +1,122 @@
+//! Middleware running around the requests of a virtual host or a path
+//!
+//! Middleware are attached to a `VirtualHost` with `VirtualHost::add_middleware`, or to a
+//! single path with `HostPath::middleware`. Each one receives the request together with a
+//! `Next` handle running the rest of the chain, so it can act before the request is
+//! handled, after the response is produced, or answer on its own without calling `next`.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use vetis::{
+//!     errors::VetisError,
+//!     server::{
+//!         http::{Request, Response},
+//!         virtual_host::middleware::{Middleware, MiddlewareFuture, Next},
+//!     },
+//! };
+//!
+//! struct PoweredBy;
+//!
+//! impl Middleware for PoweredBy {
+//!     fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+//!         Box::pin(async move {
+//!             let mut response = next.run(request).await?;
+//!             response
+//!                 .headers_mut()
+//!                 .insert("x-powered-by", http::HeaderValue::from_static("vetis"));
+//!             Ok(response)
+//!         })
+//!     }
+//! }
+//!
+//! virtual_host.add_middleware(PoweredBy);
+//! ```
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use crate::{
+    errors::VetisError,
+    server::{
+        http::{Request, Response},
+        virtual_host::{
+            path::{HostPath, Path},
+            VirtualHost,
+        },
+    },
+};
+
+/// Future returned by middleware
+pub type MiddlewareFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + 'a>>;
+
+/// Trait for logic running around request handling
+pub trait Middleware: Send + Sync {
+    /// Handles the request
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to handle
+    /// * `next` - The rest of the chain, ending with the virtual host or path handler
+    ///
+    /// # Returns
+    ///
+    /// * `MiddlewareFuture<'a>` - The future that will produce the response
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a>;
+}
+
+/// What the chain runs once every middleware called `next`
+enum Endpoint<'a> {
+    VirtualHost(&'a VirtualHost),
+    Path(&'a HostPath, Arc<String>),
+}
+
+/// The remaining middleware of a chain
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    endpoint: Endpoint<'a>,
+}
+
+impl<'a> Next<'a> {
+    /// Chain running the middleware of a virtual host before routing the request
+    pub(crate) fn virtual_host(
+        middlewares: &'a [Arc<dyn Middleware>],
+        virtual_host: &'a VirtualHost,
+    ) -> Self {
+        Self { middlewares, endpoint: Endpoint::VirtualHost(virtual_host) }
+    }
+
+    /// Chain running the middleware of a path before handling the request
+    pub(crate) fn path(
+        middlewares: &'a [Arc<dyn Middleware>],
+        path: &'a HostPath,
+        uri: Arc<String>,
+    ) -> Self {
+        Self { middlewares, endpoint: Endpoint::Path(path, uri) }
+    }
+
+    /// Runs the rest of the chain
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to pass along
+    ///
+    /// # Returns
+    ///
+    /// * `MiddlewareFuture<'a>` - The future that will produce the response
+    pub fn run(self, request: Request) -> MiddlewareFuture<'a> {
+        match self
+            .middlewares
+            .split_first()
+        {
+            Some((middleware, middlewares)) => {
+                let next = Next { middlewares, endpoint: self.endpoint };
+                middleware.handle(request, next)
+            }
+            None => match self.endpoint {
+                Endpoint::VirtualHost(virtual_host) => virtual_host.dispatch(request),
+                Endpoint::Path(path, uri) => path.handle(request, uri),
+            },
+        }
+    }
+}
@@ -21,22 +21,29 @@
 ///     Ok(response)
 /// }));
 /// ```
-use std::{future::Future, path::PathBuf, pin::Pin};
+use std::{collections::HashMap, future::Future, path::PathBuf, pin::Pin};
 
 use http::StatusCode;
 use hyper_body_utils::HttpBody;
 #[cfg(feature = "python")]
 use pyo3::Python;
 use radix_trie::Trie;
+use rustls::{pki_types::UnixTime, server::danger::ClientCertVerifier};
 use std::sync::Arc;
 
 use crate::{
-    config::server::virtual_host::VirtualHostConfig,
-    errors::{FileError, VetisError, VirtualHostError},
+    config::server::virtual_host::{SecurityConfig, VirtualHostConfig},
+    errors::{VetisError, VirtualHostError},
     server::{
-        http::{Request, Response},
-        virtual_host::path::{HostPath, Path},
+        http::{Request, Response, TlsInfo},
+        tls::TlsFactory,
+        virtual_host::{
+            middleware::{Middleware, Next},
+            path::{HostPath, Path},
+            router::Router,
+        },
     },
+    utils::path::{decode_path, normalize_path},
 };
 
 #[cfg(feature = "smol-rt")]
@@ -44,6 +51,9 @@ use smol::fs::File;
 #[cfg(feature = "tokio-rt")]
 use tokio::fs::File;
 
+#[cfg(feature = "compression")]
+use crate::server::virtual_host::compression::Compression;
+
 #[cfg(feature = "static-files")]
 use crate::server::virtual_host::path::static_files::StaticPath;
 
@@ -53,7 +63,11 @@ use crate::server::virtual_host::path::proxy::ProxyPath;
 #[cfg(feature = "interface")]
 use crate::server::virtual_host::path::interface::InterfacePath;
 
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod middleware;
 pub mod path;
+pub mod router;
 
 /// Type alias for boxed handler closures.
 ///
@@ -122,10 +136,161 @@ where
     Box::new(move |req| Box::pin(f(req)))
 }
 
+/// Type alias for boxed error handler closures.
+///
+/// Error handlers render the response of a failed request, they receive the
+/// status code and the error that caused it, `None` when no path matched the
+/// request. Returning `None` falls back to the configured status pages.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::server::virtual_host::BoxedErrorHandler;
+/// use vetis::Response;
+///
+/// let error_handler: BoxedErrorHandler = Box::new(|status, error| {
+///     let message = error.map(|e| e.to_string()).unwrap_or_default();
+///     Some(Response::builder()
+///         .status(status)
+///         .html(&format!("<h1>{}</h1><p>{}</p>", status, message)))
+/// });
+/// ```
+pub type BoxedErrorHandler =
+    Box<dyn Fn(StatusCode, Option<&VetisError>) -> Option<Response> + Send + Sync>;
+
+/// How a virtual host name matched a requested hostname.
+///
+/// Variants are ordered by priority, an exact match wins over a wildcard, and a
+/// more specific wildcard wins over a shorter one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum HostMatch {
+    Default,
+    Wildcard(usize),
+    Exact,
+}
+
+/// Match a hostname against a virtual host name
+///
+/// Names starting with `*.` match any subdomain of the remaining name, other names
+/// must be equal to the hostname. Comparisons are case insensitive.
+///
+/// # Arguments
+///
+/// * `name` - The virtual host name or alias, possibly a wildcard.
+/// * `host` - The requested hostname.
+///
+/// # Returns
+///
+/// * `Option<HostMatch>` - How the hostname matched, `None` if it did not.
+pub(crate) fn match_hostname(name: &str, host: &str) -> Option<HostMatch> {
+    match name.strip_prefix("*.") {
+        Some(domain) => {
+            let label_len = host
+                .len()
+                .checked_sub(domain.len() + 1)?;
+            let (label, suffix) = (host.get(..label_len)?, host.get(label_len..)?);
+            let matches = !label.is_empty()
+                && suffix.starts_with('.')
+                && suffix[1..].eq_ignore_ascii_case(domain);
+            matches.then_some(HostMatch::Wildcard(domain.len()))
+        }
+        None => name
+            .eq_ignore_ascii_case(host)
+            .then_some(HostMatch::Exact),
+    }
+}
+
+/// Find the virtual host serving a hostname on a port
+///
+/// Exact hostnames and aliases take priority over wildcards, the default virtual
+/// host of the port is used when nothing else matches.
+///
+/// # Arguments
+///
+/// * `virtual_hosts` - The registered virtual hosts.
+/// * `host` - The requested hostname.
+/// * `port` - The port the request was received on.
+///
+/// # Returns
+///
+/// * `Option<&Arc<VirtualHost>>` - The matching virtual host, if any.
+pub(crate) fn find_virtual_host<'a>(
+    virtual_hosts: &'a HashMap<(Arc<str>, u16), Arc<VirtualHost>>,
+    host: &str,
+    port: u16,
+) -> Option<&'a Arc<VirtualHost>> {
+    if let Some(virtual_host) = virtual_hosts.get(&(Arc::from(host), port)) {
+        return Some(virtual_host);
+    }
+
+    virtual_hosts
+        .values()
+        .filter(|virtual_host| virtual_host.port() == port)
+        .filter_map(|virtual_host| {
+            virtual_host
+                .match_host(host)
+                .map(|host_match| (host_match, virtual_host))
+        })
+        .max_by_key(|(host_match, _)| *host_match)
+        .map(|(_, virtual_host)| virtual_host)
+}
+
+/// Ensure a virtual host can be served alongside other virtual hosts
+///
+/// Hostnames and aliases must be unique per port, and each port has at most one
+/// default virtual host.
+///
+/// # Arguments
+///
+/// * `virtual_hosts` - The virtual hosts already registered.
+/// * `virtual_host` - The virtual host to register.
+///
+/// # Returns
+///
+/// * `Result<(), VetisError>` - An error naming the conflicting hostname and port.
+pub(crate) fn check_conflicts<'a>(
+    virtual_hosts: impl IntoIterator<Item = &'a Arc<VirtualHost>>,
+    virtual_host: &VirtualHost,
+) -> Result<(), VetisError> {
+    let port = virtual_host.port();
+    for other in virtual_hosts
+        .into_iter()
+        .filter(|other| other.port() == port)
+    {
+        let conflict = virtual_host
+            .names()
+            .find(|name| {
+                other
+                    .names()
+                    .any(|other_name| other_name.eq_ignore_ascii_case(name))
+            });
+
+        if let Some(name) = conflict {
+            return Err(VetisError::VirtualHost(VirtualHostError::AlreadyExists(format!(
+                "{}:{}",
+                name, port
+            ))));
+        }
+
+        if virtual_host.is_default() && other.is_default() {
+            return Err(VetisError::VirtualHost(VirtualHostError::AlreadyExists(format!(
+                "default virtual host on port {}",
+                port
+            ))));
+        }
+    }
+
+    Ok(())
+}
+
 // All of them should have a handler to process requests
 pub struct VirtualHost {
     config: VirtualHostConfig,
     paths: Trie<String, HostPath>,
+    router: Router,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    error_handler: Option<BoxedErrorHandler>,
+    client_verifier: Option<Arc<dyn ClientCertVerifier>>,
 }
 
 impl VirtualHost {
@@ -139,11 +304,39 @@ impl VirtualHost {
     ///
     /// * `Self` - A new `VirtualHost` instance.
     pub fn new(host_config: VirtualHostConfig) -> Self {
-        let mut host = Self { config: host_config.clone(), paths: Trie::new() };
+        let mut host = Self {
+            config: host_config.clone(),
+            paths: Trie::new(),
+            router: Router::default(),
+            middlewares: Vec::new(),
+            error_handler: None,
+            client_verifier: None,
+        };
+
+        if let Some(security) = host_config
+            .security()
+            .as_ref()
+            .filter(|security| security.client_auth())
+        {
+            // Without a verifier every request is refused, see `verify_client`
+            match TlsFactory::client_verifier(
+                security
+                    .ca_cert()
+                    .as_deref(),
+            ) {
+                Ok(client_verifier) => host.client_verifier = Some(client_verifier),
+                Err(e) => log::error!("Failed to verify client certificates: {}", e),
+            }
+        }
 
         #[cfg(feature = "python")]
         Python::initialize();
 
+        #[cfg(feature = "compression")]
+        if let Some(compression) = host_config.compression() {
+            host.add_middleware(Compression::new(compression.clone()));
+        }
+
         #[cfg(feature = "static-files")]
         if let Some(static_paths) = &host_config.static_paths() {
             for static_path in static_paths {
@@ -170,6 +363,10 @@ impl VirtualHost {
 
     /// Add a path to the virtual host
     ///
+    /// Paths are matched by prefix, except handler paths with parameters which
+    /// must match the whole request path. A path equal to the request path is used
+    /// first, then the most specific pattern, then the longest prefix.
+    ///
     /// # Arguments
     ///
     /// * `path` - A `HostPath` instance containing the path configuration.
@@ -178,6 +375,15 @@ impl VirtualHost {
         P: Into<HostPath>,
     {
         let path = path.into();
+        if let Some(pattern) = path
+            .pattern()
+            .cloned()
+        {
+            self.router
+                .insert(pattern, path);
+            return;
+        }
+
         self.paths.insert(
             path.uri()
                 .to_string(),
@@ -185,6 +391,38 @@ impl VirtualHost {
         );
     }
 
+    /// Add a middleware running around every request of the virtual host
+    ///
+    /// Middleware run in the order they were added, before path middleware, and
+    /// also see the status pages served when no path matches or a path fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `middleware` - A type implementing the `Middleware` trait.
+    pub fn add_middleware<M>(&mut self, middleware: M)
+    where
+        M: Middleware + 'static,
+    {
+        self.middlewares
+            .push(Arc::new(middleware));
+    }
+
+    /// Set the handler rendering the responses of failed requests
+    ///
+    /// The handler is called for every error response of the virtual host, such as
+    /// a missing path or a failing handler, status pages are only served when it
+    /// returns `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `error_handler` - A function receiving the status code and the error, if any.
+    pub fn set_error_handler<F>(&mut self, error_handler: F)
+    where
+        F: Fn(StatusCode, Option<&VetisError>) -> Option<Response> + Send + Sync + 'static,
+    {
+        self.error_handler = Some(Box::new(error_handler));
+    }
+
     /// Returns virtual host configuration
     ///
     /// # Returns
@@ -213,6 +451,47 @@ impl VirtualHost {
         self.config.port()
     }
 
+    /// Returns every name the virtual host answers to
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = &str>` - The virtual host hostname followed by its aliases.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        let aliases = self
+            .config
+            .aliases()
+            .iter()
+            .flatten()
+            .map(String::as_str);
+
+        std::iter::once(self.hostname()).chain(aliases)
+    }
+
+    /// Returns whether this is the default virtual host of its port
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether requests for unknown hostnames are served by this virtual host.
+    pub fn is_default(&self) -> bool {
+        self.config
+            .is_default()
+    }
+
+    /// Returns how the virtual host matches a requested hostname
+    pub(crate) fn match_host(&self, host: &str) -> Option<HostMatch> {
+        let host_match = self
+            .names()
+            .filter_map(|name| match_hostname(name, host))
+            .max();
+
+        match host_match {
+            Some(host_match) => Some(host_match),
+            None => self
+                .is_default()
+                .then_some(HostMatch::Default),
+        }
+    }
+
     /// Returns virtual host security configuration
     ///
     /// # Returns
@@ -224,23 +503,29 @@ impl VirtualHost {
             .is_some()
     }
 
-    async fn serve_status_page(&self, status: u16) -> Result<Response, VetisError> {
-        let status_code = match StatusCode::from_u16(status) {
-            Ok(code) => code,
-            Err(_) => {
-                return Err(VetisError::VirtualHost(VirtualHostError::Interface(
-                    "Invalid status code".to_string(),
-                )))
+    /// Serve the response of a failed request
+    ///
+    /// The error handler is tried first, then the status page configured for the
+    /// status code, then the status code reason.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status code of the response.
+    /// * `error` - The error that caused the response, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Response` - The error response.
+    pub(crate) async fn serve_error(
+        &self,
+        status: StatusCode,
+        error: Option<&VetisError>,
+    ) -> Response {
+        if let Some(error_handler) = &self.error_handler {
+            if let Some(response) = error_handler(status, error) {
+                return response;
             }
-        };
-
-        let static_status_response = Response::builder()
-            .status(status_code)
-            .text(
-                status_code
-                    .canonical_reason()
-                    .unwrap_or("Unknown status code"),
-            );
+        }
 
         if let Some(status_pages) = &self
             .config
@@ -250,23 +535,84 @@ impl VirtualHost {
                 self.config
                     .root_directory(),
             );
-            if let Some(page) = status_pages.get(&status) {
+            if let Some(page) = status_pages.get(&status.as_u16()) {
                 let file = root_directory.join(page);
                 if file.exists() {
                     let result = File::open(file).await;
                     if let Ok(data) = result {
-                        return Ok(Response::builder()
-                            .status(status_code)
-                            .body(HttpBody::from_file(data)));
+                        return Response::builder()
+                            .status(status)
+                            .body(HttpBody::from_file(data));
                     }
                 }
             }
         }
-        Ok(static_status_response)
+
+        Response::builder()
+            .status(status)
+            .text(
+                status
+                    .canonical_reason()
+                    .unwrap_or("Unknown status code"),
+            )
+    }
+
+    /// Ensure the client presented a certificate signed by the virtual host CA
+    ///
+    /// Always succeeds when client authentication is disabled.
+    fn verify_client(&self, request: &Request) -> Result<(), VetisError> {
+        let client_auth = self
+            .config
+            .security()
+            .as_ref()
+            .is_some_and(SecurityConfig::client_auth);
+        if !client_auth {
+            return Ok(());
+        }
+
+        let Some(client_verifier) = &self.client_verifier else {
+            return Err(VetisError::VirtualHost(VirtualHostError::ClientCertificate(
+                "No trusted CA certificate".to_string(),
+            )));
+        };
+
+        let certificates = request
+            .tls()
+            .map(TlsInfo::peer_certificates)
+            .unwrap_or_default();
+        let Some((end_entity, intermediates)) = certificates.split_first() else {
+            return Err(VetisError::VirtualHost(VirtualHostError::ClientCertificate(
+                "No certificate presented".to_string(),
+            )));
+        };
+
+        client_verifier
+            .verify_client_cert(end_entity, intermediates, UnixTime::now())
+            .map_err(|e| {
+                VetisError::VirtualHost(VirtualHostError::ClientCertificate(e.to_string()))
+            })?;
+
+        Ok(())
+    }
+
+    /// Serve the response of an error returned while processing a request
+    async fn serve_failure(&self, error: VetisError) -> Response {
+        let status = error.status_code();
+        if status.is_server_error() {
+            log::error!("Request failed: {}", error);
+        } else {
+            log::warn!("Request failed: {}", error);
+        }
+
+        self.serve_error(status, Some(&error))
+            .await
     }
 
     /// Route request to the appropriate handler
     ///
+    /// Errors returned by paths and middleware are served as error responses, see
+    /// `set_error_handler` and the `status_pages` configuration.
+    ///
     /// # Arguments
     ///
     /// * `request` - A `Request` instance containing the request information.
@@ -276,66 +622,102 @@ impl VirtualHost {
     /// * `Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>>` - A pinned box containing the future that will resolve to a `Result<Response, VetisError>`.
     pub fn route(
         &self,
-        request: Request,
+        mut request: Request,
+    ) -> Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>> {
+        request.set_virtual_host(self.hostname());
+        request.set_max_body_size(
+            self.config
+                .max_body_size(),
+        );
+        request.set_body_timeout(
+            self.config
+                .body_timeout(),
+        );
+        if let Err(error) = self.verify_client(&request) {
+            return Box::pin(async move {
+                Ok(self
+                    .serve_failure(error)
+                    .await)
+            });
+        }
+        let result = Next::virtual_host(&self.middlewares, self).run(request);
+
+        Box::pin(async move {
+            match result.await {
+                Ok(response) => Ok(response),
+                Err(error) => Ok(self
+                    .serve_failure(error)
+                    .await),
+            }
+        })
+    }
+
+    /// Route request to the matching path, once the virtual host middleware ran
+    pub(crate) fn dispatch(
+        &self,
+        mut request: Request,
     ) -> Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>> {
         let uri_path: String = request
             .uri()
             .path()
             .into();
 
-        if uri_path.starts_with("..") {
+        // Paths going above the root, such as `/static/../../etc/passwd` or its
+        // percent-encoded forms, are refused before any path sees them
+        let escapes_root = decode_path(&uri_path)
+            .and_then(|path| normalize_path(&path))
+            .is_none();
+        if uri_path.starts_with("..") || escapes_root {
             return Box::pin(async move {
-                self.serve_status_page(http::StatusCode::FORBIDDEN.as_u16())
-                    .await
+                Ok(self
+                    .serve_error(StatusCode::FORBIDDEN, None)
+                    .await)
             });
         }
 
-        let matches = self
+        // Literal paths equal to the request path win over patterns, which win over prefixes
+        let (path, target_path) = if let Some(path) = self
             .paths
-            .get_ancestor_value(&uri_path);
-
-        let Some(path) = matches else {
-            return Box::pin(async move {
-                self.serve_status_page(http::StatusCode::NOT_FOUND.as_u16())
-                    .await
-            });
+            .get(&uri_path)
+        {
+            (path, String::new())
+        } else if let Some((path, params)) = self
+            .router
+            .find(&uri_path)
+        {
+            request.set_params(params);
+            (path, String::new())
+        } else {
+            let matches = self
+                .paths
+                .get_ancestor_value(&uri_path);
+
+            let Some(path) = matches else {
+                return Box::pin(async move {
+                    Ok(self
+                        .serve_error(StatusCode::NOT_FOUND, None)
+                        .await)
+                });
+            };
+
+            let target_path: String = uri_path
+                .strip_prefix(path.uri())
+                .unwrap_or(&uri_path)
+                .into();
+
+            (path, target_path)
         };
 
-        let target_path: String = uri_path
-            .strip_prefix(path.uri())
-            .unwrap_or(&uri_path)
-            .into();
+        request.set_matched_path(path.uri());
 
         let result = path.handle(request, Arc::from(target_path));
 
         Box::pin(async move {
             match result.await {
                 Ok(response) => Ok(response),
-                Err(error) => {
-                    match error {
-                        VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)) => {
-                            log::error!("Invalid path: {}", error);
-                            return self
-                                .serve_status_page(http::StatusCode::NOT_FOUND.as_u16())
-                                .await;
-                        }
-                        VetisError::VirtualHost(VirtualHostError::Proxy(ref error)) => {
-                            log::error!("Proxy error: {}", error);
-                            return self
-                                .serve_status_page(http::StatusCode::BAD_GATEWAY.as_u16())
-                                .await;
-                        }
-                        VetisError::VirtualHost(VirtualHostError::Auth(e)) => {
-                            log::error!("Auth error: {}", e);
-                            return self
-                                .serve_status_page(http::StatusCode::UNAUTHORIZED.as_u16())
-                                .await;
-                        }
-                        _ => {}
-                    }
-
-                    Err(error)
-                }
+                Err(error) => Ok(self
+                    .serve_failure(error)
+                    .await),
             }
         })
     }
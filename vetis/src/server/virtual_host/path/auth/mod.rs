@@ -1,6 +1,15 @@
 use std::future::Future;
 
-use crate::{errors::VetisError, server::virtual_host::path::auth::basic_auth::BasicAuth};
+use crate::{
+    errors::{VetisError, VirtualHostError},
+    server::{
+        http::Request,
+        virtual_host::{
+            middleware::{Middleware, MiddlewareFuture, Next},
+            path::auth::basic_auth::BasicAuth,
+        },
+    },
+};
 
 use http::HeaderMap;
 
@@ -35,3 +44,32 @@ impl Auth for AuthType {
         }
     }
 }
+
+impl Middleware for AuthType {
+    /// Rejects requests failing authentication before they reach the path
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to authenticate
+    /// * `next` - The rest of the chain
+    ///
+    /// # Returns
+    ///
+    /// * `MiddlewareFuture<'a>` - The response, or an auth error answered with `401 Unauthorized`
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+        Box::pin(async move {
+            if !self
+                .authenticate(request.headers())
+                .await
+                .unwrap_or(false)
+            {
+                return Err(VetisError::VirtualHost(VirtualHostError::Auth(
+                    "Unauthorized".to_string(),
+                )));
+            }
+
+            next.run(request)
+                .await
+        })
+    }
+}
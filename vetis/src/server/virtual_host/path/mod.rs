@@ -4,18 +4,29 @@ use std::{future::Future, pin::Pin};
 
 use std::sync::Arc;
 
+use http::{header, HeaderValue, Method, StatusCode};
+use hyper_body_utils::HttpBody;
+
 #[cfg(feature = "interface")]
 use crate::server::virtual_host::path::interface::InterfacePath;
 #[cfg(feature = "reverse-proxy")]
 use crate::server::virtual_host::path::proxy::ProxyPath;
+#[cfg(feature = "tower")]
+use crate::server::virtual_host::path::service::{PathService, ServicePath};
 #[cfg(feature = "static-files")]
 use crate::server::virtual_host::path::static_files::StaticPath;
+#[cfg(feature = "websocket")]
+use crate::server::virtual_host::path::websocket::WebSocketPath;
 
 use crate::{
     errors::{HandlerError, VetisError, VirtualHostError},
     server::{
         http::{Request, Response},
-        virtual_host::BoxedHandlerClosure,
+        virtual_host::{
+            middleware::{Middleware, Next},
+            router::PathPattern,
+            BoxedHandlerClosure,
+        },
     },
 };
 
@@ -25,8 +36,12 @@ pub mod auth;
 pub mod interface;
 #[cfg(feature = "reverse-proxy")]
 pub mod proxy;
+#[cfg(feature = "tower")]
+pub mod service;
 #[cfg(feature = "static-files")]
 pub mod static_files;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 /// Trait for handling different types of paths in the server
 pub trait Path {
@@ -67,6 +82,121 @@ pub enum HostPath {
     #[cfg(feature = "interface")]
     /// Interface path
     Interface(InterfacePath),
+    #[cfg(feature = "tower")]
+    /// Tower service path
+    Service(ServicePath),
+    #[cfg(feature = "websocket")]
+    /// WebSocket path
+    WebSocket(WebSocketPath),
+    /// Path wrapped by middleware
+    Layered(LayeredPath),
+}
+
+impl HostPath {
+    /// Wrap the path with a middleware
+    ///
+    /// Middleware run in the order they were added, the first one added being the
+    /// first to receive the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `middleware` - The middleware to run around the path
+    ///
+    /// # Returns
+    ///
+    /// * `HostPath` - The path wrapped by the middleware
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let path = HandlerPath::builder()
+    ///     .uri("/api")
+    ///     .handler(handler_fn(api))
+    ///     .build()?
+    ///     .middleware(Timing);
+    ///
+    /// virtual_host.add_path(path);
+    /// ```
+    pub fn middleware<M>(self, middleware: M) -> HostPath
+    where
+        M: Middleware + 'static,
+    {
+        let middleware: Arc<dyn Middleware> = Arc::new(middleware);
+        match self {
+            HostPath::Layered(mut layered) => {
+                layered
+                    .middlewares
+                    .push(middleware);
+                HostPath::Layered(layered)
+            }
+            path => HostPath::Layered(LayeredPath {
+                path: Box::new(path),
+                middlewares: vec![middleware],
+            }),
+        }
+    }
+
+    /// Wrap the path with a tower layer
+    ///
+    /// The layer receives a `PathService` handling requests with this path, and the
+    /// resulting service is mounted on the same uri.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer` - The tower layer to apply
+    ///
+    /// # Returns
+    ///
+    /// * `HostPath` - The path wrapped by the layer
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let path = HandlerPath::builder()
+    ///     .uri("/api")
+    ///     .handler(handler_fn(api))
+    ///     .build()?
+    ///     .layer(tower_http::timeout::TimeoutLayer::new(Duration::from_secs(10)));
+    ///
+    /// virtual_host.add_path(path);
+    /// ```
+    #[cfg(feature = "tower")]
+    pub fn layer<L, B>(self, layer: L) -> HostPath
+    where
+        L: tower_layer::Layer<PathService>,
+        L::Service: tower_service::Service<http::Request<HttpBody>, Response = http::Response<B>>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <L::Service as tower_service::Service<http::Request<HttpBody>>>::Future: Send + 'static,
+        <L::Service as tower_service::Service<http::Request<HttpBody>>>::Error:
+            Into<Box<dyn std::error::Error + Send + Sync>>,
+        B: hyper::body::Body<Data = bytes::Bytes> + Send + 'static,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        HostPath::Service(ServicePath::layered(self, layer))
+    }
+
+    /// Returns the pattern of handler paths with parameters
+    pub(crate) fn pattern(&self) -> Option<&PathPattern> {
+        match self {
+            HostPath::Handler(handler) => handler.pattern(),
+            #[cfg(feature = "tower")]
+            HostPath::Service(service) => service.pattern(),
+            #[cfg(feature = "websocket")]
+            HostPath::WebSocket(websocket) => websocket.pattern(),
+            HostPath::Layered(layered) => layered
+                .path
+                .pattern(),
+            #[cfg(feature = "reverse-proxy")]
+            HostPath::Proxy(_) => None,
+            #[cfg(feature = "static-files")]
+            HostPath::Static(_) => None,
+            #[cfg(feature = "interface")]
+            HostPath::Interface(_) => None,
+        }
+    }
 }
 
 impl Path for HostPath {
@@ -84,6 +214,11 @@ impl Path for HostPath {
             HostPath::Static(static_path) => static_path.uri(),
             #[cfg(feature = "interface")]
             HostPath::Interface(interface_path) => interface_path.uri(),
+            #[cfg(feature = "tower")]
+            HostPath::Service(service) => service.uri(),
+            #[cfg(feature = "websocket")]
+            HostPath::WebSocket(websocket) => websocket.uri(),
+            HostPath::Layered(layered) => layered.uri(),
         }
     }
 
@@ -110,19 +245,64 @@ impl Path for HostPath {
             HostPath::Static(static_path) => static_path.handle(request, uri),
             #[cfg(feature = "interface")]
             HostPath::Interface(interface_path) => interface_path.handle(request, uri),
+            #[cfg(feature = "tower")]
+            HostPath::Service(service) => service.handle(request, uri),
+            #[cfg(feature = "websocket")]
+            HostPath::WebSocket(websocket) => websocket.handle(request, uri),
+            HostPath::Layered(layered) => layered.handle(request, uri),
         }
     }
 }
 
+/// Path wrapped by middleware
+pub struct LayeredPath {
+    path: Box<HostPath>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl Path for LayeredPath {
+    /// Returns the URI of the wrapped path
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The URI of the path
+    fn uri(&self) -> &str {
+        self.path.uri()
+    }
+
+    /// Runs the middleware, then the wrapped path
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to handle
+    /// * `uri` - The URI of the path
+    ///
+    /// # Returns
+    ///
+    /// * `Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>>` - The future that will handle the request
+    fn handle(
+        &self,
+        request: Request,
+        uri: Arc<String>,
+    ) -> Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>> {
+        Next::path(&self.middlewares, &self.path, uri).run(request)
+    }
+}
+
 /// Builder for handler path
 pub struct HandlerPathBuilder {
     uri: Arc<String>,
     handler: Option<BoxedHandlerClosure>,
+    methods: Vec<(Method, BoxedHandlerClosure)>,
 }
 
 impl HandlerPathBuilder {
     /// Allow set handler uri path
     ///
+    /// The uri can capture segments of the request path with named parameters such
+    /// as `/users/:id`, and the remainder of the path with a trailing wildcard such as
+    /// `/files/*path`. Captured values are available through `Request::params`.
+    ///
     /// # Arguments
     ///
     /// * `uri` - The uri of the handler path
@@ -137,6 +317,8 @@ impl HandlerPathBuilder {
 
     /// Allow set handler function
     ///
+    /// The handler serves every method without a handler of its own.
+    ///
     /// # Arguments
     ///
     /// * `handler` - The handler function
@@ -149,6 +331,66 @@ impl HandlerPathBuilder {
         self
     }
 
+    /// Allow set handler function for a single method
+    ///
+    /// Requests using a method without handler are answered with
+    /// `405 Method Not Allowed`, unless a handler for all methods is set.
+    /// `HEAD` requests are served by the `GET` handler and `OPTIONS` requests
+    /// are answered automatically when they have no handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The HTTP method
+    /// * `handler` - The handler function
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use vetis::server::virtual_host::{handler_fn, path::HandlerPath};
+    ///
+    /// let path = HandlerPath::builder()
+    ///     .uri("/users/:id")
+    ///     .method(http::Method::GET, handler_fn(get_user))
+    ///     .method(http::Method::DELETE, handler_fn(delete_user))
+    ///     .build()?;
+    /// ```
+    pub fn method(mut self, method: Method, handler: BoxedHandlerClosure) -> Self {
+        self.methods
+            .retain(|(other, _)| *other != method);
+        self.methods
+            .push((method, handler));
+        self
+    }
+
+    /// Allow set handler function for `GET` requests
+    pub fn get(self, handler: BoxedHandlerClosure) -> Self {
+        self.method(Method::GET, handler)
+    }
+
+    /// Allow set handler function for `POST` requests
+    pub fn post(self, handler: BoxedHandlerClosure) -> Self {
+        self.method(Method::POST, handler)
+    }
+
+    /// Allow set handler function for `PUT` requests
+    pub fn put(self, handler: BoxedHandlerClosure) -> Self {
+        self.method(Method::PUT, handler)
+    }
+
+    /// Allow set handler function for `PATCH` requests
+    pub fn patch(self, handler: BoxedHandlerClosure) -> Self {
+        self.method(Method::PATCH, handler)
+    }
+
+    /// Allow set handler function for `DELETE` requests
+    pub fn delete(self, handler: BoxedHandlerClosure) -> Self {
+        self.method(Method::DELETE, handler)
+    }
+
     /// Build the handler path
     ///
     /// # Returns
@@ -161,23 +403,35 @@ impl HandlerPathBuilder {
             ))));
         }
 
-        let handler = match self.handler {
-            Some(handler) => handler,
-            None => {
-                return Err(VetisError::VirtualHost(VirtualHostError::Handler(
-                    HandlerError::Handler("Handler must be set".to_string()),
-                )))
-            }
-        };
+        if self
+            .handler
+            .is_none()
+            && self
+                .methods
+                .is_empty()
+        {
+            return Err(VetisError::VirtualHost(VirtualHostError::Handler(HandlerError::Handler(
+                "Handler must be set".to_string(),
+            ))));
+        }
+
+        let pattern = PathPattern::parse(&self.uri)?;
 
-        Ok(HostPath::Handler(HandlerPath { uri: self.uri, handler }))
+        Ok(HostPath::Handler(HandlerPath {
+            uri: self.uri,
+            pattern,
+            handler: self.handler,
+            methods: self.methods,
+        }))
     }
 }
 
 /// Handler path
 pub struct HandlerPath {
     uri: Arc<String>,
-    handler: BoxedHandlerClosure,
+    pattern: Option<PathPattern>,
+    handler: Option<BoxedHandlerClosure>,
+    methods: Vec<(Method, BoxedHandlerClosure)>,
 }
 
 impl HandlerPath {
@@ -187,7 +441,46 @@ impl HandlerPath {
     ///
     /// * `HandlerPathBuilder` - The builder
     pub fn builder() -> HandlerPathBuilder {
-        HandlerPathBuilder { uri: Arc::from("/".to_string()), handler: None }
+        HandlerPathBuilder { uri: Arc::from("/".to_string()), handler: None, methods: Vec::new() }
+    }
+
+    /// Returns the pattern of the uri, `None` when it has no parameters
+    pub(crate) fn pattern(&self) -> Option<&PathPattern> {
+        self.pattern
+            .as_ref()
+    }
+
+    fn method_handler(&self, method: &Method) -> Option<&BoxedHandlerClosure> {
+        let handler = self
+            .methods
+            .iter()
+            .find(|(other, _)| other == method)
+            .map(|(_, handler)| handler);
+
+        match handler {
+            Some(handler) => Some(handler),
+            None if method == Method::HEAD => self.method_handler(&Method::GET),
+            None => None,
+        }
+    }
+
+    /// Returns the value of the `Allow` header
+    fn allow(&self) -> String {
+        let mut allowed: Vec<&str> = self
+            .methods
+            .iter()
+            .map(|(method, _)| method.as_str())
+            .collect();
+
+        if allowed.contains(&Method::GET.as_str()) && !allowed.contains(&Method::HEAD.as_str()) {
+            allowed.push(Method::HEAD.as_str());
+        }
+
+        if !allowed.contains(&Method::OPTIONS.as_str()) {
+            allowed.push(Method::OPTIONS.as_str());
+        }
+
+        allowed.join(", ")
     }
 }
 
@@ -216,6 +509,44 @@ impl Path for HandlerPath {
         request: Request,
         _uri: Arc<String>,
     ) -> Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>> {
-        (self.handler)(request)
+        let handler = self
+            .method_handler(request.method())
+            .or(self
+                .handler
+                .as_ref());
+
+        if let Some(handler) = handler {
+            return handler(request);
+        }
+
+        let status = if request.method() == Method::OPTIONS {
+            StatusCode::NO_CONTENT
+        } else {
+            StatusCode::METHOD_NOT_ALLOWED
+        };
+
+        let allow = HeaderValue::from_str(&self.allow());
+
+        Box::pin(async move {
+            let allow = allow.map_err(|e| {
+                VetisError::VirtualHost(VirtualHostError::Handler(HandlerError::Handler(
+                    e.to_string(),
+                )))
+            })?;
+
+            let response = Response::builder()
+                .status(status)
+                .header(header::ALLOW, allow);
+
+            Ok(if status == StatusCode::NO_CONTENT {
+                response.body(HttpBody::from_bytes(&[]))
+            } else {
+                response.text(
+                    status
+                        .canonical_reason()
+                        .unwrap_or_default(),
+                )
+            })
+        })
     }
 }
@@ -1,12 +1,18 @@
+#[cfg(feature = "compression")]
+use crate::server::virtual_host::compression::Compression;
 use crate::{
     config::server::virtual_host::path::proxy::ProxyPathConfig,
     errors::{VetisError, VirtualHostError},
+    rt::sleep,
     server::{
         http::{Request, Response},
         virtual_host::path::{HostPath, Path},
     },
 };
-use deboa::{client::conn::pool::HttpConnectionPool, request::DeboaRequest, Client};
+use deboa::{
+    client::conn::pool::HttpConnectionPool, errors::DeboaError, request::DeboaRequest, Client,
+};
+use futures_util::future::{select, Either};
 use std::{
     future::Future,
     pin::Pin,
@@ -38,6 +44,8 @@ impl ProxyPath {
 impl From<ProxyPath> for HostPath {
     /// Convert proxy path to host path
     ///
+    /// Proxy paths configured with compression are wrapped by their compression middleware.
+    ///
     /// # Arguments
     ///
     /// * `value` - The proxy path to convert
@@ -46,6 +54,15 @@ impl From<ProxyPath> for HostPath {
     ///
     /// * `HostPath` - The host path
     fn from(value: ProxyPath) -> Self {
+        #[cfg(feature = "compression")]
+        if let Some(compression) = value
+            .config
+            .compression()
+            .clone()
+        {
+            return HostPath::Proxy(value).middleware(Compression::new(compression));
+        }
+
         HostPath::Proxy(value)
     }
 }
@@ -78,6 +95,9 @@ impl Path for ProxyPath {
         let (request_parts, request_body) = request.into_parts();
 
         let target = self.config.target();
+        let timeout = self
+            .config
+            .timeout();
 
         Box::pin(async move {
             let target_url = format!("{}{}", target, uri);
@@ -105,13 +125,25 @@ impl Path for ProxyPath {
                     .build()
             });
 
-            // TODO: Check errors and handle them properly by returning a proper response 500, 503 or 504
-            let response = client
-                .execute(deboa_request)
-                .await;
+            let execute = client.execute(deboa_request);
+            let response = match select(Box::pin(execute), Box::pin(sleep(timeout))).await {
+                Either::Left((response, _)) => response,
+                Either::Right(_) => {
+                    return Err(VetisError::VirtualHost(VirtualHostError::Timeout(format!(
+                        "{} did not answer within {:?}",
+                        target, timeout
+                    ))))
+                }
+            };
 
             let response = match response {
                 Ok(response) => response,
+                // The target cannot be reached, as opposed to answering badly
+                Err(DeboaError::Connection(e)) => {
+                    return Err(VetisError::VirtualHost(VirtualHostError::Unavailable(
+                        e.to_string(),
+                    )))
+                }
                 Err(e) => {
                     return Err(VetisError::VirtualHost(VirtualHostError::Proxy(e.to_string())))
                 }
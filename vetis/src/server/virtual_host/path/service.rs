@@ -0,0 +1,258 @@
+//! Tower services mounted as paths, and tower layers applied to paths
+
+use std::{
+    error::Error,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::future::poll_fn;
+use http_body_util::BodyExt;
+use hyper::body::Body;
+use hyper_body_utils::HttpBody;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{
+    errors::{HandlerError, VetisError, VirtualHostError},
+    server::{
+        http::{body::SyncBody, Request, Response},
+        virtual_host::{
+            path::{HostPath, Path},
+            router::PathPattern,
+        },
+    },
+};
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+type BoxedService = Box<
+    dyn Fn(
+            http::Request<HttpBody>,
+        ) -> Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Path serving requests with a tower service
+///
+/// Requests are passed to the service unchanged, with their full uri. The service is
+/// cloned for every request, as done by hyper and axum.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::server::virtual_host::path::service::ServicePath;
+///
+/// let api = axum::Router::new().route("/api/users", axum::routing::get(list_users));
+///
+/// virtual_host.add_path(ServicePath::new("/api", api));
+/// ```
+pub struct ServicePath {
+    uri: String,
+    pattern: Option<PathPattern>,
+    service: BoxedService,
+}
+
+impl ServicePath {
+    /// Create a new service path
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The uri prefix of the requests served by the service
+    /// * `service` - The tower service
+    ///
+    /// # Returns
+    ///
+    /// * `ServicePath` - The service path
+    pub fn new<S, B>(uri: &str, service: S) -> ServicePath
+    where
+        S: Service<http::Request<HttpBody>, Response = http::Response<B>>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<BoxError>,
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        ServicePath { uri: uri.to_string(), pattern: None, service: boxed(service) }
+    }
+
+    /// Wrap a path with a tower layer
+    ///
+    /// The layered path keeps the uri of the wrapped path, including its parameters.
+    pub(crate) fn layered<L, B>(path: HostPath, layer: L) -> ServicePath
+    where
+        L: Layer<PathService>,
+        L::Service: Service<http::Request<HttpBody>, Response = http::Response<B>>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <L::Service as Service<http::Request<HttpBody>>>::Future: Send + 'static,
+        <L::Service as Service<http::Request<HttpBody>>>::Error: Into<BoxError>,
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let uri = path
+            .uri()
+            .to_string();
+        let pattern = path
+            .pattern()
+            .cloned();
+        let service = layer.layer(PathService { path: Arc::new(path) });
+
+        ServicePath { uri, pattern, service: boxed(service) }
+    }
+
+    /// Returns the pattern of the wrapped handler path
+    pub(crate) fn pattern(&self) -> Option<&PathPattern> {
+        self.pattern
+            .as_ref()
+    }
+}
+
+impl From<ServicePath> for HostPath {
+    /// Convert service path to host path
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The service path to convert
+    ///
+    /// # Returns
+    ///
+    /// * `HostPath` - The host path
+    fn from(value: ServicePath) -> Self {
+        HostPath::Service(value)
+    }
+}
+
+impl Path for ServicePath {
+    /// Returns the uri of the service path
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The uri of the service path
+    fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Handles the request for the service path
+    ///
+    /// # Returns
+    ///
+    /// * `Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>>` - The response to the request
+    fn handle(
+        &self,
+        request: Request,
+        uri: Arc<String>,
+    ) -> Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>> {
+        let (mut parts, body) = request.into_parts();
+        parts
+            .extensions
+            .insert(TargetPath(uri));
+
+        (self.service)(http::Request::from_parts(parts, body))
+    }
+}
+
+/// Remainder of the request path once the path uri is stripped
+#[derive(Clone)]
+struct TargetPath(Arc<String>);
+
+/// Tower service handling requests with a vetis path
+///
+/// It is the inner service given to layers applied with `HostPath::layer`.
+#[derive(Clone)]
+pub struct PathService {
+    path: Arc<HostPath>,
+}
+
+impl Service<http::Request<HttpBody>> for PathService {
+    type Response = http::Response<HttpBody>;
+    type Error = VetisError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<HttpBody>) -> Self::Future {
+        let path = self.path.clone();
+
+        Box::pin(async move {
+            let uri = match request
+                .extensions()
+                .get::<TargetPath>()
+            {
+                Some(target) => target.0.clone(),
+                None => {
+                    let request_path = request.uri().path();
+                    let target = request_path
+                        .strip_prefix(path.uri())
+                        .unwrap_or(request_path);
+                    Arc::new(target.to_string())
+                }
+            };
+
+            let (parts, body) = request.into_parts();
+            let response = path
+                .handle(Request::from_parts(parts, body), uri)
+                .await?;
+
+            Ok(response.into_inner())
+        })
+    }
+}
+
+fn boxed<S, B>(service: S) -> BoxedService
+where
+    S: Service<http::Request<HttpBody>, Response = http::Response<B>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    B: Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    Box::new(move |request| {
+        let mut service = service.clone();
+        Box::pin(async move {
+            poll_fn(|cx| service.poll_ready(cx))
+                .await
+                .map_err(service_error)?;
+
+            let response = service
+                .call(request)
+                .await
+                .map_err(service_error)?;
+
+            let (parts, body) = response.into_parts();
+            let body = SyncBody::new(body)
+                .map_err(|e| std::io::Error::other(e.into()))
+                .boxed();
+
+            Ok(Response { inner: http::Response::from_parts(parts, HttpBody::Stream(body)) })
+        }) as Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send>>
+    })
+}
+
+/// Convert a service error, keeping errors raised by vetis paths
+fn service_error<E: Into<BoxError>>(error: E) -> VetisError {
+    match error
+        .into()
+        .downcast::<VetisError>()
+    {
+        Ok(error) => *error,
+        Err(error) => VetisError::VirtualHost(VirtualHostError::Handler(HandlerError::Handler(
+            error.to_string(),
+        ))),
+    }
+}
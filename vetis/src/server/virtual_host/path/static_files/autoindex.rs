@@ -0,0 +1,179 @@
+//! Directory listings of static paths
+
+use std::{cmp::Ordering, fmt::Write, path::PathBuf};
+
+use http::{header, HeaderValue};
+use log::error;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::{
+    errors::{FileError, VetisError, VirtualHostError},
+    rt::unblock,
+    server::http::{Request, Response},
+    utils::date::format_http_date,
+};
+
+/// Characters escaped in the links of a listing
+pub(super) const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Entry of a directory listing
+#[derive(Debug, Serialize)]
+struct Entry {
+    name: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: Option<u64>,
+    modified: Option<String>,
+}
+
+impl Entry {
+    fn is_directory(&self) -> bool {
+        self.kind == "directory"
+    }
+}
+
+/// Read the visible entries of a directory, directories first then by name
+fn read_entries(directory: PathBuf, extensions: Option<Regex>) -> std::io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let Ok(name) = entry
+            .file_name()
+            .into_string()
+        else {
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+
+        // Follow symbolic links, dangling ones are left out
+        let Ok(metadata) = std::fs::metadata(entry.path()) else {
+            continue;
+        };
+        let is_directory = metadata.is_dir();
+        if !is_directory
+            && extensions
+                .as_ref()
+                .is_some_and(|extensions| !extensions.is_match(&name))
+        {
+            continue;
+        }
+
+        entries.push(Entry {
+            name,
+            kind: if is_directory { "directory" } else { "file" },
+            size: (!is_directory).then_some(metadata.len()),
+            modified: metadata
+                .modified()
+                .ok()
+                .map(format_http_date),
+        });
+    }
+
+    entries.sort_by(|a, b| match (a.is_directory(), b.is_directory()) {
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        _ => a.name.cmp(&b.name),
+    });
+
+    Ok(entries)
+}
+
+pub(super) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_html(path: &str, entries: &[Entry]) -> String {
+    let title = escape_html(&percent_decode_str(path).decode_utf8_lossy());
+    let base = escape_html(path);
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Index of {title}</title>\n</head>\n<body>\n<h1>Index of {title}</h1>\n<table>\n<thead><tr><th>Name</th><th>Size</th><th>Last modified</th></tr></thead>\n<tbody>\n"
+    );
+
+    if path != "/" {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td>-</td><td>-</td></tr>\n");
+    }
+
+    for entry in entries {
+        let suffix = if entry.is_directory() { "/" } else { "" };
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"{base}{href}{suffix}\">{name}{suffix}</a></td><td>{size}</td><td>{modified}</td></tr>",
+            href = utf8_percent_encode(&entry.name, PATH_SEGMENT),
+            name = escape_html(&entry.name),
+            size = entry
+                .size
+                .map_or_else(|| "-".to_string(), |size| size.to_string()),
+            modified = entry
+                .modified
+                .as_deref()
+                .unwrap_or("-"),
+        );
+    }
+
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    html
+}
+
+/// Render the listing of a directory, as JSON when the client accepts it or HTML otherwise
+///
+/// # Arguments
+///
+/// * `request` - The request of the directory, its path is the base of the links
+/// * `directory` - The directory to list
+/// * `extensions` - Regular expression the listed file names must match
+///
+/// # Returns
+///
+/// * `Result<Response, VetisError>` - The listing
+pub(crate) async fn serve_listing(
+    request: &Request,
+    directory: PathBuf,
+    extensions: Option<Regex>,
+) -> Result<Response, VetisError> {
+    let path = request.uri().path();
+    let path = if path.ends_with('/') { path.to_string() } else { format!("{}/", path) };
+
+    let entries = unblock(move || read_entries(directory, extensions))
+        .await
+        .map_err(|e| {
+            error!("Error listing directory {}: {}", path, e);
+            VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound))
+        })?;
+
+    let wants_json = request
+        .headers()
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.contains("application/json"));
+
+    let response = Response::builder().header(header::VARY, HeaderValue::from_static("accept"));
+    if wants_json {
+        response.json(&entries)
+    } else {
+        Ok(response.html(&render_html(&path, &entries)))
+    }
+}
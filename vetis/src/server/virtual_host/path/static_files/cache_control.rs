@@ -0,0 +1,130 @@
+//! `Cache-Control` rules of static files
+
+use std::time::{Duration, SystemTime};
+
+use http::{header, HeaderMap, HeaderValue};
+use log::error;
+use regex::Regex;
+
+use crate::{
+    config::server::virtual_host::path::static_files::CacheRule, utils::date::format_http_date,
+};
+
+/// Rule with its compiled pattern
+struct CompiledRule {
+    extensions: Vec<String>,
+    pattern: Option<Regex>,
+    cache_control: HeaderValue,
+}
+
+impl CompiledRule {
+    fn matches(&self, path: &str) -> bool {
+        let extension = path
+            .rsplit_once('/')
+            .map_or(path, |(_, filename)| filename)
+            .rsplit_once('.')
+            .map(|(_, extension)| extension);
+
+        let has_extension = extension.is_some_and(|extension| {
+            self.extensions
+                .iter()
+                .any(|candidate| {
+                    candidate
+                        .trim_start_matches('.')
+                        .eq_ignore_ascii_case(extension)
+                })
+        });
+
+        has_extension
+            || self
+                .pattern
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_match(path))
+    }
+}
+
+/// `Cache-Control` rules of a static path, the first matching rule applies
+pub(crate) struct CacheRules(Vec<CompiledRule>);
+
+impl CacheRules {
+    /// Compile the rules of a static path, invalid rules are left out
+    pub(crate) fn new(rules: &[CacheRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| {
+                let pattern = match rule.pattern() {
+                    Some(pattern) => match Regex::new(pattern) {
+                        Ok(pattern) => Some(pattern),
+                        Err(e) => {
+                            error!("Invalid cache rule pattern {}: {}", pattern, e);
+                            return None;
+                        }
+                    },
+                    None => None,
+                };
+                let Ok(cache_control) = HeaderValue::from_str(rule.cache_control()) else {
+                    error!("Invalid Cache-Control value {}", rule.cache_control());
+                    return None;
+                };
+
+                Some(CompiledRule {
+                    extensions: rule
+                        .extensions()
+                        .to_vec(),
+                    pattern,
+                    cache_control,
+                })
+            })
+            .collect();
+
+        CacheRules(rules)
+    }
+
+    /// Add the `Cache-Control` header of a file, and `Expires` when it has a `max-age`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file within the directory of the static path, such as `/index.html`
+    /// * `headers` - The headers of the response
+    pub(crate) fn insert_headers(&self, path: &str, headers: &mut HeaderMap) {
+        let Some(rule) = self
+            .0
+            .iter()
+            .find(|rule| rule.matches(path))
+        else {
+            return;
+        };
+
+        headers.insert(
+            header::CACHE_CONTROL,
+            rule.cache_control
+                .clone(),
+        );
+
+        let max_age = rule
+            .cache_control
+            .to_str()
+            .ok()
+            .and_then(|cache_control| {
+                cache_control
+                    .split(',')
+                    .filter_map(|directive| {
+                        directive
+                            .trim()
+                            .strip_prefix("max-age=")
+                    })
+                    .find_map(|seconds| {
+                        seconds
+                            .parse::<u64>()
+                            .ok()
+                    })
+            });
+        if let Some(expires) =
+            max_age.and_then(|max_age| SystemTime::now().checked_add(Duration::from_secs(max_age)))
+        {
+            if let Ok(expires) = HeaderValue::from_str(&format_http_date(expires)) {
+                headers.insert(header::EXPIRES, expires);
+            }
+        }
+    }
+}
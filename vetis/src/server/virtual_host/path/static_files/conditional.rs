@@ -0,0 +1,215 @@
+//! Conditional requests of static files (RFC 9110, section 13)
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use hyper_body_utils::HttpBody;
+
+use crate::{
+    server::http::{Request, Response},
+    utils::date::{format_http_date, parse_http_date},
+};
+
+/// Validators of a file version: its entity tag and modification date
+pub(crate) struct Validators {
+    etag: String,
+    last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    /// Build the validators of a file from its size and modification date
+    ///
+    /// The entity tag is weak when the file was modified within the last second, as it
+    /// could be modified again without its modification date changing.
+    pub(crate) fn new(len: u64, modified: Option<SystemTime>) -> Self {
+        let since_epoch = modified
+            .and_then(|modified| {
+                modified
+                    .duration_since(UNIX_EPOCH)
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        let is_recent = modified
+            .and_then(|modified| {
+                SystemTime::now()
+                    .duration_since(modified)
+                    .ok()
+            })
+            .map_or(true, |age| age < Duration::from_secs(1));
+
+        let tag =
+            format!("\"{:x}.{:x}-{:x}\"", since_epoch.as_secs(), since_epoch.subsec_nanos(), len);
+        let etag = if is_recent { format!("W/{}", tag) } else { tag };
+
+        // HTTP dates have a resolution of one second
+        let last_modified =
+            modified.map(|_| UNIX_EPOCH + Duration::from_secs(since_epoch.as_secs()));
+
+        Self { etag, last_modified }
+    }
+
+    /// Distinguish the entity tag of a precompressed variant by its content coding
+    pub(crate) fn encoded(mut self, encoding: &str) -> Self {
+        if let Some(tag) = self
+            .etag
+            .strip_suffix('"')
+        {
+            self.etag = format!("{}-{}\"", tag, encoding);
+        }
+        self
+    }
+
+    /// Returns the entity tag, such as `"5f5e100.0-2a"`
+    #[cfg(feature = "webdav")]
+    pub(crate) fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    /// Add the `ETag` and `Last-Modified` headers
+    pub(crate) fn insert_headers(&self, headers: &mut HeaderMap) {
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Some(last_modified) = self.last_modified {
+            if let Ok(date) = HeaderValue::from_str(&format_http_date(last_modified)) {
+                headers.insert(header::LAST_MODIFIED, date);
+            }
+        }
+    }
+
+    fn is_modified_since(&self, date: SystemTime) -> bool {
+        self.last_modified
+            .map_or(true, |last_modified| last_modified > date)
+    }
+}
+
+/// Outcome of the preconditions of a request
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Precondition {
+    /// Serve the file
+    Proceed,
+    /// Answer with `304 Not Modified`
+    NotModified,
+    /// Answer with `412 Precondition Failed`
+    Failed,
+}
+
+/// Strip the weakness indicator of an entity tag
+fn opaque_tag(etag: &str) -> &str {
+    etag.strip_prefix("W/")
+        .unwrap_or(etag)
+}
+
+/// Strong comparison, which requires both entity tags to be strong
+fn strong_eq(candidate: &str, etag: &str) -> bool {
+    !candidate.starts_with("W/") && !etag.starts_with("W/") && candidate == etag
+}
+
+/// Whether an `If-Match` or `If-None-Match` list matches the entity tag, weak comparison
+/// only compares the opaque tags
+fn matches(headers: &HeaderMap, name: header::HeaderName, etag: &str, strong: bool) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| {
+            if candidate == "*" {
+                return true;
+            }
+            if strong {
+                strong_eq(candidate, etag)
+            } else {
+                opaque_tag(candidate) == opaque_tag(etag)
+            }
+        })
+}
+
+fn header_date(headers: &HeaderMap, name: header::HeaderName) -> Option<SystemTime> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_http_date)
+}
+
+/// Evaluate `If-Match`, `If-Unmodified-Since`, `If-None-Match` and `If-Modified-Since`,
+/// in the order of RFC 9110, section 13.2.2
+pub(crate) fn evaluate(request: &Request, validators: &Validators) -> Precondition {
+    let headers = request.headers();
+    let is_read = matches!(*request.method(), Method::GET | Method::HEAD);
+
+    if headers.contains_key(header::IF_MATCH) {
+        if !matches(headers, header::IF_MATCH, &validators.etag, true) {
+            return Precondition::Failed;
+        }
+    } else if let Some(date) = header_date(headers, header::IF_UNMODIFIED_SINCE) {
+        if validators.is_modified_since(date) {
+            return Precondition::Failed;
+        }
+    }
+
+    if headers.contains_key(header::IF_NONE_MATCH) {
+        if matches(headers, header::IF_NONE_MATCH, &validators.etag, false) {
+            return if is_read { Precondition::NotModified } else { Precondition::Failed };
+        }
+    } else if is_read {
+        if let Some(date) = header_date(headers, header::IF_MODIFIED_SINCE) {
+            if !validators.is_modified_since(date) {
+                return Precondition::NotModified;
+            }
+        }
+    }
+
+    Precondition::Proceed
+}
+
+/// Whether the `Range` header applies, which requires `If-Range` to match the current
+/// version of the file when present
+pub(crate) fn if_range_matches(request: &Request, validators: &Validators) -> bool {
+    let Some(if_range) = request
+        .headers()
+        .get(header::IF_RANGE)
+    else {
+        return true;
+    };
+    let Ok(if_range) = if_range.to_str() else {
+        return false;
+    };
+
+    let if_range = if_range.trim();
+    if if_range.starts_with('"') || if_range.starts_with("W/") {
+        return strong_eq(if_range, &validators.etag);
+    }
+
+    // Dates only match an exact modification date
+    parse_http_date(if_range)
+        .zip(validators.last_modified)
+        .is_some_and(|(date, last_modified)| date == last_modified)
+}
+
+/// Build the response of a failed precondition
+///
+/// # Returns
+///
+/// * `Response` - `304 Not Modified` with the file validators, or `412 Precondition Failed`
+pub(crate) fn precondition_response(
+    precondition: Precondition,
+    validators: &Validators,
+) -> Response {
+    let status = match precondition {
+        Precondition::NotModified => StatusCode::NOT_MODIFIED,
+        _ => StatusCode::PRECONDITION_FAILED,
+    };
+
+    let mut headers = HeaderMap::new();
+    if status == StatusCode::NOT_MODIFIED {
+        validators.insert_headers(&mut headers);
+    }
+
+    Response::builder()
+        .status(status)
+        .headers(headers)
+        .body(HttpBody::from_bytes(&[]))
+}
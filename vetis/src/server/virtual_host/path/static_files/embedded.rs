@@ -0,0 +1,156 @@
+//! Assets compiled into the binary, served by static paths like files of a directory
+//!
+//! Tables of assets are usually generated with the `embed_assets!` macro of `vetis-macros`,
+//! which walks a directory at compile time.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Asset compiled into the binary
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedAsset {
+    path: &'static str,
+    contents: &'static [u8],
+    modified: Option<Duration>,
+}
+
+impl EmbeddedAsset {
+    /// Create an asset
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the asset, such as `/assets/app.js`
+    /// * `contents` - The contents of the asset
+    /// * `modified` - The modification date of the asset, since the Unix epoch
+    ///
+    /// # Returns
+    ///
+    /// * `EmbeddedAsset` - The asset
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// EmbeddedAsset::new("/index.html", include_bytes!("../ui/index.html"), None)
+    /// ```
+    pub const fn new(
+        path: &'static str,
+        contents: &'static [u8],
+        modified: Option<Duration>,
+    ) -> Self {
+        EmbeddedAsset { path, contents, modified }
+    }
+
+    /// Returns the path of the asset
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The path of the asset
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// Returns the contents of the asset
+    ///
+    /// # Returns
+    ///
+    /// * `&'static [u8]` - The contents of the asset
+    pub fn contents(&self) -> &'static [u8] {
+        self.contents
+    }
+
+    /// Returns the modification date of the asset
+    ///
+    /// # Returns
+    ///
+    /// * `Option<SystemTime>` - The modification date, when it is known
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+            .and_then(|modified| UNIX_EPOCH.checked_add(modified))
+    }
+}
+
+/// Table of assets compiled into the binary
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedAssets {
+    assets: &'static [EmbeddedAsset],
+}
+
+impl EmbeddedAssets {
+    /// Create a table of assets
+    ///
+    /// # Arguments
+    ///
+    /// * `assets` - The assets, in any order
+    ///
+    /// # Returns
+    ///
+    /// * `EmbeddedAssets` - The table of assets
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// static ASSETS: EmbeddedAssets = EmbeddedAssets::new(&[
+    ///     EmbeddedAsset::new("/index.html", include_bytes!("../ui/index.html"), None),
+    ///     EmbeddedAsset::new("/app.js", include_bytes!("../ui/app.js"), None),
+    /// ]);
+    /// ```
+    pub const fn new(assets: &'static [EmbeddedAsset]) -> Self {
+        EmbeddedAssets { assets }
+    }
+
+    /// Returns the assets
+    ///
+    /// # Returns
+    ///
+    /// * `&'static [EmbeddedAsset]` - The assets
+    pub fn assets(&self) -> &'static [EmbeddedAsset] {
+        self.assets
+    }
+}
+
+/// Assets of a static path by path, with the directories leading to them
+pub(crate) struct EmbeddedFiles {
+    files: HashMap<String, &'static EmbeddedAsset>,
+    directories: HashSet<String>,
+}
+
+impl EmbeddedFiles {
+    /// Index a table of assets, paths are made absolute and later assets replace earlier ones
+    pub(crate) fn new(assets: &EmbeddedAssets) -> Self {
+        let mut files = HashMap::new();
+        let mut directories = HashSet::new();
+
+        for asset in assets.assets() {
+            let path = format!(
+                "/{}",
+                asset
+                    .path
+                    .trim_start_matches('/')
+            );
+
+            let mut directory = path.as_str();
+            while let Some((parent, _)) = directory.rsplit_once('/') {
+                directories.insert(parent.to_string());
+                directory = parent;
+            }
+            files.insert(path, asset);
+        }
+
+        EmbeddedFiles { files, directories }
+    }
+
+    /// Returns the asset of a path, such as `/assets/app.js`
+    pub(crate) fn get(&self, path: &str) -> Option<&'static EmbeddedAsset> {
+        self.files
+            .get(path)
+            .copied()
+    }
+
+    /// Whether assets are below a path, the root being the empty path
+    pub(crate) fn is_dir(&self, path: &str) -> bool {
+        self.directories
+            .contains(path.trim_end_matches('/'))
+    }
+}
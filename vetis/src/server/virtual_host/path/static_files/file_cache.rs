@@ -0,0 +1,350 @@
+//! Cache of the files served by a static path
+//!
+//! Files are kept open, or in memory when small enough, and shared by the responses serving
+//! them. Open files are read at explicit offsets, so concurrent responses never move each
+//! other's position, and are closed once evicted and no longer read by a response.
+
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
+
+use bytes::Bytes;
+use log::error;
+use lru::LruCache;
+
+use crate::{
+    config::server::virtual_host::path::static_files::FileCacheConfig,
+    errors::{FileError, VetisError, VirtualHostError},
+    VetisRwLock,
+};
+
+#[cfg(target_os = "linux")]
+use super::watcher::Watcher;
+
+/// Contents of a cached file
+#[derive(Clone)]
+pub(crate) enum FileContent {
+    /// An open file, read at explicit offsets
+    File(Arc<std::fs::File>),
+    /// The whole file, kept in memory
+    Memory(Bytes),
+}
+
+impl FileContent {
+    /// Read up to `len` bytes at `offset`, fewer bytes are returned at the end of the file
+    pub(crate) async fn read(&self, offset: u64, len: usize) -> std::io::Result<Bytes> {
+        match self {
+            FileContent::Memory(bytes) => {
+                let start = usize::try_from(offset)
+                    .unwrap_or(usize::MAX)
+                    .min(bytes.len());
+                let end = start
+                    .saturating_add(len)
+                    .min(bytes.len());
+                Ok(bytes.slice(start..end))
+            }
+            FileContent::File(file) => {
+                let file = file.clone();
+                crate::rt::unblock(move || {
+                    let mut chunk = vec![0; len];
+                    let read = read_at(&file, &mut chunk, offset)?;
+                    chunk.truncate(read);
+                    Ok(Bytes::from(chunk))
+                })
+                .await
+            }
+        }
+    }
+}
+
+/// Read at an offset, without moving the position of the file
+#[cfg(unix)]
+fn read_at(file: &std::fs::File, buffer: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buffer, offset)
+}
+
+/// Read at an offset, the position of the file is not used by other reads
+#[cfg(windows)]
+fn read_at(file: &std::fs::File, buffer: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buffer, offset)
+}
+
+/// File of the cache, with the size and modification date it was opened with
+pub(crate) struct CachedFile {
+    content: FileContent,
+    len: u64,
+    modified: Option<SystemTime>,
+    cached_at: Instant,
+}
+
+impl CachedFile {
+    /// Open a file, reading it in memory when it is at most `max_content_size` bytes
+    fn open(path: &Path, max_content_size: u64) -> std::io::Result<CachedFile> {
+        let mut file = std::fs::File::open(path)?;
+        let metadata = file.metadata()?;
+
+        let mut content = None;
+        if max_content_size > 0 && metadata.len() <= max_content_size {
+            let mut bytes = Vec::with_capacity(metadata.len() as usize);
+            file.read_to_end(&mut bytes)?;
+            // A file written meanwhile stays open, as its contents may not match its metadata
+            if bytes.len() as u64 == metadata.len() {
+                content = Some(FileContent::Memory(Bytes::from(bytes)));
+            }
+        }
+        let content = content.unwrap_or_else(|| FileContent::File(Arc::new(file)));
+
+        Ok(CachedFile {
+            content,
+            len: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok(),
+            cached_at: Instant::now(),
+        })
+    }
+
+    /// Wrap contents already in memory, such as an embedded asset
+    pub(crate) fn memory(content: Bytes, modified: Option<SystemTime>) -> CachedFile {
+        CachedFile {
+            len: content.len() as u64,
+            content: FileContent::Memory(content),
+            modified,
+            cached_at: Instant::now(),
+        }
+    }
+
+    /// Returns the contents of the file
+    pub(crate) fn content(&self) -> FileContent {
+        self.content.clone()
+    }
+
+    /// Returns the size of the file
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns the modification date of the file
+    pub(crate) fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// Size of the contents kept in memory
+    fn content_size(&self) -> u64 {
+        match &self.content {
+            FileContent::Memory(bytes) => bytes.len() as u64,
+            FileContent::File(_) => 0,
+        }
+    }
+}
+
+/// Cached files, by path
+pub(crate) struct Entries {
+    files: LruCache<PathBuf, Arc<CachedFile>>,
+    content_size: u64,
+    /// Incremented by each invalidation, files opened meanwhile are not cached
+    generation: u64,
+}
+
+impl Entries {
+    fn remove(&mut self, path: &Path) {
+        if let Some(evicted) = self.files.pop(path) {
+            self.content_size -= evicted.content_size();
+        }
+    }
+
+    fn insert(&mut self, path: PathBuf, cached: Arc<CachedFile>, content_capacity: u64) {
+        self.remove(&path);
+
+        // Make room for the contents, evicting the least recently used contents first
+        let size = cached.content_size();
+        if self.content_size + size > content_capacity {
+            let mut freed = 0;
+            let evicted: Vec<PathBuf> = self
+                .files
+                .iter()
+                .rev()
+                .filter(|(_, file)| file.content_size() > 0)
+                .take_while(|(_, file)| {
+                    let enough = self.content_size - freed + size <= content_capacity;
+                    freed += file.content_size();
+                    !enough
+                })
+                .map(|(path, _)| path.clone())
+                .collect();
+            for path in evicted {
+                self.remove(&path);
+            }
+        }
+
+        if let Some((_, evicted)) = self
+            .files
+            .push(path, cached)
+        {
+            self.content_size -= evicted.content_size();
+        }
+        self.content_size += size;
+    }
+
+    /// Evict a path and the files below it
+    pub(crate) fn invalidate(&mut self, path: &Path) {
+        self.generation += 1;
+
+        let evicted: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(file, _)| file.starts_with(path))
+            .map(|(file, _)| file.clone())
+            .collect();
+        for file in evicted {
+            self.remove(&file);
+        }
+    }
+
+    /// Evict every file
+    pub(crate) fn clear(&mut self) {
+        self.generation += 1;
+        self.files.clear();
+        self.content_size = 0;
+    }
+}
+
+/// Cache of the files of a static path
+pub(crate) struct FileCache {
+    config: FileCacheConfig,
+    #[cfg(target_os = "linux")]
+    directory: PathBuf,
+    entries: Arc<VetisRwLock<Entries>>,
+    #[cfg(target_os = "linux")]
+    watcher: Option<Arc<Watcher>>,
+}
+
+impl FileCache {
+    /// Create the cache of the files of a directory, watching it for changes when enabled
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    pub(crate) fn new(config: &FileCacheConfig, directory: &Path) -> FileCache {
+        let capacity =
+            std::num::NonZeroUsize::new(config.capacity()).unwrap_or(std::num::NonZeroUsize::MIN);
+        let entries = Arc::new(VetisRwLock::new(Entries {
+            files: LruCache::new(capacity),
+            content_size: 0,
+            generation: 0,
+        }));
+
+        #[cfg(target_os = "linux")]
+        let watcher = if config.watch() {
+            match Watcher::new(Arc::downgrade(&entries)) {
+                Ok(watcher) => Some(Arc::new(watcher)),
+                Err(e) => {
+                    error!("Error watching directory {}: {}", directory.display(), e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        FileCache {
+            config: config.clone(),
+            #[cfg(target_os = "linux")]
+            directory: directory.to_path_buf(),
+            entries,
+            #[cfg(target_os = "linux")]
+            watcher,
+        }
+    }
+
+    /// Evict a path and the files below it, such as once it was written
+    #[cfg(feature = "webdav")]
+    pub(crate) async fn invalidate(&self, path: &Path) {
+        self.entries
+            .write()
+            .await
+            .invalidate(path);
+    }
+
+    /// Open a file from the cache, or from disk when it is not cached or has expired
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file path
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Arc<CachedFile>, VetisError>` - The file
+    pub(crate) async fn open(&self, path: &Path) -> Result<Arc<CachedFile>, VetisError> {
+        let generation = {
+            let mut entries = self
+                .entries
+                .write()
+                .await;
+            if let Some(cached) = entries
+                .files
+                .get(path)
+            {
+                let expired = self
+                    .config
+                    .ttl()
+                    .is_some_and(|ttl| {
+                        cached
+                            .cached_at
+                            .elapsed()
+                            >= ttl
+                    });
+                if !expired {
+                    return Ok(cached.clone());
+                }
+            }
+            entries.remove(path);
+            entries.generation
+        };
+
+        let file_path = path.to_path_buf();
+        let max_content_size = self
+            .config
+            .max_content_size();
+        #[cfg(target_os = "linux")]
+        let watch = self
+            .watcher
+            .clone()
+            .map(|watcher| {
+                (
+                    watcher,
+                    self.directory
+                        .clone(),
+                )
+            });
+        let cached = crate::rt::unblock(move || {
+            // Watched before being opened, so changes made meanwhile are not missed
+            #[cfg(target_os = "linux")]
+            if let Some((watcher, directory)) = watch {
+                watcher.watch(&directory, &file_path);
+            }
+            CachedFile::open(&file_path, max_content_size)
+        })
+        .await
+        .map_err(|e| {
+            error!("Error opening file {}: {}", path.display(), e);
+            VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound))
+        })?;
+        let cached = Arc::new(cached);
+
+        let mut entries = self
+            .entries
+            .write()
+            .await;
+        if entries.generation == generation {
+            entries.insert(
+                path.to_path_buf(),
+                cached.clone(),
+                self.config
+                    .content_capacity(),
+            );
+        }
+
+        Ok(cached)
+    }
+}
@@ -0,0 +1,80 @@
+//! Content types of static files
+
+use std::collections::HashMap;
+
+use http::HeaderValue;
+
+/// Content type of files of unknown type
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Non `text/*` types carrying text, which get a charset
+const TEXT_TYPES: [&str; 4] =
+    ["application/javascript", "application/xml", "application/xhtml+xml", "image/svg+xml"];
+
+/// Whether a content type carries text
+fn is_text(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim();
+
+    essence
+        .get(..5)
+        .is_some_and(|kind| kind.eq_ignore_ascii_case("text/"))
+        || TEXT_TYPES
+            .iter()
+            .any(|text_type| essence.eq_ignore_ascii_case(text_type))
+}
+
+/// Content type of a file, from the mapping of its extension or guessed from its name
+///
+/// # Arguments
+///
+/// * `file_path` - The file path
+/// * `mime_types` - Content types by extension, taking priority over the built-in ones
+/// * `charset` - The charset added to text types, unless they already have one
+///
+/// # Returns
+///
+/// * `Option<HeaderValue>` - The content type, `application/octet-stream` for unknown files
+pub(crate) fn content_type(
+    file_path: &std::path::Path,
+    mime_types: &HashMap<String, String>,
+    charset: Option<&str>,
+) -> Option<HeaderValue> {
+    let filename = file_path
+        .file_name()
+        .and_then(|filename| filename.to_str())?;
+
+    let mapped = filename
+        .rsplit_once('.')
+        .and_then(|(_, extension)| {
+            mime_types
+                .iter()
+                .find(|(mapped, _)| {
+                    mapped
+                        .trim_start_matches('.')
+                        .eq_ignore_ascii_case(extension)
+                })
+        })
+        .map(|(_, content_type)| content_type.clone());
+
+    let content_type = mapped
+        .or_else(|| minimime::lookup_by_filename(filename).map(|mime_type| mime_type.content_type))
+        .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_string());
+
+    let content_type = match charset {
+        Some(charset)
+            if is_text(&content_type)
+                && !content_type
+                    .to_ascii_lowercase()
+                    .contains("charset=") =>
+        {
+            format!("{}; charset={}", content_type, charset)
+        }
+        _ => content_type,
+    };
+
+    HeaderValue::from_str(&content_type).ok()
+}
@@ -1,40 +1,73 @@
-use filedescriptor::{AsRawFileDescriptor, FileDescriptor, RawFileDescriptor};
+mod autoindex;
+mod cache_control;
+mod conditional;
+pub mod embedded;
+mod file_cache;
+mod mime;
+mod precompressed;
+mod range;
+#[cfg(target_os = "linux")]
+mod watcher;
+#[cfg(feature = "webdav")]
+mod webdav;
+
 use hyper_body_utils::HttpBody;
-use log::error;
 
-#[cfg(feature = "smol-rt")]
-use futures_lite::AsyncSeekExt;
-use lru::LruCache;
-#[cfg(feature = "tokio-rt")]
-use tokio::io::AsyncSeekExt;
+use cache_control::CacheRules;
+use conditional::{Precondition, Validators};
+use embedded::{EmbeddedAssets, EmbeddedFiles};
+#[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "http1"))]
+use file_cache::FileContent;
+use file_cache::{CachedFile, FileCache};
+use range::Ranges;
+#[cfg(feature = "webdav")]
+use webdav::WebDav;
 
 use crate::{
-    config::server::virtual_host::path::static_files::StaticPathConfig,
+    config::server::virtual_host::path::static_files::{
+        try_files_status, StaticPathConfig, SymlinkPolicy, DEFAULT_CHARSET,
+    },
     errors::{FileError, VetisError, VirtualHostError},
+    rt::unblock,
     server::{
-        http::{static_response, Request, Response},
+        http::{Request, Response},
         virtual_host::path::{HostPath, Path},
     },
-    VetisFile, VetisRwLock,
+    utils::path::{decode_path, normalize_path},
 };
+use bytes::Bytes;
 use http::{HeaderMap, HeaderValue};
-use std::{future::Future, num::NonZeroUsize, path::PathBuf, pin::Pin, sync::Arc};
-
-#[cfg(feature = "auth")]
-use crate::server::virtual_host::path::auth::Auth;
-
-pub(crate) type VetisFileCache = Arc<VetisRwLock<LruCache<String, RawFileDescriptor>>>;
+use std::{collections::HashMap, future::Future, path::PathBuf, pin::Pin, sync::Arc};
+
+#[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "http1"))]
+use crate::server::conn::sendfile::SendFile;
+#[cfg(feature = "compression")]
+use crate::server::virtual_host::compression::Compression;
+
+/// Files served by a static path
+enum Source {
+    /// Files of the directory of the static path
+    Directory(FileCache),
+    /// Assets compiled into the binary
+    Embedded(EmbeddedFiles),
+}
 
 /// Static path
 pub struct StaticPath {
-    config: StaticPathConfig,
+    config: Box<StaticPathConfig>,
     index_file: Option<String>,
-    file_cache: VetisFileCache,
+    source: Source,
+    cache_rules: CacheRules,
+    #[cfg(feature = "webdav")]
+    webdav: Option<WebDav>,
 }
 
 impl StaticPath {
     /// Create a new static path with provided configuration
     ///
+    /// With WebDAV settings, the directory is writable by the WebDAV methods, authenticated
+    /// by the auth middleware of the static path.
+    ///
     /// # Arguments
     ///
     /// * `config` - The configuration for the static path
@@ -43,262 +76,488 @@ impl StaticPath {
     ///
     /// * `StaticPath` - The static path
     pub fn new(config: StaticPathConfig) -> StaticPath {
-        let file_cache = Arc::new(VetisRwLock::new(LruCache::new(NonZeroUsize::new(100).unwrap())));
-        if let Some(index_files) = config.index_files() {
-            let directory = PathBuf::from(config.directory());
-            if let Some(index_file) = index_files
-                .iter()
-                .find(|index_file| {
-                    directory
-                        .join(index_file)
-                        .exists()
-                })
-            {
-                return StaticPath {
-                    config: config.clone(),
-                    index_file: Some(index_file.to_string()),
-                    file_cache,
-                };
-            }
+        let file_cache =
+            FileCache::new(config.file_cache(), std::path::Path::new(config.directory()));
+        #[cfg(feature = "webdav")]
+        let webdav = config
+            .webdav()
+            .clone()
+            .map(WebDav::new);
+
+        #[allow(unused_mut)]
+        let mut static_path = Self::with_source(config, Source::Directory(file_cache));
+        #[cfg(feature = "webdav")]
+        {
+            static_path.webdav = webdav;
         }
-        StaticPath { config, index_file: None, file_cache }
+        static_path
     }
 
-    async fn cache_file(&self, file_path: &std::path::Path) -> Result<VetisFile, VetisError> {
-        let path = file_path
-            .display()
-            .to_string();
-        let lock = self
-            .file_cache
-            .clone();
+    /// Create a static path serving assets compiled into the binary
+    ///
+    /// Assets are served like the files of a directory, with the same content types,
+    /// validators, ranges, precompressed variants and index files. The directory, symlink,
+    /// file cache, sendfile, autoindex and WebDAV settings of the configuration do not apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration for the static path
+    /// * `assets` - The assets, such as a table generated by `vetis_macros::embed_assets!`
+    ///
+    /// # Returns
+    ///
+    /// * `StaticPath` - The static path
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// static ASSETS: EmbeddedAssets = vetis_macros::embed_assets!("ui/dist");
+    ///
+    /// let path = StaticPath::embedded(
+    ///     StaticPathConfig::builder()
+    ///         .uri("/")
+    ///         .index_files(vec!["index.html".to_string()])
+    ///         .build()?,
+    ///     &ASSETS,
+    /// );
+    /// ```
+    pub fn embedded(config: StaticPathConfig, assets: &EmbeddedAssets) -> StaticPath {
+        Self::with_source(config, Source::Embedded(EmbeddedFiles::new(assets)))
+    }
 
-        let open_file = move || {
-            #[cfg(feature = "tokio-rt")]
-            let mut lock = lock.blocking_write();
-
-            #[cfg(feature = "smol-rt")]
-            let mut lock = lock.write_blocking();
-
-            if let Some(raw_fd) = lock.get(&path) {
-                FileDescriptor::dup(raw_fd)
-            } else {
-                let file = std::fs::File::open(path.clone());
-                match file {
-                    Ok(file) => {
-                        let raw_fd =
-                            lock.get_or_insert(path.clone(), || file.as_raw_file_descriptor());
-                        FileDescriptor::dup(raw_fd)
-                    }
-                    Err(e) => {
-                        error!("Error opening file {}: {}", path, e);
-                        Err(filedescriptor::Error::Io(e))
-                    }
-                }
-            }
+    fn with_source(config: StaticPathConfig, source: Source) -> StaticPath {
+        let cache_rules = CacheRules::new(config.cache_rules());
+        let mut static_path = StaticPath {
+            config: Box::new(config),
+            index_file: None,
+            source,
+            cache_rules,
+            #[cfg(feature = "webdav")]
+            webdav: None,
         };
 
-        #[cfg(feature = "tokio-rt")]
-        let task = {
-            let task = tokio::task::spawn_blocking(open_file);
-            match task.await {
-                Ok(result) => result,
-                Err(e) => Err(filedescriptor::Error::Io(e.into())),
-            }
-        };
+        if let Some(index_files) = static_path
+            .config
+            .index_files()
+        {
+            let directory = PathBuf::from(
+                static_path
+                    .config
+                    .directory(),
+            );
+            static_path.index_file = index_files
+                .iter()
+                .find(|index_file| static_path.exists(&directory.join(index_file)))
+                .cloned();
+        }
+        static_path
+    }
 
-        #[cfg(feature = "smol-rt")]
-        let task = smol::unblock(open_file).await;
+    /// Whether a located path is a file
+    fn is_file(&self, path: &std::path::Path) -> bool {
+        match &self.source {
+            Source::Directory(_) => path.is_file(),
+            Source::Embedded(files) => self
+                .relative_path(path)
+                .is_some_and(|path| {
+                    files
+                        .get(&path)
+                        .is_some()
+                }),
+        }
+    }
 
-        match task {
-            Ok(raw_fd) => {
-                let file = raw_fd.as_file();
-                match file {
-                    Ok(file) => {
-                        #[cfg(feature = "tokio-rt")]
-                        let file = VetisFile::from_std(file);
+    /// Whether a located path is a directory
+    fn is_dir(&self, path: &std::path::Path) -> bool {
+        match &self.source {
+            Source::Directory(_) => path.is_dir(),
+            Source::Embedded(files) => self
+                .relative_path(path)
+                .is_some_and(|path| files.is_dir(&path)),
+        }
+    }
 
-                        #[cfg(feature = "smol-rt")]
-                        let file = VetisFile::from(file);
+    /// Whether a located path exists
+    fn exists(&self, path: &std::path::Path) -> bool {
+        match &self.source {
+            Source::Directory(_) => path.exists(),
+            Source::Embedded(_) => self.is_file(path) || self.is_dir(path),
+        }
+    }
 
-                        Ok(file)
-                    }
-                    Err(e) => {
-                        error!("Error opening file {}", e);
-                        Err(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)))
-                    }
-                }
+    /// Open a located file, from the file cache or the embedded assets
+    async fn open(&self, path: &std::path::Path) -> Result<Arc<CachedFile>, VetisError> {
+        match &self.source {
+            Source::Directory(file_cache) => {
+                file_cache
+                    .open(path)
+                    .await
             }
-            Err(e) => {
-                error!("Error opening file: {}", e);
-                Err(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)))
+            Source::Embedded(files) => {
+                let asset = self
+                    .relative_path(path)
+                    .and_then(|path| files.get(&path))
+                    .ok_or(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)))?;
+                Ok(Arc::new(CachedFile::memory(
+                    Bytes::from_static(asset.contents()),
+                    asset.modified(),
+                )))
             }
         }
     }
 
     async fn serve_file(
         &self,
+        request: &Request,
         file_path: &std::path::Path,
-        range: Option<&str>,
     ) -> Result<Response, VetisError> {
-        let mut file = self
-            .cache_file(file_path)
-            .await?;
-
-        let filesize = match file
-            .metadata()
-            .await
+        // Precompressed variants are served in place of the file, as representations of their own
+        let variant = if self
+            .config
+            .precompressed()
         {
-            Ok(metadata) => metadata.len(),
-            Err(e) => {
-                error!("Error getting metadata for file {}: {}", file_path.display(), e);
-                return Err(VetisError::VirtualHost(VirtualHostError::File(
-                    FileError::InvalidMetadata,
-                )));
-            }
+            precompressed::select(
+                request
+                    .headers()
+                    .get(http::header::ACCEPT_ENCODING)
+                    .and_then(|value| value.to_str().ok()),
+                file_path,
+                |variant| self.is_file(variant),
+            )
+        } else {
+            None
+        };
+        let (served_path, encoding) = match &variant {
+            Some((variant_path, encoding)) => (variant_path.as_path(), Some(*encoding)),
+            None => (file_path, None),
         };
 
-        if let Some(range) = range {
-            let range_info = match range
-                .split_once("=")
-                .ok_or(VetisError::VirtualHost(VirtualHostError::File(FileError::InvalidRange)))
-            {
-                Ok(info) => info,
-                Err(e) => return Err(e),
-            };
+        let file = self
+            .open(served_path)
+            .await?;
 
-            let (unit, range) = range_info;
-            if unit != "bytes" {
-                return Err(VetisError::VirtualHost(VirtualHostError::File(
-                    FileError::InvalidRange,
-                )));
+        let filesize = file.len();
+
+        let mut validators = Validators::new(filesize, file.modified());
+        if let Some(encoding) = encoding {
+            validators = validators.encoded(encoding);
+        }
+
+        // Headers sent with the file, and with `304 Not Modified`
+        let mut headers = HeaderMap::new();
+        validators.insert_headers(&mut headers);
+        if self
+            .config
+            .precompressed()
+        {
+            headers.insert(http::header::VARY, HeaderValue::from_static("accept-encoding"));
+        }
+        if let Some(path) = self.relative_path(file_path) {
+            self.cache_rules
+                .insert_headers(&path, &mut headers);
+        }
+
+        match conditional::evaluate(request, &validators) {
+            Precondition::Proceed => {}
+            Precondition::NotModified => {
+                let mut response =
+                    conditional::precondition_response(Precondition::NotModified, &validators);
+                for (name, value) in &headers {
+                    response
+                        .headers_mut()
+                        .insert(name, value.clone());
+                }
+                return Ok(response);
+            }
+            precondition => {
+                let mut response = conditional::precondition_response(precondition, &validators);
+                if let Some(vary) = headers.get(http::header::VARY) {
+                    response
+                        .headers_mut()
+                        .insert(http::header::VARY, vary.clone());
+                }
+                return Ok(response);
             }
+        }
 
-            let (start, end) = range
-                .split_once("-")
-                .ok_or(VetisError::VirtualHost(VirtualHostError::File(FileError::InvalidRange)))?;
-            let start = start
-                .parse::<u64>()
-                .map_err(|_| {
-                    VetisError::VirtualHost(VirtualHostError::File(FileError::InvalidRange))
-                })?;
-            let end = end
-                .parse::<u64>()
-                .map_err(|_| {
-                    VetisError::VirtualHost(VirtualHostError::File(FileError::InvalidRange))
-                })?;
-            if start > end || start >= filesize {
-                return Ok(Response::builder()
-                    .status(http::StatusCode::RANGE_NOT_SATISFIABLE)
-                    .body(HttpBody::from_text("")));
-            } else if start < end
-                && end < filesize
-                && file
-                    .seek(std::io::SeekFrom::Start(start))
-                    .await
-                    .is_ok()
-            {
-                return Ok(Response::builder()
-                    .status(http::StatusCode::PARTIAL_CONTENT)
-                    .body(HttpBody::from_file(file)));
+        if let Some(encoding) = encoding {
+            headers.insert(http::header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        }
+        let content_type = self.content_type(file_path);
+
+        let range = request
+            .headers()
+            .get(http::header::RANGE)
+            .and_then(|value| value.to_str().ok())
+            .filter(|_| conditional::if_range_matches(request, &validators))
+            .filter(|_| request.method() != http::Method::HEAD);
+
+        match range.map_or(Ranges::Full, |range| range::parse(range, filesize)) {
+            Ranges::Full => {}
+            // A multipart body cannot carry the content coding of a variant, which is sent whole
+            Ranges::Partial(ranges) if encoding.is_some() && ranges.len() > 1 => {}
+            Ranges::Partial(ranges) => {
+                #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "http1"))]
+                let single = match ranges.as_slice() {
+                    [range] => Some(*range),
+                    _ => None,
+                };
+                #[allow(unused_mut)]
+                let mut response =
+                    range::partial(file.content(), filesize, ranges, content_type, headers);
+                #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "http1"))]
+                if let Some(range) = single {
+                    self.send_file(&mut response, &file, range.start, range.len(), encoding);
+                }
+                return Ok(response);
             }
+            Ranges::Unsatisfiable => return Ok(range::unsatisfiable(filesize, headers)),
         }
 
-        Ok(Response::builder()
+        if let Some(content_type) = content_type {
+            headers.insert(http::header::CONTENT_TYPE, content_type);
+        }
+        headers.insert(http::header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        headers.insert(http::header::CONTENT_LENGTH, HeaderValue::from(filesize));
+
+        let body = if request.method() == http::Method::HEAD {
+            HttpBody::from_bytes(&[])
+        } else {
+            range::full_body(file.content(), filesize)
+        };
+
+        #[allow(unused_mut)]
+        let mut response = Response::builder()
             .status(http::StatusCode::OK)
-            .header(
-                http::header::ACCEPT_RANGES,
-                "bytes"
-                    .parse()
-                    .unwrap(),
-            )
-            .header(http::header::CONTENT_LENGTH, HeaderValue::from(filesize))
-            .body(HttpBody::from_file(file)))
+            .headers(headers)
+            .body(body);
+        #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "http1"))]
+        self.send_file(&mut response, &file, 0, filesize, encoding);
+
+        Ok(response)
     }
 
-    async fn serve_metadata(&self, file_path: PathBuf) -> Result<Response, VetisError> {
-        let file = self
-            .cache_file(&file_path)
-            .await?;
+    /// Let plaintext HTTP/1 connections send the body of a response with `sendfile(2)`
+    #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "http1"))]
+    fn send_file(
+        &self,
+        response: &mut Response,
+        file: &CachedFile,
+        offset: u64,
+        len: u64,
+        encoding: Option<&'static str>,
+    ) {
+        if !self
+            .config
+            .sendfile()
+        {
+            return;
+        }
+        // Contents kept in memory are sent as they are
+        if let FileContent::File(handle) = file.content() {
+            response
+                .inner
+                .extensions_mut()
+                .insert(SendFile::new(handle, offset, len, encoding));
+        }
+    }
 
-        let metadata = match file
-            .metadata()
-            .await
+    /// Content type of a file, from the mapping of the static path or guessed from its name
+    fn content_type(&self, file_path: &std::path::Path) -> Option<HeaderValue> {
+        mime::content_type(
+            file_path,
+            self.config
+                .mime_types(),
+            self.config
+                .charset(),
+        )
+    }
+
+    /// Path of a file within the directory of the static path, such as `/assets/app.js`
+    fn relative_path(&self, file_path: &std::path::Path) -> Option<String> {
+        let relative = file_path
+            .strip_prefix(
+                self.config
+                    .directory(),
+            )
+            .ok()?;
+
+        let mut path = String::new();
+        for component in relative.components() {
+            path.push('/');
+            path.push_str(
+                component
+                    .as_os_str()
+                    .to_str()?,
+            );
+        }
+        Some(path)
+    }
+
+    /// Serve a directory: its listing when enabled and it has no index file, else its index file
+    async fn serve_directory(
+        &self,
+        request: &Request,
+        directory: PathBuf,
+    ) -> Result<Response, VetisError> {
+        let has_index_file = self
+            .index_file
+            .as_ref()
+            .is_some_and(|index_file| self.is_file(&directory.join(index_file)));
+        // Embedded assets are not listed
+        if self
+            .config
+            .autoindex()
+            && !has_index_file
+            && matches!(self.source, Source::Directory(_))
         {
-            Ok(metadata) => metadata,
-            Err(e) => {
-                error!("Error getting metadata for file {:?}: {}", file_path, e);
-                return Err(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)));
+            let ext_regex = regex::Regex::new(
+                self.config
+                    .extensions(),
+            );
+            return autoindex::serve_listing(request, directory, ext_regex.ok()).await;
+        }
+        if self
+            .config
+            .index_files()
+            .is_some()
+        {
+            return self
+                .serve_index_file(request, &directory)
+                .await;
+        }
+
+        Err(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)))
+    }
+
+    /// Serve the first existing entry of `try_files`, or its last entry
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request
+    /// * `request_path` - The decoded and normalized request path within the static path
+    /// * `try_files` - The entries to try, the last one being the fallback
+    async fn try_files(
+        &self,
+        request: &Request,
+        request_path: &str,
+        try_files: &[String],
+    ) -> Result<Response, VetisError> {
+        // The request path is already decoded, entries are only normalized
+        let resolve = |entry: &str| {
+            let path = normalize_path(&entry.replace("$uri", request_path));
+            let is_directory = entry.ends_with('/');
+            async move {
+                let path = path
+                    .ok_or(VetisError::VirtualHost(VirtualHostError::File(FileError::Forbidden)))?;
+                Ok::<_, VetisError>((
+                    self.locate(&path)
+                        .await?,
+                    is_directory,
+                ))
             }
         };
 
-        let len = metadata.len();
-        let mut headers = HeaderMap::new();
-        match len
-            .to_string()
-            .parse()
-        {
-            Ok(len) => {
-                headers.insert(http::header::CONTENT_LENGTH, len);
+        let Some((fallback, entries)) = try_files.split_last() else {
+            return Err(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)));
+        };
+
+        for entry in entries {
+            let Ok((file, is_directory)) = resolve(entry).await else {
+                continue;
+            };
+            if is_directory && self.is_dir(&file) {
+                return self
+                    .serve_directory(request, file)
+                    .await;
             }
-            Err(_) => todo!(),
-        }
-        let last_modified = metadata.modified();
-        match last_modified {
-            Ok(date) => {
-                let date = crate::utils::date::format_date(date);
-                headers.insert(
-                    http::header::LAST_MODIFIED,
-                    date.parse()
-                        .map_err(|_| {
-                            VetisError::VirtualHost(VirtualHostError::File(
-                                FileError::InvalidMetadata,
-                            ))
-                        })?,
-                );
+            if !is_directory && self.is_file(&file) {
+                return self
+                    .serve_file(request, &file)
+                    .await;
             }
-            Err(_) => todo!(),
         }
 
-        match file_path.file_name() {
-            Some(filename) => {
-                let mime_type = minimime::lookup_by_filename(
-                    filename
-                        .to_str()
-                        .ok_or(VetisError::VirtualHost(VirtualHostError::File(
-                            FileError::InvalidMetadata,
-                        )))?,
-                );
-                if let Some(mime_type) = mime_type {
-                    headers.insert(
-                        http::header::CONTENT_TYPE,
-                        HeaderValue::from_str(
-                            mime_type
-                                .content_type
-                                .as_str(),
-                        )
-                        .map_err(|_| {
-                            VetisError::VirtualHost(VirtualHostError::File(
-                                FileError::InvalidMetadata,
-                            ))
-                        })?,
-                    );
-                }
-            }
-            None => {
-                return Err(VetisError::VirtualHost(VirtualHostError::File(
-                    FileError::InvalidMetadata,
-                )));
-            }
+        if let Some(status) = try_files_status(fallback) {
+            let status = status.unwrap_or(http::StatusCode::NOT_FOUND);
+            return Err(VetisError::VirtualHost(VirtualHostError::File(FileError::Status(status))));
         }
 
-        Ok(Response { inner: static_response(http::StatusCode::OK, Some(headers), String::new()) })
+        let (file, is_directory) = resolve(fallback).await?;
+        if is_directory || self.is_dir(&file) {
+            return self
+                .serve_directory(request, file)
+                .await;
+        }
+        self.serve_file(request, &file)
+            .await
+    }
+
+    /// Locate the file of a normalized request path within the directory of the static path
+    ///
+    /// Dotfiles are not found unless allowed, and files outside of the directory or behind a
+    /// symbolic link denied by the symlink policy are forbidden.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_path` - The decoded and normalized path, such as `/assets/app.js`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf, VetisError>` - The file, which may not exist
+    async fn locate(&self, request_path: &str) -> Result<PathBuf, VetisError> {
+        let forbidden = || VetisError::VirtualHost(VirtualHostError::File(FileError::Forbidden));
+
+        let segments: Vec<String> = request_path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+        // Backslashes and drive prefixes would be separators on Windows
+        if segments
+            .iter()
+            .any(|segment| segment.contains('\\') || (cfg!(windows) && segment.contains(':')))
+        {
+            return Err(forbidden());
+        }
+        if !self
+            .config
+            .dotfiles()
+            && segments
+                .iter()
+                .any(|segment| segment.starts_with('.'))
+        {
+            return Err(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)));
+        }
+
+        let directory = PathBuf::from(
+            self.config
+                .directory(),
+        );
+        if matches!(self.source, Source::Embedded(_)) {
+            return Ok(directory.join(segments.join("/")));
+        }
+
+        // Every segment is looked up on the file system, away from the async workers
+        let symlinks = self
+            .config
+            .symlinks();
+        unblock(move || Ok(resolve_file(directory, &segments, symlinks)))
+            .await
+            .ok()
+            .flatten()
+            .ok_or_else(forbidden)
     }
 
-    async fn serve_index_file(&self, directory: &std::path::Path) -> Result<Response, VetisError> {
+    async fn serve_index_file(
+        &self,
+        request: &Request,
+        directory: &std::path::Path,
+    ) -> Result<Response, VetisError> {
         match &self.index_file {
             Some(index_file) => {
                 let full_path = directory.join(index_file);
-                self.serve_file(&full_path, None)
+                self.serve_file(request, &full_path)
                     .await
             }
             None => {
@@ -309,9 +568,130 @@ impl StaticPath {
     }
 }
 
+/// Resolve the segments of a request path within a directory, following the symlink policy
+///
+/// # Returns
+///
+/// * `Option<PathBuf>` - The file, which may not exist, `None` when it is forbidden
+fn resolve_file(
+    directory: PathBuf,
+    segments: &[String],
+    symlinks: SymlinkPolicy,
+) -> Option<PathBuf> {
+    let Ok(root) = std::fs::canonicalize(&directory) else {
+        return Some(directory.join(segments.join("/")));
+    };
+
+    let mut file = directory;
+    for (index, segment) in segments
+        .iter()
+        .enumerate()
+    {
+        file.push(segment);
+
+        let Ok(metadata) = std::fs::symlink_metadata(&file) else {
+            // Missing files are created in their existing parent, which must not escape
+            let contained = file
+                .parent()
+                .and_then(|parent| std::fs::canonicalize(parent).ok())
+                .is_some_and(|parent| parent.starts_with(&root));
+            if !contained {
+                return None;
+            }
+            file.extend(&segments[index + 1..]);
+            return Some(file);
+        };
+        if !metadata
+            .file_type()
+            .is_symlink()
+        {
+            continue;
+        }
+
+        let allowed = match symlinks {
+            SymlinkPolicy::Follow => true,
+            SymlinkPolicy::Deny => false,
+            SymlinkPolicy::OwnerMatch => {
+                std::fs::metadata(&file).is_ok_and(|target| same_owner(&metadata, &target))
+            }
+        };
+        // Dangling links would create their target wherever it points
+        if !allowed || std::fs::metadata(&file).is_err() {
+            return None;
+        }
+    }
+
+    match std::fs::canonicalize(&file) {
+        Ok(canonical) if !canonical.starts_with(&root) => None,
+        _ => Some(file),
+    }
+}
+
+/// Whether a symbolic link and its target have the same owner
+#[cfg(unix)]
+fn same_owner(link: &std::fs::Metadata, target: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    link.uid() == target.uid()
+}
+
+/// Ownership is not compared on other platforms, links are denied
+#[cfg(not(unix))]
+fn same_owner(_link: &std::fs::Metadata, _target: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Headers describing a file: `Content-Length`, `Last-Modified` and `Content-Type`
+///
+/// # Arguments
+///
+/// * `file_path` - The file path, its name gives the content type
+/// * `metadata` - The file metadata
+///
+/// # Returns
+///
+/// * `Result<HeaderMap, VetisError>` - The file headers
+pub(crate) fn file_headers(
+    file_path: &std::path::Path,
+    metadata: &std::fs::Metadata,
+) -> Result<HeaderMap, VetisError> {
+    let invalid_metadata =
+        || VetisError::VirtualHost(VirtualHostError::File(FileError::InvalidMetadata));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(http::header::CONTENT_LENGTH, HeaderValue::from(metadata.len()));
+
+    if let Ok(date) = metadata.modified() {
+        let date = crate::utils::date::format_http_date(date);
+        headers.insert(
+            http::header::LAST_MODIFIED,
+            date.parse()
+                .map_err(|_| invalid_metadata())?,
+        );
+    }
+
+    if file_path
+        .file_name()
+        .and_then(|filename| filename.to_str())
+        .is_none()
+    {
+        return Err(invalid_metadata());
+    }
+    if let Some(content_type) =
+        mime::content_type(file_path, &HashMap::new(), Some(DEFAULT_CHARSET))
+    {
+        headers.insert(http::header::CONTENT_TYPE, content_type);
+    }
+
+    Ok(headers)
+}
+
 impl From<StaticPath> for HostPath {
     /// Convert static path to host path
     ///
+    /// Static paths configured with compression or authentication are wrapped by their
+    /// compression and auth middleware, in this order.
+    ///
     /// # Arguments
     ///
     /// * `value` - The static path to convert
@@ -320,7 +700,31 @@ impl From<StaticPath> for HostPath {
     ///
     /// * `HostPath` - The host path
     fn from(value: StaticPath) -> Self {
-        HostPath::Static(value)
+        #[cfg(feature = "compression")]
+        let compression = value
+            .config
+            .compression()
+            .clone();
+        #[cfg(feature = "auth")]
+        let auth = value
+            .config
+            .auth()
+            .clone();
+
+        #[allow(unused_mut)]
+        let mut path = HostPath::Static(value);
+
+        #[cfg(feature = "compression")]
+        if let Some(compression) = compression {
+            path = path.middleware(Compression::new(compression));
+        }
+
+        #[cfg(feature = "auth")]
+        if let Some(auth) = auth {
+            path = path.middleware(auth);
+        }
+
+        path
     }
 }
 
@@ -355,70 +759,57 @@ impl Path for StaticPath {
                     .directory(),
             );
 
-            #[cfg(feature = "auth")]
-            if let Some(auth) = self.config.auth() {
-                if !auth
-                    .authenticate(request.headers())
-                    .await
-                    .unwrap_or(false)
-                {
-                    return Err(VetisError::VirtualHost(VirtualHostError::Auth(
-                        "Unauthorized".to_string(),
-                    )));
+            let request_path = decode_path(&uri)
+                .and_then(|path| normalize_path(&path))
+                .ok_or(VetisError::VirtualHost(VirtualHostError::File(FileError::Forbidden)))?;
+
+            #[cfg(feature = "webdav")]
+            if let Some(webdav) = &self.webdav {
+                if WebDav::handles(request.method()) {
+                    return self
+                        .serve_webdav(webdav, request, &request_path)
+                        .await;
                 }
             }
 
-            let uri = uri
-                .strip_prefix("/")
-                .unwrap_or(&uri);
-            let file = directory.join(uri);
+            if let Some(try_files) = self
+                .config
+                .try_files()
+            {
+                return self
+                    .try_files(&request, &request_path, try_files)
+                    .await;
+            }
+
+            let file = self
+                .locate(&request_path)
+                .await?;
+
+            if self.is_dir(&file) {
+                return self
+                    .serve_directory(&request, file)
+                    .await;
+            }
 
             if self
                 .config
                 .index_files()
                 .is_some()
             {
-                if !file.exists() {
+                if !self.exists(&file) {
                     if let Ok(ext_regex) = ext_regex {
-                        if !ext_regex.is_match(uri.as_ref()) {
+                        if !ext_regex.is_match(&request_path) {
                             return self
-                                .serve_index_file(&directory)
+                                .serve_index_file(&request, &directory)
                                 .await;
                         }
                     }
-                } else if file.is_dir() {
-                    return self
-                        .serve_index_file(&file)
-                        .await;
                 }
-            } else if !file.exists() {
+            } else if !self.exists(&file) {
                 return Err(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)));
             }
 
-            if request.method() == http::Method::HEAD {
-                return self
-                    .serve_metadata(file)
-                    .await;
-            }
-
-            let range = if request
-                .headers()
-                .contains_key(http::header::RANGE)
-            {
-                let value = request
-                    .headers()
-                    .get(http::header::RANGE);
-                Some(
-                    value
-                        .unwrap()
-                        .to_str()
-                        .unwrap(),
-                )
-            } else {
-                None
-            };
-
-            self.serve_file(&file, range)
+            self.serve_file(&request, &file)
                 .await
         })
     }
@@ -0,0 +1,50 @@
+//! Precompressed variants of static files, such as `app.js.br` next to `app.js`
+
+use std::path::{Path, PathBuf};
+
+use crate::server::http::body::encoding_quality;
+
+/// Content codings of the variants and their file extensions, in order of preference
+const ENCODINGS: [(&str, &str); 3] = [("br", "br"), ("zstd", "zst"), ("gzip", "gz")];
+
+/// Select the precompressed variant of a file accepted by the client
+///
+/// # Arguments
+///
+/// * `accept_encoding` - The `Accept-Encoding` header of the request
+/// * `file_path` - The original file
+/// * `is_file` - Whether a variant exists, next to the original file
+///
+/// # Returns
+///
+/// * `Option<(PathBuf, &'static str)>` - The variant with the highest quality and its content coding
+pub(crate) fn select(
+    accept_encoding: Option<&str>,
+    file_path: &Path,
+    is_file: impl Fn(&Path) -> bool,
+) -> Option<(PathBuf, &'static str)> {
+    let accept_encoding = accept_encoding?;
+    let filename = file_path.file_name()?;
+
+    let mut selected: Option<(PathBuf, &'static str, u16)> = None;
+    for (encoding, extension) in ENCODINGS {
+        let q = encoding_quality(accept_encoding, encoding);
+        if q == 0
+            || selected
+                .as_ref()
+                .is_some_and(|(_, _, best)| *best >= q)
+        {
+            continue;
+        }
+
+        let mut variant = filename.to_os_string();
+        variant.push(".");
+        variant.push(extension);
+        let variant = file_path.with_file_name(variant);
+        if is_file(&variant) {
+            selected = Some((variant, encoding, q));
+        }
+    }
+
+    selected.map(|(variant, encoding, _)| (variant, encoding))
+}
@@ -0,0 +1,252 @@
+//! Range requests of static files (RFC 9110, section 14)
+
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+use futures_util::{stream, TryStreamExt};
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use http_body_util::{BodyExt, StreamBody};
+use hyper::body::Frame;
+use hyper_body_utils::HttpBody;
+
+use super::file_cache::FileContent;
+use crate::{server::http::body::SyncBody, server::http::Response};
+
+/// Requests with more ranges are served whole, as they cost more than the full file
+const MAX_RANGES: usize = 16;
+
+/// Size of the chunks read from files
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Inclusive range of bytes of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ByteRange {
+    pub(crate) start: u64,
+    pub(crate) end: u64,
+}
+
+impl ByteRange {
+    pub(crate) fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn content_range(&self, filesize: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, filesize)
+    }
+}
+
+/// Outcome of the `Range` header of a request
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Ranges {
+    /// Serve the whole file, as the header is invalid or not worth honoring
+    Full,
+    /// Serve the ranges, in the requested order
+    Partial(Vec<ByteRange>),
+    /// Answer with `416 Range Not Satisfiable`
+    Unsatisfiable,
+}
+
+/// Parse a `Range` header for a file of `filesize` bytes
+///
+/// Supports `bytes=start-end`, open-ended `bytes=start-` and suffix `bytes=-length`
+/// ranges, separated by commas. Ends past the file are clamped, ranges starting past
+/// the file are left out.
+pub(crate) fn parse(range: &str, filesize: u64) -> Ranges {
+    let Some((unit, specs)) = range.split_once('=') else {
+        return Ranges::Full;
+    };
+    if !unit
+        .trim()
+        .eq_ignore_ascii_case("bytes")
+    {
+        return Ranges::Full;
+    }
+
+    let mut ranges = Vec::new();
+    let mut has_specs = false;
+    for spec in specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        let Some((start, end)) = spec.split_once('-') else {
+            return Ranges::Full;
+        };
+        let (start, end) = (start.trim(), end.trim());
+        has_specs = true;
+
+        let range = if start.is_empty() {
+            let Ok(suffix) = end.parse::<u64>() else {
+                return Ranges::Full;
+            };
+            (suffix > 0 && filesize > 0)
+                .then(|| ByteRange { start: filesize.saturating_sub(suffix), end: filesize - 1 })
+        } else {
+            let Ok(start) = start.parse::<u64>() else {
+                return Ranges::Full;
+            };
+            let end = if end.is_empty() {
+                u64::MAX
+            } else {
+                match end.parse::<u64>() {
+                    Ok(end) if end >= start => end,
+                    _ => return Ranges::Full,
+                }
+            };
+            (start < filesize).then(|| ByteRange { start, end: end.min(filesize - 1) })
+        };
+
+        ranges.extend(range);
+        if ranges.len() > MAX_RANGES {
+            return Ranges::Full;
+        }
+    }
+
+    if !has_specs {
+        return Ranges::Full;
+    }
+    if ranges.is_empty() {
+        return Ranges::Unsatisfiable;
+    }
+
+    Ranges::Partial(ranges)
+}
+
+/// Build the `416 Range Not Satisfiable` response of a file
+pub(crate) fn unsatisfiable(filesize: u64, mut headers: HeaderMap) -> Response {
+    if let Ok(content_range) = HeaderValue::from_str(&format!("bytes */{}", filesize)) {
+        headers.insert(header::CONTENT_RANGE, content_range);
+    }
+
+    Response::builder()
+        .status(StatusCode::RANGE_NOT_SATISFIABLE)
+        .headers(headers)
+        .body(HttpBody::from_bytes(&[]))
+}
+
+/// Part of a range response body
+enum Segment {
+    Bytes(Bytes),
+    File(ByteRange),
+}
+
+/// Build the `206 Partial Content` response of a file
+///
+/// A single range is sent as is with `Content-Range`, several ranges are sent as a
+/// `multipart/byteranges` body.
+///
+/// # Arguments
+///
+/// * `content` - The contents of the file
+/// * `filesize` - The size of the file
+/// * `ranges` - The ranges to send, at least one
+/// * `content_type` - The content type of the file, repeated in each part
+/// * `headers` - The headers of the response, such as the file validators
+pub(crate) fn partial(
+    content: FileContent,
+    filesize: u64,
+    ranges: Vec<ByteRange>,
+    content_type: Option<HeaderValue>,
+    mut headers: HeaderMap,
+) -> Response {
+    let mut segments = VecDeque::new();
+
+    if let [range] = ranges.as_slice() {
+        if let Ok(content_range) = HeaderValue::from_str(&range.content_range(filesize)) {
+            headers.insert(header::CONTENT_RANGE, content_range);
+        }
+        if let Some(content_type) = content_type {
+            headers.insert(header::CONTENT_TYPE, content_type);
+        }
+        segments.push_back(Segment::File(*range));
+    } else {
+        let boundary = format!("{:016x}", rand::random::<u64>());
+        let part_type = content_type
+            .as_ref()
+            .and_then(|content_type| {
+                content_type
+                    .to_str()
+                    .ok()
+            })
+            .map(|content_type| format!("Content-Type: {}\r\n", content_type))
+            .unwrap_or_default();
+
+        for range in ranges {
+            segments.push_back(Segment::Bytes(Bytes::from(format!(
+                "\r\n--{}\r\n{}Content-Range: {}\r\n\r\n",
+                boundary,
+                part_type,
+                range.content_range(filesize)
+            ))));
+            segments.push_back(Segment::File(range));
+        }
+        segments.push_back(Segment::Bytes(Bytes::from(format!("\r\n--{}--\r\n", boundary))));
+
+        if let Ok(content_type) =
+            HeaderValue::from_str(&format!("multipart/byteranges; boundary={}", boundary))
+        {
+            headers.insert(header::CONTENT_TYPE, content_type);
+        }
+    }
+
+    let length: u64 = segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Bytes(bytes) => bytes.len() as u64,
+            Segment::File(range) => range.len(),
+        })
+        .sum();
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    Response::builder()
+        .status(StatusCode::PARTIAL_CONTENT)
+        .headers(headers)
+        .body(segments_body(content, segments))
+}
+
+/// Stream the whole contents of a file
+pub(crate) fn full_body(content: FileContent, filesize: u64) -> HttpBody {
+    let segments = (filesize > 0)
+        .then(|| Segment::File(ByteRange { start: 0, end: filesize - 1 }))
+        .into_iter()
+        .collect();
+
+    segments_body(content, segments)
+}
+
+/// Stream the segments of a range response, reading file ranges in chunks
+fn segments_body(content: FileContent, segments: VecDeque<Segment>) -> HttpBody {
+    // Offset and bytes left to read from the file for the current segment
+    let chunks = stream::try_unfold(
+        (content, segments, 0u64, 0u64),
+        |(content, mut segments, offset, left)| async move {
+            if left > 0 {
+                let chunk = content
+                    .read(offset, left.min(CHUNK_SIZE) as usize)
+                    .await?;
+                if chunk.is_empty() {
+                    return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+                }
+                let read = chunk.len() as u64;
+                return Ok(Some((chunk, (content, segments, offset + read, left - read))));
+            }
+
+            match segments.pop_front() {
+                Some(Segment::Bytes(bytes)) => Ok(Some((bytes, (content, segments, 0, 0)))),
+                Some(Segment::File(range)) => {
+                    Ok(Some((Bytes::new(), (content, segments, range.start, range.len()))))
+                }
+                None => Ok(None),
+            }
+        },
+    );
+
+    let body = StreamBody::new(
+        chunks
+            .try_filter(|chunk| std::future::ready(!chunk.is_empty()))
+            .map_ok(Frame::data),
+    );
+
+    HttpBody::Stream(SyncBody::new(body).boxed())
+}
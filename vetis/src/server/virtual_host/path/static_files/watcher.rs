@@ -0,0 +1,206 @@
+//! Invalidation of cached files with inotify
+//!
+//! inotify does not watch directories recursively, so the directories leading to each cached
+//! file are watched as files are cached. Events are read by a thread of the watcher, which
+//! evicts the changed files and stops once the cache is dropped.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{CString, OsStr},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+};
+
+use log::error;
+
+use super::file_cache::Entries;
+use crate::VetisRwLock;
+
+/// Events evicting the files of a watched directory
+const WATCH_MASK: u32 = libc::IN_MODIFY
+    | libc::IN_ATTRIB
+    | libc::IN_CLOSE_WRITE
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_DELETE_SELF
+    | libc::IN_MOVE_SELF
+    | libc::IN_ONLYDIR;
+
+/// How often the thread checks whether the cache was dropped, in milliseconds
+const POLL_TIMEOUT: libc::c_int = 1000;
+
+/// Size of the buffer events are read into
+const EVENT_BUFFER: usize = 16 * 1024;
+
+/// Watched directories, by watch descriptor
+#[derive(Default)]
+struct Watches {
+    directories: HashMap<libc::c_int, Vec<PathBuf>>,
+    watched: HashSet<PathBuf>,
+}
+
+/// Watcher of the directories of a file cache
+pub(crate) struct Watcher {
+    fd: Arc<OwnedFd>,
+    watches: Arc<Mutex<Watches>>,
+}
+
+impl Watcher {
+    /// Create a watcher evicting the changed files of a cache
+    pub(crate) fn new(entries: Weak<VetisRwLock<Entries>>) -> std::io::Result<Watcher> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let fd = Arc::new(unsafe { OwnedFd::from_raw_fd(fd) });
+        let watches = Arc::new(Mutex::new(Watches::default()));
+
+        let thread_fd = fd.clone();
+        let thread_watches = watches.clone();
+        std::thread::Builder::new()
+            .name("vetis-file-watcher".to_string())
+            .spawn(move || read_events(&thread_fd, &thread_watches, &entries))?;
+
+        Ok(Watcher { fd, watches })
+    }
+
+    /// Watch the directories from `directory` down to the parent of `file`
+    pub(crate) fn watch(&self, directory: &Path, file: &Path) {
+        let Some(parent) = file.parent() else {
+            return;
+        };
+        let directories = parent
+            .ancestors()
+            .take_while(|ancestor| ancestor.starts_with(directory))
+            .collect::<Vec<_>>();
+
+        let Ok(mut watches) = self.watches.lock() else {
+            return;
+        };
+        for directory in directories
+            .into_iter()
+            .rev()
+        {
+            if watches
+                .watched
+                .contains(directory)
+            {
+                continue;
+            }
+            let Ok(path) = CString::new(
+                directory
+                    .as_os_str()
+                    .as_bytes(),
+            ) else {
+                return;
+            };
+
+            let wd =
+                unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), path.as_ptr(), WATCH_MASK) };
+            if wd < 0 {
+                error!(
+                    "Error watching directory {}: {}",
+                    directory.display(),
+                    std::io::Error::last_os_error()
+                );
+                return;
+            }
+
+            // Directories reached through links share the watch descriptor of their target
+            watches
+                .directories
+                .entry(wd)
+                .or_default()
+                .push(directory.to_path_buf());
+            watches
+                .watched
+                .insert(directory.to_path_buf());
+        }
+    }
+}
+
+/// Read the events of a watcher until its cache is dropped
+fn read_events(fd: &OwnedFd, watches: &Mutex<Watches>, entries: &Weak<VetisRwLock<Entries>>) {
+    let mut buffer = vec![0u8; EVENT_BUFFER];
+    loop {
+        let mut pollfd = libc::pollfd { fd: fd.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        let ready = unsafe { libc::poll(&mut pollfd, 1, POLL_TIMEOUT) };
+
+        let Some(entries) = entries.upgrade() else {
+            return;
+        };
+        if ready <= 0 {
+            continue;
+        }
+
+        let read = unsafe {
+            libc::read(fd.as_raw_fd(), buffer.as_mut_ptr() as *mut libc::c_void, buffer.len())
+        };
+        if read <= 0 {
+            continue;
+        }
+
+        #[cfg(feature = "tokio-rt")]
+        let mut entries = entries.blocking_write();
+
+        #[cfg(feature = "smol-rt")]
+        let mut entries = entries.write_blocking();
+
+        let Ok(mut watches) = watches.lock() else {
+            return;
+        };
+
+        let header = std::mem::size_of::<libc::inotify_event>();
+        let mut offset = 0;
+        while offset + header <= read as usize {
+            let event = unsafe {
+                std::ptr::read_unaligned(buffer[offset..].as_ptr() as *const libc::inotify_event)
+            };
+            let name = &buffer[offset + header..offset + header + event.len as usize];
+            let name = &name[..name
+                .iter()
+                .position(|byte| *byte == 0)
+                .unwrap_or(name.len())];
+            offset += header + event.len as usize;
+
+            if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                entries.clear();
+                continue;
+            }
+
+            let directories = if event.mask & libc::IN_IGNORED != 0 {
+                // The directory was removed, it is watched again once files are cached in it
+                let directories = watches
+                    .directories
+                    .remove(&event.wd)
+                    .unwrap_or_default();
+                for directory in &directories {
+                    watches
+                        .watched
+                        .remove(directory);
+                }
+                directories
+            } else {
+                watches
+                    .directories
+                    .get(&event.wd)
+                    .cloned()
+                    .unwrap_or_default()
+            };
+
+            for directory in directories {
+                if name.is_empty() {
+                    entries.invalidate(&directory);
+                } else {
+                    entries.invalidate(&directory.join(OsStr::from_bytes(name)));
+                }
+            }
+        }
+    }
+}
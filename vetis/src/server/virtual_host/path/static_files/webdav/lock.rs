@@ -0,0 +1,206 @@
+//! Write locks of WebDAV resources (RFC 4918, section 6)
+//!
+//! Locks are kept in memory and released when the server stops. Expired locks are purged
+//! whenever locks are read or changed.
+
+use std::{
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Scope of a lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum LockScope {
+    /// Held by a single principal
+    Exclusive,
+    /// Held along with other shared locks
+    Shared,
+}
+
+/// Write lock of a resource, and of its members when infinite
+#[derive(Debug, Clone)]
+pub(super) struct Lock {
+    pub(super) token: String,
+    /// Request path of the locked resource, such as `/docs/report.txt`
+    pub(super) root: String,
+    pub(super) scope: LockScope,
+    pub(super) infinite: bool,
+    /// XML fragment describing the owner, as sent by the client
+    pub(super) owner: Option<String>,
+    pub(super) timeout: Duration,
+    expires: Instant,
+}
+
+impl Lock {
+    /// Whether the lock applies to a path
+    fn covers(&self, path: &str) -> bool {
+        self.root == path || (self.infinite && is_within(path, &self.root))
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires <= now
+    }
+}
+
+/// Whether a path is a collection or below it
+pub(super) fn is_within(path: &str, collection: &str) -> bool {
+    collection == "/"
+        || path == collection
+        || path
+            .strip_prefix(collection)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Parent collection of a path, `None` for the root
+pub(super) fn parent(path: &str) -> Option<&str> {
+    match path.rsplit_once('/') {
+        Some(("", "")) | None => None,
+        Some(("", _)) => Some("/"),
+        Some((parent, _)) => Some(parent),
+    }
+}
+
+/// Generate a lock token, a random UUID (RFC 9562, version 4)
+fn lock_token() -> String {
+    let mut bytes = rand::random::<u128>().to_be_bytes();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!(
+        "urn:uuid:{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Locks of a static path
+#[derive(Default)]
+pub(super) struct Locks(Mutex<Vec<Lock>>);
+
+impl Locks {
+    /// The active locks, once expired ones are purged
+    fn active(&self) -> MutexGuard<'_, Vec<Lock>> {
+        let mut locks = self
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        locks.retain(|lock| !lock.is_expired(now));
+        locks
+    }
+
+    /// Lock a path, unless it conflicts with the locks already held
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Lock>` - The new lock, `None` on conflict
+    pub(super) fn lock(
+        &self,
+        root: &str,
+        scope: LockScope,
+        infinite: bool,
+        owner: Option<String>,
+        timeout: Duration,
+    ) -> Option<Lock> {
+        let mut locks = self.active();
+        let conflict = locks
+            .iter()
+            .any(|lock| {
+                let overlaps = lock.covers(root) || (infinite && is_within(&lock.root, root));
+                overlaps && (lock.scope == LockScope::Exclusive || scope == LockScope::Exclusive)
+            });
+        if conflict {
+            return None;
+        }
+
+        let lock = Lock {
+            token: lock_token(),
+            root: root.to_string(),
+            scope,
+            infinite,
+            owner,
+            timeout,
+            expires: Instant::now() + timeout,
+        };
+        locks.push(lock.clone());
+        Some(lock)
+    }
+
+    /// Refresh the lock of a path whose token was submitted
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Lock>` - The refreshed lock, `None` when no token matches
+    pub(super) fn refresh(&self, path: &str, tokens: &[String], timeout: Duration) -> Option<Lock> {
+        let mut locks = self.active();
+        let lock = locks
+            .iter_mut()
+            .find(|lock| lock.covers(path) && tokens.contains(&lock.token))?;
+
+        lock.timeout = timeout;
+        lock.expires = Instant::now() + timeout;
+        Some(lock.clone())
+    }
+
+    /// Release the lock of a path
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the token matched a lock of the path
+    pub(super) fn unlock(&self, path: &str, token: &str) -> bool {
+        let mut locks = self.active();
+        let count = locks.len();
+        locks.retain(|lock| !(lock.token == token && lock.covers(path)));
+        locks.len() < count
+    }
+
+    /// Whether a path may be written with the submitted tokens
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The written path
+    /// * `recursive` - Whether its members are written too, such as by `DELETE`
+    /// * `member` - Whether the path is added to or removed from its parent collection
+    /// * `tokens` - The lock tokens of the `If` header
+    pub(super) fn check(
+        &self,
+        path: &str,
+        recursive: bool,
+        member: bool,
+        tokens: &[String],
+    ) -> bool {
+        let parent = parent(path).filter(|_| member);
+
+        self.active()
+            .iter()
+            .filter(|lock| !tokens.contains(&lock.token))
+            .all(|lock| {
+                let conflicts = lock.covers(path)
+                    || (recursive && is_within(&lock.root, path))
+                    || parent.is_some_and(|parent| lock.covers(parent));
+                !conflicts
+            })
+    }
+
+    /// Locks applying to a path
+    pub(super) fn discover(&self, path: &str) -> Vec<Lock> {
+        self.active()
+            .iter()
+            .filter(|lock| lock.covers(path))
+            .cloned()
+            .collect()
+    }
+
+    /// Release the locks of a removed path and of its members
+    pub(super) fn remove_within(&self, path: &str) {
+        self.active()
+            .retain(|lock| !is_within(&lock.root, path));
+    }
+}
@@ -0,0 +1,1020 @@
+//! Write access to the directory of a static path with WebDAV (RFC 4918, classes 1 and 2)
+//!
+//! Uploads and copies are written to a temporary file next to their target, then renamed over
+//! it, so readers never see a partial file. Temporary files are dotfiles, which static paths
+//! do not serve unless dotfiles are allowed.
+//!
+//! Properties are the live properties of the file system, dead properties cannot be stored.
+
+mod lock;
+mod xml;
+
+use std::{io::Write, path::PathBuf, time::Duration};
+
+use http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use http_body_util::BodyExt;
+use hyper_body_utils::HttpBody;
+use log::error;
+use percent_encoding::utf8_percent_encode;
+
+use lock::Locks;
+use xml::{Multistatus, PropName, Propfind};
+
+use super::{
+    autoindex::PATH_SEGMENT,
+    conditional::{self, Precondition, Validators},
+    Source, StaticPath,
+};
+use crate::{
+    config::server::virtual_host::path::static_files::WebDavConfig,
+    errors::{BodyError, FileError, VetisError, VirtualHostError},
+    rt::unblock,
+    server::http::{body::body_error, Request, Response},
+    utils::{
+        date::{format_http_date, format_rfc3339},
+        path::{decode_path, normalize_path},
+    },
+};
+
+/// Methods of a static path with WebDAV, answered to `OPTIONS`
+const ALLOWED_METHODS: &str =
+    "OPTIONS, GET, HEAD, PUT, DELETE, MKCOL, COPY, MOVE, PROPFIND, PROPPATCH, LOCK, UNLOCK";
+
+/// WebDAV settings and locks of a static path
+pub(super) struct WebDav {
+    config: WebDavConfig,
+    locks: Locks,
+}
+
+impl WebDav {
+    pub(super) fn new(config: WebDavConfig) -> Self {
+        WebDav { config, locks: Locks::default() }
+    }
+
+    /// Whether a method is served by WebDAV rather than as a read
+    pub(super) fn handles(method: &Method) -> bool {
+        matches!(
+            method.as_str(),
+            "OPTIONS"
+                | "PUT"
+                | "DELETE"
+                | "MKCOL"
+                | "COPY"
+                | "MOVE"
+                | "PROPFIND"
+                | "PROPPATCH"
+                | "LOCK"
+                | "UNLOCK"
+        )
+    }
+}
+
+/// Error answered with a status, such as `409 Conflict`
+fn status(status: StatusCode) -> VetisError {
+    VetisError::VirtualHost(VirtualHostError::File(FileError::Status(status)))
+}
+
+/// Error of a file system operation
+fn io_error(path: &std::path::Path, e: std::io::Error) -> VetisError {
+    error!("Error writing {}: {}", path.display(), e);
+    let error = match e.kind() {
+        std::io::ErrorKind::NotFound => FileError::NotFound,
+        std::io::ErrorKind::PermissionDenied => FileError::Forbidden,
+        _ => FileError::Status(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    VetisError::VirtualHost(VirtualHostError::File(error))
+}
+
+fn empty(status: StatusCode) -> Response {
+    Response::builder()
+        .status(status)
+        .body(HttpBody::from_bytes(&[]))
+}
+
+/// `423 Locked`, the lock token of the resource was not submitted
+fn locked() -> Response {
+    xml::error_response(StatusCode::LOCKED, "lock-token-submitted")
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+}
+
+/// Lock tokens submitted in the `If` header, such as `<urn:uuid:...>`
+fn submitted_tokens(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get_all("if")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| {
+            value
+                .split('<')
+                .skip(1)
+        })
+        .filter_map(|part| part.split_once('>'))
+        .map(|(token, _)| token.to_string())
+        .collect()
+}
+
+/// Timeout requested by a `Timeout` header, at most `max`
+fn lock_timeout(headers: &HeaderMap, max: Duration) -> Duration {
+    header_str(headers, "timeout")
+        .and_then(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .find_map(|timeout| {
+                    if timeout.eq_ignore_ascii_case("infinite") {
+                        return Some(max);
+                    }
+                    timeout
+                        .strip_prefix("Second-")
+                        .and_then(|seconds| seconds.parse().ok())
+                        .map(Duration::from_secs)
+                })
+        })
+        .map_or(max, |timeout| timeout.clamp(Duration::from_secs(1), max))
+}
+
+/// Whether the `Depth` header requests infinite depth, the default; `0` is allowed when
+/// `allow_zero` is set
+fn is_infinite(headers: &HeaderMap, allow_zero: bool) -> Result<bool, VetisError> {
+    match header_str(headers, "depth") {
+        None => Ok(true),
+        Some(depth) if depth.eq_ignore_ascii_case("infinity") => Ok(true),
+        Some("0") if allow_zero => Ok(false),
+        Some(_) => Err(status(StatusCode::BAD_REQUEST)),
+    }
+}
+
+/// Temporary file written before being renamed to `file`
+fn temp_path(file: &std::path::Path) -> PathBuf {
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    file.with_file_name(format!(".{}.{:016x}.tmp", name, rand::random::<u64>()))
+}
+
+/// Remove a file, or a directory with its contents
+fn remove(path: &std::path::Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Copy a file or a directory, with its contents when `infinite`
+///
+/// Links within copied directories are left out, as they could point outside of the
+/// directory of the static path.
+fn copy(source: &std::path::Path, target: &std::path::Path, infinite: bool) -> std::io::Result<()> {
+    if !std::fs::metadata(source)?.is_dir() {
+        let temp = temp_path(target);
+        let result = std::fs::copy(source, &temp).and_then(|_| std::fs::rename(&temp, target));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        return result;
+    }
+
+    std::fs::create_dir(target)?;
+    if !infinite {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        if entry
+            .file_type()?
+            .is_symlink()
+        {
+            continue;
+        }
+        copy(&entry.path(), &target.join(entry.file_name()), true)?;
+    }
+    Ok(())
+}
+
+/// Move a file or a directory, copying it when it is moved to another file system
+fn rename(source: &std::path::Path, target: &std::path::Path) -> std::io::Result<()> {
+    match std::fs::rename(source, target) {
+        Err(e) if is_cross_device(&e) => {
+            copy(source, target, true)?;
+            remove(source)
+        }
+        result => result,
+    }
+}
+
+#[cfg(unix)]
+fn is_cross_device(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(libc::EXDEV)
+}
+
+#[cfg(not(unix))]
+fn is_cross_device(e: &std::io::Error) -> bool {
+    // ERROR_NOT_SAME_DEVICE
+    e.raw_os_error() == Some(17)
+}
+
+/// Write a request body to a new file, failing once more than `limit` bytes were received
+async fn write_body(
+    mut body: HttpBody,
+    path: &std::path::Path,
+    limit: u64,
+) -> Result<(), VetisError> {
+    let file_path = path.to_path_buf();
+    let mut file = unblock(move || {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(file_path)
+    })
+    .await
+    .map_err(|e| io_error(path, e))?;
+
+    let mut written = 0u64;
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|e| body_error(BodyError::Read(e.to_string())))?;
+        let Ok(data) = frame.into_data() else {
+            continue;
+        };
+        written += data.len() as u64;
+        if written > limit {
+            return Err(body_error(BodyError::TooLarge(
+                usize::try_from(limit).unwrap_or(usize::MAX),
+            )));
+        }
+        file = unblock(move || {
+            file.write_all(&data)?;
+            Ok(file)
+        })
+        .await
+        .map_err(|e| io_error(path, e))?;
+    }
+
+    unblock(move || file.sync_all())
+        .await
+        .map_err(|e| io_error(path, e))
+}
+
+impl StaticPath {
+    /// Serve a WebDAV request, once authenticated by the auth middleware of the static path
+    ///
+    /// # Arguments
+    ///
+    /// * `webdav` - The WebDAV settings and locks of the static path
+    /// * `request` - The request
+    /// * `request_path` - The decoded and normalized request path within the static path
+    pub(super) async fn serve_webdav(
+        &self,
+        webdav: &WebDav,
+        request: Request,
+        request_path: &str,
+    ) -> Result<Response, VetisError> {
+        if request.method() == Method::OPTIONS {
+            return Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header("dav", HeaderValue::from_static("1, 2"))
+                .header(header::ALLOW, HeaderValue::from_static(ALLOWED_METHODS))
+                .header("ms-author-via", HeaderValue::from_static("DAV"))
+                .body(HttpBody::from_bytes(&[])));
+        }
+
+        let tokens = submitted_tokens(request.headers());
+        match request
+            .method()
+            .as_str()
+        {
+            "PUT" => {
+                self.put(webdav, request, request_path, &tokens)
+                    .await
+            }
+            "DELETE" => {
+                self.delete(webdav, &request, request_path, &tokens)
+                    .await
+            }
+            "MKCOL" => {
+                self.mkcol(webdav, request, request_path, &tokens)
+                    .await
+            }
+            "COPY" => {
+                self.copy_or_move(webdav, &request, request_path, &tokens, false)
+                    .await
+            }
+            "MOVE" => {
+                self.copy_or_move(webdav, &request, request_path, &tokens, true)
+                    .await
+            }
+            "PROPFIND" => {
+                self.propfind(webdav, request, request_path)
+                    .await
+            }
+            "PROPPATCH" => {
+                self.proppatch(webdav, request, request_path, &tokens)
+                    .await
+            }
+            "LOCK" => {
+                self.lock(webdav, request, request_path, &tokens)
+                    .await
+            }
+            "UNLOCK" => self.unlock(webdav, &request, request_path),
+            _ => Err(status(StatusCode::METHOD_NOT_ALLOWED)),
+        }
+    }
+
+    /// Evict a written path and the files below it from the file cache
+    async fn invalidate(&self, file: &std::path::Path) {
+        if let Source::Directory(file_cache) = &self.source {
+            file_cache
+                .invalidate(file)
+                .await;
+        }
+    }
+
+    /// Link of a request path, such as `/dav/my%20docs/` for a collection
+    fn href(&self, request_path: &str, collection: bool) -> String {
+        let mut href = self
+            .config
+            .uri()
+            .trim_end_matches('/')
+            .to_string();
+        for segment in request_path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+        {
+            href.push('/');
+            href.push_str(&utf8_percent_encode(segment, PATH_SEGMENT).to_string());
+        }
+        if collection || href.is_empty() {
+            href.push('/');
+        }
+        href
+    }
+
+    /// Request path of the `Destination` header of `COPY` and `MOVE`, which must be on the
+    /// same host and within the static path
+    fn destination(&self, request: &Request) -> Result<String, VetisError> {
+        let destination = header_str(request.headers(), "destination")
+            .and_then(|destination| {
+                destination
+                    .parse::<http::Uri>()
+                    .ok()
+            })
+            .ok_or_else(|| status(StatusCode::BAD_REQUEST))?;
+
+        if let Some(authority) = destination.authority() {
+            let host = header_str(request.headers(), header::HOST.as_str()).or_else(|| {
+                request
+                    .uri()
+                    .authority()
+                    .map(|authority| authority.as_str())
+            });
+            if !host.is_some_and(|host| host.eq_ignore_ascii_case(authority.as_str())) {
+                return Err(status(StatusCode::BAD_GATEWAY));
+            }
+        }
+
+        let prefix = self
+            .config
+            .uri()
+            .trim_end_matches('/');
+        let path = destination
+            .path()
+            .strip_prefix(prefix)
+            .filter(|path| path.is_empty() || path.starts_with('/'))
+            .ok_or_else(|| status(StatusCode::BAD_GATEWAY))?;
+
+        decode_path(path)
+            .and_then(|path| normalize_path(&path))
+            .ok_or(VetisError::VirtualHost(VirtualHostError::File(FileError::Forbidden)))
+    }
+
+    /// Upload a file, replacing it atomically once the whole body was received
+    async fn put(
+        &self,
+        webdav: &WebDav,
+        request: Request,
+        request_path: &str,
+        tokens: &[String],
+    ) -> Result<Response, VetisError> {
+        let file = self
+            .locate(request_path)
+            .await?;
+        if request_path == "/" || file.is_dir() {
+            return Err(status(StatusCode::METHOD_NOT_ALLOWED));
+        }
+        if !file
+            .parent()
+            .is_some_and(|parent| parent.is_dir())
+        {
+            return Err(status(StatusCode::CONFLICT));
+        }
+        // Partial updates are not supported
+        if request
+            .headers()
+            .contains_key(header::CONTENT_RANGE)
+        {
+            return Err(status(StatusCode::BAD_REQUEST));
+        }
+
+        let metadata = std::fs::metadata(&file).ok();
+        if !webdav
+            .locks
+            .check(request_path, false, metadata.is_none(), tokens)
+        {
+            return Ok(locked());
+        }
+        let precondition = match &metadata {
+            Some(metadata) => conditional::evaluate(
+                &request,
+                &Validators::new(
+                    metadata.len(),
+                    metadata
+                        .modified()
+                        .ok(),
+                ),
+            ),
+            None if request
+                .headers()
+                .contains_key(header::IF_MATCH) =>
+            {
+                Precondition::Failed
+            }
+            None => Precondition::Proceed,
+        };
+        if precondition != Precondition::Proceed {
+            return Err(status(StatusCode::PRECONDITION_FAILED));
+        }
+
+        let limit = webdav
+            .config
+            .max_upload_size();
+        let content_length = header_str(request.headers(), header::CONTENT_LENGTH.as_str())
+            .and_then(|value| {
+                value
+                    .parse::<u64>()
+                    .ok()
+            });
+        if content_length.is_some_and(|len| len > limit) {
+            return Err(body_error(BodyError::TooLarge(
+                usize::try_from(limit).unwrap_or(usize::MAX),
+            )));
+        }
+
+        let (_, body) = request.into_parts();
+        let temp = temp_path(&file);
+        let written = write_body(body, &temp, limit).await;
+        let target = file.clone();
+        let temp_file = temp.clone();
+        let result = match written {
+            Ok(()) => unblock(move || std::fs::rename(&temp_file, &target))
+                .await
+                .map_err(|e| io_error(&file, e)),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            let _ = unblock(move || std::fs::remove_file(temp)).await;
+            return Err(e);
+        }
+
+        self.invalidate(&file)
+            .await;
+        Ok(empty(if metadata.is_some() { StatusCode::NO_CONTENT } else { StatusCode::CREATED }))
+    }
+
+    /// Remove a file, or a collection with its members
+    async fn delete(
+        &self,
+        webdav: &WebDav,
+        request: &Request,
+        request_path: &str,
+        tokens: &[String],
+    ) -> Result<Response, VetisError> {
+        if request_path == "/" {
+            return Err(VetisError::VirtualHost(VirtualHostError::File(FileError::Forbidden)));
+        }
+        let file = self
+            .locate(request_path)
+            .await?;
+        let metadata = std::fs::symlink_metadata(&file)
+            .map_err(|_| VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)))?;
+
+        if !webdav
+            .locks
+            .check(request_path, true, true, tokens)
+        {
+            return Ok(locked());
+        }
+        if metadata.is_file() {
+            let validators = Validators::new(
+                metadata.len(),
+                metadata
+                    .modified()
+                    .ok(),
+            );
+            if conditional::evaluate(request, &validators) != Precondition::Proceed {
+                return Err(status(StatusCode::PRECONDITION_FAILED));
+            }
+        }
+
+        let target = file.clone();
+        unblock(move || remove(&target))
+            .await
+            .map_err(|e| io_error(&file, e))?;
+
+        webdav
+            .locks
+            .remove_within(request_path);
+        self.invalidate(&file)
+            .await;
+        Ok(empty(StatusCode::NO_CONTENT))
+    }
+
+    /// Create a collection
+    async fn mkcol(
+        &self,
+        webdav: &WebDav,
+        request: Request,
+        request_path: &str,
+        tokens: &[String],
+    ) -> Result<Response, VetisError> {
+        let file = self
+            .locate(request_path)
+            .await?;
+        if request_path == "/" || std::fs::symlink_metadata(&file).is_ok() {
+            return Err(status(StatusCode::METHOD_NOT_ALLOWED));
+        }
+        if !file
+            .parent()
+            .is_some_and(|parent| parent.is_dir())
+        {
+            return Err(status(StatusCode::CONFLICT));
+        }
+        if !webdav
+            .locks
+            .check(request_path, false, true, tokens)
+        {
+            return Ok(locked());
+        }
+        // Bodies of MKCOL are not defined
+        if !request
+            .bytes()
+            .await?
+            .is_empty()
+        {
+            return Err(status(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+        }
+
+        let directory = file.clone();
+        unblock(move || std::fs::create_dir(directory))
+            .await
+            .map_err(|e| io_error(&file, e))?;
+
+        Ok(empty(StatusCode::CREATED))
+    }
+
+    /// Copy or move a resource to the path of the `Destination` header
+    async fn copy_or_move(
+        &self,
+        webdav: &WebDav,
+        request: &Request,
+        request_path: &str,
+        tokens: &[String],
+        is_move: bool,
+    ) -> Result<Response, VetisError> {
+        let forbidden = || VetisError::VirtualHost(VirtualHostError::File(FileError::Forbidden));
+
+        let destination = self.destination(request)?;
+        let source = self
+            .locate(request_path)
+            .await?;
+        if !source.exists() {
+            return Err(VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)));
+        }
+        // A collection cannot be copied into itself, nor replace one of its ancestors
+        if (is_move && request_path == "/")
+            || lock::is_within(&destination, request_path)
+            || lock::is_within(request_path, &destination)
+        {
+            return Err(forbidden());
+        }
+
+        let infinite = is_infinite(request.headers(), !is_move)?;
+        let overwrite = match header_str(request.headers(), "overwrite") {
+            None | Some("T" | "t") => true,
+            Some("F" | "f") => false,
+            Some(_) => return Err(status(StatusCode::BAD_REQUEST)),
+        };
+
+        let target = self
+            .locate(&destination)
+            .await?;
+        if !target
+            .parent()
+            .is_some_and(|parent| parent.is_dir())
+        {
+            return Err(status(StatusCode::CONFLICT));
+        }
+        let existed = std::fs::symlink_metadata(&target).is_ok();
+        if existed && !overwrite {
+            return Err(status(StatusCode::PRECONDITION_FAILED));
+        }
+
+        let source_locked = is_move
+            && !webdav
+                .locks
+                .check(request_path, true, true, tokens);
+        if source_locked
+            || !webdav
+                .locks
+                .check(&destination, true, true, tokens)
+        {
+            return Ok(locked());
+        }
+
+        let (from, to) = (source.clone(), target.clone());
+        unblock(move || {
+            if existed {
+                remove(&to)?;
+            }
+            if is_move {
+                rename(&from, &to)
+            } else {
+                copy(&from, &to, infinite)
+            }
+        })
+        .await
+        .map_err(|e| io_error(&target, e))?;
+
+        if is_move {
+            webdav
+                .locks
+                .remove_within(request_path);
+            self.invalidate(&source)
+                .await;
+        }
+        webdav
+            .locks
+            .remove_within(&destination);
+        self.invalidate(&target)
+            .await;
+        Ok(empty(if existed { StatusCode::NO_CONTENT } else { StatusCode::CREATED }))
+    }
+
+    /// List the properties of a resource, and of its members with `Depth: 1`
+    async fn propfind(
+        &self,
+        webdav: &WebDav,
+        request: Request,
+        request_path: &str,
+    ) -> Result<Response, VetisError> {
+        let with_members = match header_str(request.headers(), "depth") {
+            Some("0") => false,
+            Some("1") => true,
+            // Listing whole trees is refused, as it could be arbitrarily expensive
+            None | Some("infinity" | "Infinity") => {
+                return Ok(xml::error_response(StatusCode::FORBIDDEN, "propfind-finite-depth"))
+            }
+            Some(_) => return Err(status(StatusCode::BAD_REQUEST)),
+        };
+
+        let file = self
+            .locate(request_path)
+            .await?;
+        let metadata = std::fs::metadata(&file)
+            .map_err(|_| VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)))?;
+        let propfind = xml::parse_propfind(
+            &request
+                .text()
+                .await?,
+        )
+        .ok_or_else(|| status(StatusCode::BAD_REQUEST))?;
+
+        let mut multistatus = Multistatus::new();
+        self.describe(webdav, &mut multistatus, request_path, &file, &metadata, &propfind);
+
+        if with_members && metadata.is_dir() {
+            let directory = file.clone();
+            let mut names = unblock(move || {
+                std::fs::read_dir(directory)?
+                    .map(|entry| entry.map(|entry| entry.file_name()))
+                    .collect::<std::io::Result<Vec<_>>>()
+            })
+            .await
+            .map_err(|e| io_error(&file, e))?;
+            names.sort();
+
+            for name in names {
+                let Some(name) = name.to_str() else {
+                    continue;
+                };
+                let member_path = if request_path == "/" {
+                    format!("/{}", name)
+                } else {
+                    format!("{}/{}", request_path, name)
+                };
+                // Members are left out like they would not be served: dotfiles, denied links
+                let Ok(member) = self
+                    .locate(&member_path)
+                    .await
+                else {
+                    continue;
+                };
+                let Ok(metadata) = std::fs::metadata(&member) else {
+                    continue;
+                };
+                self.describe(
+                    webdav,
+                    &mut multistatus,
+                    &member_path,
+                    &member,
+                    &metadata,
+                    &propfind,
+                );
+            }
+        }
+
+        Ok(multistatus.finish())
+    }
+
+    /// Add the requested properties of a resource to a multistatus response
+    fn describe(
+        &self,
+        webdav: &WebDav,
+        multistatus: &mut Multistatus,
+        request_path: &str,
+        file: &std::path::Path,
+        metadata: &std::fs::Metadata,
+        propfind: &Propfind,
+    ) {
+        let is_dir = metadata.is_dir();
+        let properties = self.properties(webdav, request_path, file, metadata);
+        let href = self.href(request_path, is_dir);
+
+        let propstats = match propfind {
+            Propfind::AllProp => vec![(
+                StatusCode::OK,
+                properties
+                    .iter()
+                    .map(|(name, value)| name.element(value))
+                    .collect(),
+            )],
+            Propfind::PropName => vec![(
+                StatusCode::OK,
+                properties
+                    .iter()
+                    .map(|(name, _)| name.element(""))
+                    .collect(),
+            )],
+            Propfind::Prop(names) => {
+                let (found, missing): (Vec<_>, Vec<_>) = names
+                    .iter()
+                    .partition(|name| {
+                        properties
+                            .iter()
+                            .any(|(property, _)| property == *name)
+                    });
+                vec![
+                    (
+                        StatusCode::OK,
+                        found
+                            .into_iter()
+                            .filter_map(|name| {
+                                properties
+                                    .iter()
+                                    .find(|(property, _)| property == name)
+                                    .map(|(name, value)| name.element(value))
+                            })
+                            .collect(),
+                    ),
+                    (
+                        StatusCode::NOT_FOUND,
+                        missing
+                            .into_iter()
+                            .map(|name| name.element(""))
+                            .collect(),
+                    ),
+                ]
+            }
+        };
+
+        multistatus.response(&href, &propstats);
+    }
+
+    /// Live properties of a resource, with their XML values
+    fn properties(
+        &self,
+        webdav: &WebDav,
+        request_path: &str,
+        file: &std::path::Path,
+        metadata: &std::fs::Metadata,
+    ) -> Vec<(PropName, String)> {
+        let escape = super::autoindex::escape_html;
+        let modified = metadata
+            .modified()
+            .ok();
+        let name = request_path
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+
+        let mut properties = Vec::new();
+        if let Some(created) = metadata
+            .created()
+            .ok()
+            .or(modified)
+        {
+            properties.push((PropName::dav("creationdate"), format_rfc3339(created)));
+        }
+        properties.push((PropName::dav("displayname"), escape(name)));
+        if metadata.is_dir() {
+            properties.push((PropName::dav("resourcetype"), "<D:collection/>".to_string()));
+        } else {
+            properties.push((PropName::dav("resourcetype"), String::new()));
+            properties.push((
+                PropName::dav("getcontentlength"),
+                metadata
+                    .len()
+                    .to_string(),
+            ));
+            if let Some(content_type) = self
+                .content_type(file)
+                .and_then(|content_type| {
+                    content_type
+                        .to_str()
+                        .ok()
+                        .map(escape)
+                })
+            {
+                properties.push((PropName::dav("getcontenttype"), content_type));
+            }
+            let validators = Validators::new(metadata.len(), modified);
+            properties.push((PropName::dav("getetag"), escape(validators.etag())));
+        }
+        if let Some(modified) = modified {
+            properties.push((PropName::dav("getlastmodified"), format_http_date(modified)));
+        }
+        properties.push((PropName::dav("supportedlock"), xml::supportedlock()));
+        properties.push((
+            PropName::dav("lockdiscovery"),
+            xml::lockdiscovery(
+                &webdav
+                    .locks
+                    .discover(request_path),
+                |root| self.href(root, false),
+            ),
+        ));
+
+        properties
+    }
+
+    /// Refuse to change properties, dead properties cannot be stored
+    async fn proppatch(
+        &self,
+        webdav: &WebDav,
+        request: Request,
+        request_path: &str,
+        tokens: &[String],
+    ) -> Result<Response, VetisError> {
+        let file = self
+            .locate(request_path)
+            .await?;
+        let metadata = std::fs::metadata(&file)
+            .map_err(|_| VetisError::VirtualHost(VirtualHostError::File(FileError::NotFound)))?;
+        if !webdav
+            .locks
+            .check(request_path, false, false, tokens)
+        {
+            return Ok(locked());
+        }
+
+        let names = xml::parse_proppatch(
+            &request
+                .text()
+                .await?,
+        )
+        .ok_or_else(|| status(StatusCode::BAD_REQUEST))?;
+
+        let mut multistatus = Multistatus::new();
+        multistatus.response(
+            &self.href(request_path, metadata.is_dir()),
+            &[(
+                StatusCode::FORBIDDEN,
+                names
+                    .iter()
+                    .map(|name| name.element(""))
+                    .collect(),
+            )],
+        );
+        Ok(multistatus.finish())
+    }
+
+    /// Lock a resource, creating an empty file at unmapped paths, or refresh a lock
+    async fn lock(
+        &self,
+        webdav: &WebDav,
+        request: Request,
+        request_path: &str,
+        tokens: &[String],
+    ) -> Result<Response, VetisError> {
+        let timeout = lock_timeout(
+            request.headers(),
+            webdav
+                .config
+                .lock_timeout(),
+        );
+        let infinite = is_infinite(request.headers(), true)?;
+        let href = |root: &str| self.href(root, false);
+
+        let body = request
+            .text()
+            .await?;
+        // Locks are refreshed by requests without a body
+        if body
+            .trim()
+            .is_empty()
+        {
+            return match webdav
+                .locks
+                .refresh(request_path, tokens, timeout)
+            {
+                Some(lock) => Ok(xml::lock_response(StatusCode::OK, &lock, href)),
+                None => Err(status(StatusCode::PRECONDITION_FAILED)),
+            };
+        }
+        let lockinfo = xml::parse_lockinfo(&body).ok_or_else(|| status(StatusCode::BAD_REQUEST))?;
+
+        let file = self
+            .locate(request_path)
+            .await?;
+        let exists = std::fs::symlink_metadata(&file).is_ok();
+        if !exists
+            && !file
+                .parent()
+                .is_some_and(|parent| parent.is_dir())
+        {
+            return Err(status(StatusCode::CONFLICT));
+        }
+
+        let Some(lock) =
+            webdav
+                .locks
+                .lock(request_path, lockinfo.scope, infinite, lockinfo.owner, timeout)
+        else {
+            return Ok(locked());
+        };
+        if exists {
+            return Ok(xml::lock_response(StatusCode::OK, &lock, href));
+        }
+
+        // Unmapped paths are locked as empty files
+        let target = file.clone();
+        let created = unblock(move || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(target)
+        })
+        .await;
+        if let Err(e) = created {
+            webdav
+                .locks
+                .unlock(request_path, &lock.token);
+            return Err(io_error(&file, e));
+        }
+
+        self.invalidate(&file)
+            .await;
+        Ok(xml::lock_response(StatusCode::CREATED, &lock, href))
+    }
+
+    /// Release the lock of the `Lock-Token` header
+    fn unlock(
+        &self,
+        webdav: &WebDav,
+        request: &Request,
+        request_path: &str,
+    ) -> Result<Response, VetisError> {
+        let token = header_str(request.headers(), "lock-token")
+            .map(|token| {
+                token
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+            })
+            .ok_or_else(|| status(StatusCode::BAD_REQUEST))?;
+
+        if webdav
+            .locks
+            .unlock(request_path, token)
+        {
+            Ok(empty(StatusCode::NO_CONTENT))
+        } else {
+            Ok(xml::error_response(StatusCode::CONFLICT, "lock-token-matches-request-uri"))
+        }
+    }
+}
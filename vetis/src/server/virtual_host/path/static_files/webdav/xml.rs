@@ -0,0 +1,348 @@
+//! XML bodies of WebDAV requests and responses
+//!
+//! Responses declare the `DAV:` namespace with the `D` prefix, properties of other namespaces
+//! declare their own.
+
+use std::fmt::Write;
+
+use http::{header, HeaderValue, StatusCode};
+use roxmltree::{Document, Node};
+
+use super::{
+    super::autoindex::escape_html,
+    lock::{Lock, LockScope},
+};
+use crate::server::http::Response;
+
+/// Namespace of the WebDAV elements
+const DAV: &str = "DAV:";
+
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n";
+
+/// Name of a property, qualified by its namespace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct PropName {
+    namespace: String,
+    name: String,
+}
+
+impl PropName {
+    /// Property of the `DAV:` namespace
+    pub(super) fn dav(name: &str) -> Self {
+        PropName { namespace: DAV.to_string(), name: name.to_string() }
+    }
+
+    fn from_node(node: Node) -> Self {
+        PropName {
+            namespace: node
+                .tag_name()
+                .namespace()
+                .unwrap_or_default()
+                .to_string(),
+            name: node
+                .tag_name()
+                .name()
+                .to_string(),
+        }
+    }
+
+    /// Element of the property with an XML value, empty when the value is
+    pub(super) fn element(&self, value: &str) -> String {
+        let (name, declaration) = if self.namespace == DAV {
+            (format!("D:{}", self.name), String::new())
+        } else if self
+            .namespace
+            .is_empty()
+        {
+            (self.name.clone(), " xmlns=\"\"".to_string())
+        } else {
+            (format!("R:{}", self.name), format!(" xmlns:R=\"{}\"", escape_html(&self.namespace)))
+        };
+
+        if value.is_empty() {
+            format!("<{}{}/>", name, declaration)
+        } else {
+            format!("<{0}{1}>{2}</{0}>", name, declaration, value)
+        }
+    }
+}
+
+/// Properties requested by `PROPFIND`
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Propfind {
+    /// Every live property
+    AllProp,
+    /// The names of the properties
+    PropName,
+    /// The listed properties
+    Prop(Vec<PropName>),
+}
+
+/// Lock requested by `LOCK`
+#[derive(Debug)]
+pub(super) struct LockInfo {
+    pub(super) scope: LockScope,
+    pub(super) owner: Option<String>,
+}
+
+fn is_dav(node: &Node, name: &str) -> bool {
+    node.is_element()
+        && node
+            .tag_name()
+            .namespace()
+            == Some(DAV)
+        && node
+            .tag_name()
+            .name()
+            == name
+}
+
+/// Child elements of a node
+fn elements<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(Node::is_element)
+}
+
+/// Parse a body whose root element is the given `DAV:` element
+fn parse_root<'input>(body: &'input str, root: &str) -> Option<Document<'input>> {
+    let document = Document::parse(body).ok()?;
+    is_dav(&document.root_element(), root).then_some(document)
+}
+
+/// Parse a `PROPFIND` body, an empty body requesting every live property
+///
+/// # Returns
+///
+/// * `Option<Propfind>` - The requested properties, `None` when the body is invalid
+pub(super) fn parse_propfind(body: &str) -> Option<Propfind> {
+    if body
+        .trim()
+        .is_empty()
+    {
+        return Some(Propfind::AllProp);
+    }
+
+    let document = parse_root(body, "propfind")?;
+    let propfind = elements(document.root_element()).find_map(|node| {
+        if is_dav(&node, "allprop") {
+            Some(Propfind::AllProp)
+        } else if is_dav(&node, "propname") {
+            Some(Propfind::PropName)
+        } else if is_dav(&node, "prop") {
+            Some(Propfind::Prop(
+                elements(node)
+                    .map(PropName::from_node)
+                    .collect(),
+            ))
+        } else {
+            None
+        }
+    });
+    propfind
+}
+
+/// Parse a `PROPPATCH` body
+///
+/// # Returns
+///
+/// * `Option<Vec<PropName>>` - The set and removed properties, `None` when the body is invalid
+pub(super) fn parse_proppatch(body: &str) -> Option<Vec<PropName>> {
+    let document = parse_root(body, "propertyupdate")?;
+
+    Some(
+        elements(document.root_element())
+            .filter(|node| is_dav(node, "set") || is_dav(node, "remove"))
+            .flat_map(elements)
+            .filter(|node| is_dav(node, "prop"))
+            .flat_map(elements)
+            .map(PropName::from_node)
+            .collect(),
+    )
+}
+
+/// Parse a `LOCK` body creating a write lock
+///
+/// # Returns
+///
+/// * `Option<LockInfo>` - The lock, `None` when the body is invalid
+pub(super) fn parse_lockinfo(body: &str) -> Option<LockInfo> {
+    let document = parse_root(body, "lockinfo")?;
+    let root = document.root_element();
+
+    let scope = elements(root)
+        .filter(|node| is_dav(node, "lockscope"))
+        .flat_map(elements)
+        .find_map(|node| {
+            if is_dav(&node, "exclusive") {
+                Some(LockScope::Exclusive)
+            } else if is_dav(&node, "shared") {
+                Some(LockScope::Shared)
+            } else {
+                None
+            }
+        })?;
+    let is_write = elements(root)
+        .filter(|node| is_dav(node, "locktype"))
+        .flat_map(elements)
+        .any(|node| is_dav(&node, "write"));
+    if !is_write {
+        return None;
+    }
+
+    // Owners are kept as a link or as text, other markup is dropped
+    let owner = elements(root)
+        .find(|node| is_dav(node, "owner"))
+        .and_then(|owner| {
+            if let Some(href) = elements(owner).find(|node| is_dav(node, "href")) {
+                let href = text(href);
+                return Some(format!("<D:href>{}</D:href>", escape_html(href.trim())));
+            }
+            let text = text(owner);
+            let text = text.trim();
+            (!text.is_empty()).then(|| escape_html(text))
+        });
+
+    Some(LockInfo { scope, owner })
+}
+
+/// Text of a node and of its descendants
+fn text(node: Node) -> String {
+    node.descendants()
+        .filter(Node::is_text)
+        .filter_map(|node| node.text())
+        .collect()
+}
+
+/// `lockdiscovery` value of locks
+///
+/// # Arguments
+///
+/// * `locks` - The locks
+/// * `href` - The link of a request path
+pub(super) fn lockdiscovery(locks: &[Lock], href: impl Fn(&str) -> String) -> String {
+    let mut discovery = String::new();
+    for lock in locks {
+        let scope = match lock.scope {
+            LockScope::Exclusive => "exclusive",
+            LockScope::Shared => "shared",
+        };
+        let depth = if lock.infinite { "infinity" } else { "0" };
+        let _ = write!(
+            discovery,
+            "<D:activelock><D:locktype><D:write/></D:locktype><D:lockscope><D:{}/></D:lockscope>\
+             <D:depth>{}</D:depth>",
+            scope, depth
+        );
+        if let Some(owner) = &lock.owner {
+            let _ = write!(discovery, "<D:owner>{}</D:owner>", owner);
+        }
+        let _ = write!(
+            discovery,
+            "<D:timeout>Second-{}</D:timeout><D:locktoken><D:href>{}</D:href></D:locktoken>\
+             <D:lockroot><D:href>{}</D:href></D:lockroot></D:activelock>",
+            lock.timeout
+                .as_secs(),
+            lock.token,
+            escape_html(&href(&lock.root))
+        );
+    }
+    discovery
+}
+
+/// `supportedlock` value, exclusive and shared write locks
+pub(super) fn supportedlock() -> String {
+    ["exclusive", "shared"]
+        .iter()
+        .map(|scope| {
+            format!(
+                "<D:lockentry><D:lockscope><D:{}/></D:lockscope><D:locktype><D:write/></D:locktype></D:lockentry>",
+                scope
+            )
+        })
+        .collect()
+}
+
+/// Response of a `LOCK` request, the `lockdiscovery` of the lock
+pub(super) fn lock_response(
+    status: StatusCode,
+    lock: &Lock,
+    href: impl Fn(&str) -> String,
+) -> Response {
+    let body = format!(
+        "{}<D:prop xmlns:D=\"DAV:\"><D:lockdiscovery>{}</D:lockdiscovery></D:prop>",
+        XML_DECLARATION,
+        lockdiscovery(std::slice::from_ref(lock), href)
+    );
+
+    let mut builder = Response::builder().status(status);
+    if let Ok(token) = HeaderValue::from_str(&format!("<{}>", lock.token)) {
+        builder = builder.header("lock-token", token);
+    }
+    builder
+        .header(header::CONTENT_TYPE, HeaderValue::from_static("application/xml; charset=utf-8"))
+        .bytes(body.as_bytes())
+}
+
+/// Response with a precondition or postcondition code, such as `propfind-finite-depth`
+pub(super) fn error_response(status: StatusCode, condition: &str) -> Response {
+    let body = format!("{}<D:error xmlns:D=\"DAV:\"><D:{}/></D:error>", XML_DECLARATION, condition);
+
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, HeaderValue::from_static("application/xml; charset=utf-8"))
+        .bytes(body.as_bytes())
+}
+
+/// `207 Multi-Status` response, built one resource at a time
+pub(super) struct Multistatus {
+    body: String,
+}
+
+impl Multistatus {
+    pub(super) fn new() -> Self {
+        Multistatus { body: format!("{}<D:multistatus xmlns:D=\"DAV:\">", XML_DECLARATION) }
+    }
+
+    /// Add the properties of a resource, grouped by status
+    ///
+    /// # Arguments
+    ///
+    /// * `href` - The link of the resource
+    /// * `propstats` - The property elements of each status, empty groups are left out
+    pub(super) fn response(&mut self, href: &str, propstats: &[(StatusCode, Vec<String>)]) {
+        let _ = write!(self.body, "<D:response><D:href>{}</D:href>", escape_html(href));
+        for (status, properties) in propstats {
+            if properties.is_empty() {
+                continue;
+            }
+            let _ = write!(self.body, "<D:propstat><D:prop>{}</D:prop>", properties.concat());
+            let _ = write!(self.body, "<D:status>{}</D:status></D:propstat>", status_line(*status));
+        }
+        self.body
+            .push_str("</D:response>");
+    }
+
+    pub(super) fn finish(mut self) -> Response {
+        self.body
+            .push_str("</D:multistatus>");
+
+        Response::builder()
+            .status(StatusCode::MULTI_STATUS)
+            .header(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/xml; charset=utf-8"),
+            )
+            .bytes(self.body.as_bytes())
+    }
+}
+
+/// Status line of a `status` element, such as `HTTP/1.1 404 Not Found`
+fn status_line(status: StatusCode) -> String {
+    format!(
+        "HTTP/1.1 {} {}",
+        status.as_str(),
+        status
+            .canonical_reason()
+            .unwrap_or_default()
+    )
+}
@@ -0,0 +1,121 @@
+//! Paths accepting WebSocket connections
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use crate::{
+    errors::VetisError,
+    server::{
+        http::{websocket, Request, Response, WebSocket},
+        virtual_host::{
+            path::{HostPath, Path},
+            router::PathPattern,
+        },
+    },
+};
+
+type BoxedWebSocketHandler =
+    Arc<dyn Fn(Request, WebSocket) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Path accepting WebSocket connections
+///
+/// Requests are upgraded over HTTP/1.1 or with HTTP/2 extended CONNECT, then the
+/// handler receives the request and the WebSocket. Requests which are not WebSocket
+/// handshakes are answered with `400 Bad Request`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::server::{http::Message, virtual_host::path::websocket::WebSocketPath};
+///
+/// let path = WebSocketPath::new("/chat/:room", |request, mut socket| async move {
+///     let room = request.param("room").unwrap_or_default().to_string();
+///     while let Ok(Some(Message::Text(text))) = socket.recv().await {
+///         let _ = socket.send(Message::Text(format!("{}: {}", room, text))).await;
+///     }
+/// })?;
+///
+/// virtual_host.add_path(path);
+/// ```
+pub struct WebSocketPath {
+    uri: String,
+    pattern: Option<PathPattern>,
+    handler: BoxedWebSocketHandler,
+}
+
+impl WebSocketPath {
+    /// Create a new WebSocket path
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The uri of the path, which can have parameters such as `/chat/:room`
+    /// * `handler` - The function serving each WebSocket connection
+    ///
+    /// # Returns
+    ///
+    /// * `Result<WebSocketPath, VetisError>` - The WebSocket path, or an error if the uri is invalid
+    pub fn new<F, Fut>(uri: &str, handler: F) -> Result<WebSocketPath, VetisError>
+    where
+        F: Fn(Request, WebSocket) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let pattern = PathPattern::parse(uri)?;
+
+        Ok(WebSocketPath {
+            uri: uri.to_string(),
+            pattern,
+            handler: Arc::new(move |request, socket| Box::pin(handler(request, socket))),
+        })
+    }
+
+    /// Returns the pattern of paths with parameters
+    pub(crate) fn pattern(&self) -> Option<&PathPattern> {
+        self.pattern
+            .as_ref()
+    }
+}
+
+impl From<WebSocketPath> for HostPath {
+    /// Convert WebSocket path to host path
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The WebSocket path to convert
+    ///
+    /// # Returns
+    ///
+    /// * `HostPath` - The host path
+    fn from(value: WebSocketPath) -> Self {
+        HostPath::WebSocket(value)
+    }
+}
+
+impl Path for WebSocketPath {
+    /// Returns the uri of the WebSocket path
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The uri of the WebSocket path
+    fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Accepts the WebSocket handshake and spawns the handler
+    ///
+    /// # Returns
+    ///
+    /// * `Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>>` - The response accepting the handshake
+    fn handle(
+        &self,
+        mut request: Request,
+        _uri: Arc<String>,
+    ) -> Pin<Box<dyn Future<Output = Result<Response, VetisError>> + Send + '_>> {
+        let handler = self.handler.clone();
+
+        Box::pin(async move {
+            let (response, on_upgrade) = websocket::accept(&mut request)?;
+            websocket::spawn(on_upgrade, move |socket| handler(request, socket));
+
+            Ok(response)
+        })
+    }
+}
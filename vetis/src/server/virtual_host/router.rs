@@ -0,0 +1,257 @@
+//! Router matching request paths against handler path patterns
+//!
+//! Patterns are made of `/` separated segments, where a segment is either a literal,
+//! a named parameter such as `:id`, or a trailing wildcard such as `*rest` capturing
+//! the remainder of the path.
+
+use percent_encoding::percent_decode_str;
+
+use crate::{
+    errors::{HandlerError, VetisError, VirtualHostError},
+    server::virtual_host::path::HostPath,
+};
+
+/// Parameters captured from the request path
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use vetis::server::{http::Request, virtual_host::router::PathParams};
+///
+/// // Handler registered for "/users/:id/posts/*rest"
+/// async fn handler(request: Request) -> Result<vetis::Response, vetis::VetisError> {
+///     let id = request.param("id");
+///     let rest = request.params().get("rest");
+///     Ok(/* response */)
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathParams {
+    params: Vec<(String, String)>,
+}
+
+impl PathParams {
+    pub(crate) const fn new() -> Self {
+        Self { params: Vec::new() }
+    }
+
+    /// Returns the value captured for a parameter
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The parameter name, without the leading `:` or `*`
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - The percent decoded value, `None` if the pattern has no such parameter
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns an iterator over the captured parameters, in pattern order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Returns the number of captured parameters
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Returns whether no parameter was captured
+    pub fn is_empty(&self) -> bool {
+        self.params
+            .is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Static(String),
+    Param(String),
+    Wildcard(String),
+}
+
+impl Segment {
+    /// Ranks segments so literals win over parameters, and parameters over wildcards
+    fn rank(&self) -> u8 {
+        match self {
+            Segment::Static(_) => 2,
+            Segment::Param(_) => 1,
+            Segment::Wildcard(_) => 0,
+        }
+    }
+}
+
+/// A parsed handler path pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PathPattern {
+    segments: Vec<Segment>,
+}
+
+impl PathPattern {
+    /// Parse a handler uri
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The handler uri, such as `/users/:id/posts/*rest`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Self>, VetisError>` - The pattern, `None` if the uri has no parameter
+    ///   and is matched by prefix like other paths.
+    pub(crate) fn parse(uri: &str) -> Result<Option<Self>, VetisError> {
+        let Some(path) = uri.strip_prefix('/') else {
+            return Ok(None);
+        };
+
+        let invalid = |message: String| {
+            VetisError::VirtualHost(VirtualHostError::Handler(HandlerError::Uri(message)))
+        };
+
+        let parts: Vec<&str> = path
+            .split('/')
+            .collect();
+        let mut segments = Vec::with_capacity(parts.len());
+        for (index, part) in parts
+            .iter()
+            .enumerate()
+        {
+            let segment = if let Some(name) = part.strip_prefix(':') {
+                Segment::Param(name.to_string())
+            } else if let Some(name) = part.strip_prefix('*') {
+                if index != parts.len() - 1 {
+                    return Err(invalid(format!("Wildcard must be the last segment: {}", uri)));
+                }
+                Segment::Wildcard(name.to_string())
+            } else {
+                Segment::Static(part.to_string())
+            };
+
+            if let Segment::Param(name) | Segment::Wildcard(name) = &segment {
+                if name.is_empty() {
+                    return Err(invalid(format!("Unnamed parameter in uri: {}", uri)));
+                }
+
+                let duplicated = segments
+                    .iter()
+                    .any(|other| {
+                        matches!(other, Segment::Param(other) | Segment::Wildcard(other) if other == name)
+                    });
+                if duplicated {
+                    return Err(invalid(format!("Duplicated parameter {} in uri: {}", name, uri)));
+                }
+            }
+
+            segments.push(segment);
+        }
+
+        let has_params = segments
+            .iter()
+            .any(|segment| !matches!(segment, Segment::Static(_)));
+
+        Ok(has_params.then_some(Self { segments }))
+    }
+
+    /// Match a request path
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(Vec<u8>, PathParams)>` - The match rank and captured parameters, `None` if
+    ///   the path does not match.
+    fn matches(&self, path: &str) -> Option<(Vec<u8>, PathParams)> {
+        let parts: Vec<&str> = path
+            .strip_prefix('/')?
+            .split('/')
+            .collect();
+
+        let mut rank = Vec::with_capacity(self.segments.len());
+        let mut params = PathParams::default();
+        for (index, segment) in self
+            .segments
+            .iter()
+            .enumerate()
+        {
+            match segment {
+                Segment::Static(literal) => {
+                    if parts.get(index) != Some(&literal.as_str()) {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    let value = parts
+                        .get(index)
+                        .filter(|value| !value.is_empty())?;
+                    params
+                        .params
+                        .push((name.clone(), decode(value)));
+                }
+                Segment::Wildcard(name) => {
+                    let rest = parts
+                        .get(index..)
+                        .unwrap_or_default()
+                        .join("/");
+                    params
+                        .params
+                        .push((name.clone(), decode(&rest)));
+                    rank.push(segment.rank());
+                    return Some((rank, params));
+                }
+            }
+            rank.push(segment.rank());
+        }
+
+        (parts.len() == self.segments.len()).then_some((rank, params))
+    }
+}
+
+fn decode(value: &str) -> String {
+    percent_decode_str(value)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Handler paths registered with a pattern
+///
+/// When several patterns match, the most specific one wins, comparing segments from
+/// left to right.
+#[derive(Default)]
+pub(crate) struct Router {
+    routes: Vec<(PathPattern, HostPath)>,
+}
+
+impl Router {
+    /// Add a path, replacing the one registered with the same pattern
+    pub(crate) fn insert(&mut self, pattern: PathPattern, path: HostPath) {
+        self.routes
+            .retain(|(other, _)| *other != pattern);
+        self.routes
+            .push((pattern, path));
+    }
+
+    /// Find the path matching a request path
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The request path.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(&HostPath, PathParams)>` - The matching path and the captured parameters.
+    pub(crate) fn find(&self, path: &str) -> Option<(&HostPath, PathParams)> {
+        self.routes
+            .iter()
+            .filter_map(|(pattern, host_path)| {
+                pattern
+                    .matches(path)
+                    .map(|(rank, params)| (rank, host_path, params))
+            })
+            .max_by(|(rank, ..), (other, ..)| rank.cmp(other))
+            .map(|(_, host_path, params)| (host_path, params))
+    }
+}
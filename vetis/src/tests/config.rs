@@ -1,8 +1,10 @@
-use std::error::Error;
+use std::{error::Error, time::Duration};
 
 use crate::{
     config::server::{
-        virtual_host::{SecurityConfig, VirtualHostConfig},
+        virtual_host::{
+            SecurityConfig, VirtualHostConfig, DEFAULT_BODY_TIMEOUT, DEFAULT_MAX_BODY_SIZE,
+        },
         ListenerConfig, Protocol, ServerConfig,
     },
     errors::{ConfigError, VetisError},
@@ -44,6 +46,17 @@ fn test_server_config() -> Result<(), Box<dyn Error>> {
             .len(),
         1
     );
+    assert_eq!(server_config.grace_period(), Duration::from_secs(30));
+
+    let server_config = ServerConfig::builder()
+        .add_listener(
+            ListenerConfig::builder()
+                .port(8080)
+                .build()?,
+        )
+        .grace_period(Duration::from_secs(5))
+        .build()?;
+    assert_eq!(server_config.grace_period(), Duration::from_secs(5));
 
     Ok(())
 }
@@ -61,6 +74,19 @@ fn test_security_config() -> Result<(), Box<dyn Error>> {
         Some(VetisError::Config(ConfigError::Security("Missing certificate".to_string())))
     );
 
+    let security_config = SecurityConfig::builder()
+        .cert_from_bytes(vec![1])
+        .key_from_bytes(vec![1])
+        .client_auth(true)
+        .build();
+
+    assert_eq!(
+        security_config.err(),
+        Some(VetisError::Config(ConfigError::Security(
+            "Client authentication requires a CA certificate".to_string()
+        )))
+    );
+
     Ok(())
 }
 
@@ -73,6 +99,16 @@ fn test_virtual_host_config() -> Result<(), Box<dyn std::error::Error>> {
         .build()?;
     assert_eq!(virtual_host_config.hostname(), "localhost");
     assert_eq!(virtual_host_config.port(), 8080);
+    assert_eq!(virtual_host_config.max_body_size(), DEFAULT_MAX_BODY_SIZE);
+    assert_eq!(virtual_host_config.body_timeout(), DEFAULT_BODY_TIMEOUT);
+
+    let virtual_host_config = VirtualHostConfig::builder()
+        .root_directory("src/tests")
+        .max_body_size(1024)
+        .body_timeout(Duration::from_secs(5))
+        .build()?;
+    assert_eq!(virtual_host_config.max_body_size(), 1024);
+    assert_eq!(virtual_host_config.body_timeout(), Duration::from_secs(5));
 
     Ok(())
 }
@@ -103,6 +139,62 @@ fn test_invalid_virtual_host_config() -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+#[test]
+fn test_virtual_host_aliases_config() -> Result<(), Box<dyn std::error::Error>> {
+    let virtual_host_config = VirtualHostConfig::builder()
+        .hostname("example.com")
+        .alias("www.example.com")
+        .alias("*.example.org")
+        .default(true)
+        .root_directory("src/tests")
+        .build()?;
+    assert_eq!(
+        virtual_host_config.aliases(),
+        &Some(vec!["www.example.com".to_string(), "*.example.org".to_string()])
+    );
+    assert!(virtual_host_config.is_default());
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_wildcard_virtual_host_config() -> Result<(), Box<dyn std::error::Error>> {
+    let virtual_host_config = VirtualHostConfig::builder()
+        .hostname("www.*.com")
+        .root_directory("src/tests")
+        .build();
+    assert_eq!(
+        virtual_host_config.err(),
+        Some(VetisError::Config(ConfigError::VirtualHost(
+            "Invalid wildcard hostname: www.*.com, only a leading *. is allowed".to_string()
+        )))
+    );
+
+    let virtual_host_config = VirtualHostConfig::builder()
+        .hostname("example.com")
+        .alias("*.")
+        .root_directory("src/tests")
+        .build();
+    assert_eq!(
+        virtual_host_config.err(),
+        Some(VetisError::Config(ConfigError::VirtualHost(
+            "Invalid wildcard hostname: *., only a leading *. is allowed".to_string()
+        )))
+    );
+
+    let virtual_host_config = VirtualHostConfig::builder()
+        .hostname("example.com")
+        .alias("")
+        .root_directory("src/tests")
+        .build();
+    assert_eq!(
+        virtual_host_config.err(),
+        Some(VetisError::Config(ConfigError::VirtualHost("Empty alias".to_string())))
+    );
+
+    Ok(())
+}
+
 #[cfg(feature = "static-files")]
 mod static_files_tests {
     use crate::config::server::virtual_host::path::static_files::StaticPathConfig;
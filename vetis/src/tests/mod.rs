@@ -7,6 +7,9 @@ pub(crate) const CA_CERT: &[u8] = include_bytes!("certs/ca.der");
 pub(crate) const SERVER_CERT: &[u8] = include_bytes!("certs/server.der");
 pub(crate) const SERVER_KEY: &[u8] = include_bytes!("certs/server.key.der");
 
+pub(crate) const CLIENT_CERT: &[u8] = include_bytes!("certs/client.der");
+pub(crate) const CLIENT_KEY: &[u8] = include_bytes!("certs/client.key.der");
+
 pub(crate) const IP6_SERVER_CERT: &[u8] = include_bytes!("certs/ip6-server.der");
 pub(crate) const IP6_SERVER_KEY: &[u8] = include_bytes!("certs/ip6-server.key.der");
 
@@ -25,6 +28,13 @@ pub(crate) const fn default_protocol() -> Protocol {
     }
 }
 
+pub(crate) async fn sleep(duration: std::time::Duration) {
+    #[cfg(feature = "tokio-rt")]
+    tokio::time::sleep(duration).await;
+    #[cfg(feature = "smol-rt")]
+    smol::Timer::after(duration).await;
+}
+
 #[cfg(test)]
 mod config;
 #[cfg(test)]
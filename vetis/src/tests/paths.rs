@@ -57,7 +57,7 @@ mod handler {
         let mut server = crate::Vetis::new(config);
         server
             .add_virtual_host(localhost_virtual_host)
-            .await;
+            .await?;
 
         server
             .start()
@@ -99,6 +99,8 @@ mod static_files {
 
     use deboa::{cert::Certificate, request};
     use http::StatusCode;
+    use http_body_util::BodyExt;
+    use hyper_body_utils::HttpBody;
 
     #[cfg(feature = "auth")]
     use crate::config::server::virtual_host::path::auth::BasicAuthConfig;
@@ -111,12 +113,16 @@ mod static_files {
     use crate::{
         config::server::{
             virtual_host::{
-                path::static_files::StaticPathConfig, SecurityConfig, VirtualHostConfig,
+                path::static_files::{StaticPathConfig, StaticPathConfigBuilder},
+                SecurityConfig, VirtualHostConfig,
             },
             ListenerConfig, ServerConfig,
         },
         errors::{ConfigError, VetisError},
-        server::virtual_host::{path::static_files::StaticPath, VirtualHost},
+        server::{
+            http::Request,
+            virtual_host::{path::static_files::StaticPath, VirtualHost},
+        },
         tests::{default_protocol, CA_CERT, SERVER_CERT, SERVER_KEY},
     };
 
@@ -213,7 +219,7 @@ mod static_files {
         let mut server = crate::Vetis::new(config);
         server
             .add_virtual_host(virtual_host)
-            .await;
+            .await?;
 
         server
             .start()
@@ -292,7 +298,7 @@ mod static_files {
         let mut server = crate::Vetis::new(config);
         server
             .add_virtual_host(virtual_host)
-            .await;
+            .await?;
 
         server
             .start()
@@ -333,6 +339,1296 @@ mod static_files {
         do_not_found().await
     }
 
+    async fn send(
+        virtual_host: &VirtualHost,
+        method: http::Method,
+        uri: &str,
+        headers: &[(http::header::HeaderName, &str)],
+    ) -> Result<(http::response::Parts, String), Box<dyn Error>> {
+        send_body(virtual_host, method, uri, headers, "").await
+    }
+
+    async fn send_body(
+        virtual_host: &VirtualHost,
+        method: http::Method,
+        uri: &str,
+        headers: &[(http::header::HeaderName, &str)],
+        body: &str,
+    ) -> Result<(http::response::Parts, String), Box<dyn Error>> {
+        let mut request = http::Request::builder()
+            .method(method)
+            .uri(uri);
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+        let (parts, body) = request
+            .body(HttpBody::from_text(body))?
+            .into_parts();
+
+        let response = virtual_host
+            .route(Request::from_parts(parts, body))
+            .await?;
+        let (parts, body) = response
+            .into_inner()
+            .into_parts();
+        let body = body
+            .collect()
+            .await?
+            .to_bytes();
+
+        Ok((parts, String::from_utf8(body.to_vec())?))
+    }
+
+    async fn get_listing(
+        virtual_host: &VirtualHost,
+        uri: &str,
+        accept: &str,
+    ) -> Result<(http::response::Parts, String), Box<dyn Error>> {
+        send(virtual_host, http::Method::GET, uri, &[(http::header::ACCEPT, accept)]).await
+    }
+
+    async fn do_autoindex() -> Result<(), Box<dyn Error>> {
+        let host_config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(host_config);
+        virtual_host.add_path(StaticPath::new(
+            StaticPathConfig::builder()
+                .uri("/files")
+                .directory("src/tests/files/listing")
+                .extensions("\\.(html|txt)$")
+                .autoindex(true)
+                .build()?,
+        ));
+        virtual_host.add_path(StaticPath::new(
+            StaticPathConfig::builder()
+                .uri("/plain")
+                .directory("src/tests/files/listing")
+                .build()?,
+        ));
+
+        let (parts, body) = get_listing(&virtual_host, "/files/", "text/html").await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(parts.headers[http::header::CONTENT_TYPE], "text/html; charset=utf-8");
+        assert!(body.contains("<title>Index of /files/</title>"));
+
+        // Directories first, then files by name, without dotfiles or other extensions
+        let sub = body
+            .find("<a href=\"/files/sub/\">sub/</a>")
+            .ok_or("missing sub directory")?;
+        let html = body
+            .find("<a href=\"/files/a.html\">a.html</a>")
+            .ok_or("missing a.html")?;
+        let txt = body
+            .find("<a href=\"/files/b.txt\">b.txt</a></td><td>6</td>")
+            .ok_or("missing b.txt")?;
+        assert!(sub < html && html < txt);
+        assert!(!body.contains(".hidden"));
+        assert!(!body.contains("skip.bin"));
+
+        let (parts, body) = get_listing(&virtual_host, "/files/sub", "application/json").await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(parts.headers[http::header::CONTENT_TYPE], "application/json");
+        let entries: serde_json::Value = serde_json::from_str(&body)?;
+        assert_eq!(entries[0]["name"], "c.txt");
+        assert_eq!(entries[0]["type"], "file");
+        assert_eq!(entries[0]["size"], 7);
+        assert!(entries[0]["modified"].is_string());
+        assert_eq!(
+            entries
+                .as_array()
+                .map(Vec::len),
+            Some(1)
+        );
+
+        let (parts, _) = get_listing(&virtual_host, "/plain/", "text/html").await?;
+        assert_eq!(parts.status, StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_autoindex() -> Result<(), Box<dyn Error>> {
+        do_autoindex().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_autoindex() -> Result<(), Box<dyn Error>> {
+        do_autoindex().await
+    }
+
+    /// Virtual host serving the files of a directory, with a new file cache
+    fn directory_host(directory: &std::path::Path) -> Result<VirtualHost, Box<dyn Error>> {
+        directory_host_with(directory, |path| path)
+    }
+
+    /// Virtual host serving the files of a directory, with additional path settings
+    fn directory_host_with(
+        directory: &std::path::Path,
+        configure: impl FnOnce(StaticPathConfigBuilder) -> StaticPathConfigBuilder,
+    ) -> Result<VirtualHost, Box<dyn Error>> {
+        let host_config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .build()?;
+
+        let path_config = StaticPathConfig::builder()
+            .uri("/")
+            .directory(
+                directory
+                    .to_str()
+                    .ok_or("invalid temporary directory")?,
+            )
+            .extensions("\\.(txt|js)$");
+
+        let mut virtual_host = VirtualHost::new(host_config);
+        virtual_host.add_path(StaticPath::new(configure(path_config).build()?));
+
+        Ok(virtual_host)
+    }
+
+    async fn do_conditional_requests() -> Result<(), Box<dyn Error>> {
+        use http::header::{
+            ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, IF_UNMODIFIED_SINCE,
+            LAST_MODIFIED, RANGE,
+        };
+        use http::Method;
+
+        // A fixed modification date gives a known, strong entity tag
+        let directory = std::env::temp_dir().join("vetis-conditional");
+        std::fs::create_dir_all(&directory)?;
+        let file = std::fs::File::create(directory.join("asset.txt"))?;
+        std::io::Write::write_all(&mut &file, b"hello\n")?;
+        file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000))?;
+
+        let etag = "\"6553f100.0-6\"";
+        let date = "Tue, 14 Nov 2023 22:13:20 GMT";
+        let earlier = "Tue, 14 Nov 2023 22:13:19 GMT";
+
+        for method in [Method::GET, Method::HEAD] {
+            let virtual_host = directory_host(&directory)?;
+            let (parts, _) = send(&virtual_host, method, "/asset.txt", &[]).await?;
+            assert_eq!(parts.status, StatusCode::OK);
+            assert_eq!(parts.headers[ETAG], etag);
+            assert_eq!(parts.headers[LAST_MODIFIED], date);
+        }
+
+        let cases: &[(&[(http::header::HeaderName, &str)], StatusCode)] = &[
+            (&[(IF_NONE_MATCH, "W/\"6553f100.0-6\"")], StatusCode::NOT_MODIFIED),
+            (&[(IF_NONE_MATCH, "\"other\", \"6553f100.0-6\"")], StatusCode::NOT_MODIFIED),
+            (&[(IF_NONE_MATCH, "\"other\"")], StatusCode::OK),
+            (&[(IF_MODIFIED_SINCE, date)], StatusCode::NOT_MODIFIED),
+            (&[(IF_MODIFIED_SINCE, earlier)], StatusCode::OK),
+            // If-None-Match takes precedence over If-Modified-Since
+            (&[(IF_NONE_MATCH, "\"other\""), (IF_MODIFIED_SINCE, date)], StatusCode::OK),
+            (&[(IF_MATCH, "\"other\"")], StatusCode::PRECONDITION_FAILED),
+            (&[(IF_MATCH, "W/\"6553f100.0-6\"")], StatusCode::PRECONDITION_FAILED),
+            (&[(IF_MATCH, "*")], StatusCode::OK),
+            (&[(IF_UNMODIFIED_SINCE, earlier)], StatusCode::PRECONDITION_FAILED),
+            (&[(IF_UNMODIFIED_SINCE, date)], StatusCode::OK),
+            (&[(RANGE, "bytes=0-2"), (IF_RANGE, etag)], StatusCode::PARTIAL_CONTENT),
+            (&[(RANGE, "bytes=0-2"), (IF_RANGE, date)], StatusCode::PARTIAL_CONTENT),
+            (&[(RANGE, "bytes=0-2"), (IF_RANGE, "\"other\"")], StatusCode::OK),
+            (&[(RANGE, "bytes=0-2"), (IF_RANGE, earlier)], StatusCode::OK),
+        ];
+        for (headers, status) in cases {
+            let virtual_host = directory_host(&directory)?;
+            let (parts, body) = send(&virtual_host, Method::GET, "/asset.txt", headers).await?;
+            assert_eq!(parts.status, *status, "{:?}", headers);
+            if parts.status == StatusCode::NOT_MODIFIED {
+                assert_eq!(parts.headers[ETAG], etag);
+                assert!(body.is_empty());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_conditional_requests() -> Result<(), Box<dyn Error>> {
+        do_conditional_requests().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_conditional_requests() -> Result<(), Box<dyn Error>> {
+        do_conditional_requests().await
+    }
+
+    async fn do_ranges() -> Result<(), Box<dyn Error>> {
+        use http::header::{CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+
+        let directory = std::env::temp_dir().join("vetis-ranges");
+        std::fs::create_dir_all(&directory)?;
+        std::fs::write(directory.join("range.txt"), "0123456789abcdefghij")?;
+
+        let cases = [
+            ("bytes=0-4", StatusCode::PARTIAL_CONTENT, Some("bytes 0-4/20"), "01234"),
+            ("bytes=15-", StatusCode::PARTIAL_CONTENT, Some("bytes 15-19/20"), "fghij"),
+            ("bytes=-3", StatusCode::PARTIAL_CONTENT, Some("bytes 17-19/20"), "hij"),
+            ("bytes=18-100", StatusCode::PARTIAL_CONTENT, Some("bytes 18-19/20"), "ij"),
+            (
+                "bytes=-30",
+                StatusCode::PARTIAL_CONTENT,
+                Some("bytes 0-19/20"),
+                "0123456789abcdefghij",
+            ),
+            ("bytes=20-, 30-40", StatusCode::RANGE_NOT_SATISFIABLE, Some("bytes */20"), ""),
+            ("bytes=-0", StatusCode::RANGE_NOT_SATISFIABLE, Some("bytes */20"), ""),
+            // Invalid ranges are ignored
+            ("bytes=5-2", StatusCode::OK, None, "0123456789abcdefghij"),
+            ("bytes=a-b", StatusCode::OK, None, "0123456789abcdefghij"),
+            ("items=0-1", StatusCode::OK, None, "0123456789abcdefghij"),
+        ];
+        for (range, status, content_range, expected) in cases {
+            let virtual_host = directory_host(&directory)?;
+            let (parts, body) =
+                send(&virtual_host, http::Method::GET, "/range.txt", &[(RANGE, range)]).await?;
+            assert_eq!(parts.status, status, "{}", range);
+            assert_eq!(
+                parts
+                    .headers
+                    .get(CONTENT_RANGE)
+                    .and_then(|value| value.to_str().ok()),
+                content_range,
+                "{}",
+                range
+            );
+            assert_eq!(body, expected, "{}", range);
+            if status == StatusCode::PARTIAL_CONTENT {
+                assert_eq!(
+                    parts.headers[CONTENT_LENGTH],
+                    expected
+                        .len()
+                        .to_string()
+                );
+            }
+        }
+
+        let virtual_host = directory_host(&directory)?;
+        let (parts, body) =
+            send(&virtual_host, http::Method::GET, "/range.txt", &[(RANGE, "bytes=0-1, -2")])
+                .await?;
+        assert_eq!(parts.status, StatusCode::PARTIAL_CONTENT);
+        let boundary = parts.headers[CONTENT_TYPE]
+            .to_str()?
+            .strip_prefix("multipart/byteranges; boundary=")
+            .ok_or("missing multipart content type")?;
+        assert_eq!(
+            body,
+            format!(
+                "\r\n--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 0-1/20\r\n\r\n01\
+                 \r\n--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 18-19/20\r\n\r\nij\
+                 \r\n--{b}--\r\n",
+                b = boundary
+            )
+        );
+        assert_eq!(
+            parts.headers[CONTENT_LENGTH],
+            body.len()
+                .to_string()
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_ranges() -> Result<(), Box<dyn Error>> {
+        do_ranges().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_ranges() -> Result<(), Box<dyn Error>> {
+        do_ranges().await
+    }
+
+    async fn do_precompressed() -> Result<(), Box<dyn Error>> {
+        use http::header::{
+            ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_NONE_MATCH,
+            RANGE, VARY,
+        };
+
+        let directory = std::env::temp_dir().join("vetis-precompressed");
+        std::fs::create_dir_all(&directory)?;
+        std::fs::write(directory.join("app.js"), "console.log('app');")?;
+        std::fs::write(directory.join("app.js.br"), "brotli")?;
+        std::fs::write(directory.join("app.js.gz"), "gzip")?;
+
+        let precompressed = |path: StaticPathConfigBuilder| path.precompressed(true);
+
+        let cases = [
+            (None, None, "console.log('app');"),
+            (Some("gzip, br"), Some("br"), "brotli"),
+            (Some("gzip"), Some("gzip"), "gzip"),
+            (Some("x-gzip"), Some("gzip"), "gzip"),
+            (Some("br;q=0.5, gzip"), Some("gzip"), "gzip"),
+            (Some("*"), Some("br"), "brotli"),
+            (Some("*, br;q=0"), Some("gzip"), "gzip"),
+            (Some("zstd"), None, "console.log('app');"),
+        ];
+        let mut etags = Vec::new();
+        for (accept_encoding, encoding, expected) in cases {
+            let virtual_host = directory_host_with(&directory, precompressed)?;
+            let headers: Vec<_> = accept_encoding
+                .map(|accept_encoding| (ACCEPT_ENCODING, accept_encoding))
+                .into_iter()
+                .collect();
+            let (parts, body) = send(&virtual_host, http::Method::GET, "/app.js", &headers).await?;
+            assert_eq!(parts.status, StatusCode::OK);
+            assert_eq!(body, expected, "{:?}", accept_encoding);
+            assert_eq!(
+                parts
+                    .headers
+                    .get(CONTENT_ENCODING)
+                    .and_then(|value| value.to_str().ok()),
+                encoding
+            );
+            assert_eq!(parts.headers[VARY], "accept-encoding");
+            if encoding.is_some() {
+                assert_eq!(parts.headers[CONTENT_TYPE], "text/javascript; charset=utf-8");
+            }
+            etags.push(parts.headers[ETAG].clone());
+        }
+        // Each variant has its own entity tag
+        assert_ne!(etags[0], etags[1]);
+        assert_ne!(etags[1], etags[2]);
+
+        let virtual_host = directory_host_with(&directory, precompressed)?;
+        let (parts, _) = send(
+            &virtual_host,
+            http::Method::GET,
+            "/app.js",
+            &[(ACCEPT_ENCODING, "br"), (IF_NONE_MATCH, etags[1].to_str()?)],
+        )
+        .await?;
+        assert_eq!(parts.status, StatusCode::NOT_MODIFIED);
+        assert_eq!(parts.headers[VARY], "accept-encoding");
+
+        // Ranges apply to the bytes of the variant
+        let virtual_host = directory_host_with(&directory, precompressed)?;
+        let (parts, body) = send(
+            &virtual_host,
+            http::Method::GET,
+            "/app.js",
+            &[(ACCEPT_ENCODING, "br"), (RANGE, "bytes=0-1")],
+        )
+        .await?;
+        assert_eq!(parts.status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(parts.headers[CONTENT_RANGE], "bytes 0-1/6");
+        assert_eq!(parts.headers[CONTENT_ENCODING], "br");
+        assert_eq!(body, "br");
+
+        let virtual_host = directory_host(&directory)?;
+        let (parts, body) =
+            send(&virtual_host, http::Method::GET, "/app.js", &[(ACCEPT_ENCODING, "br")]).await?;
+        assert_eq!(body, "console.log('app');");
+        assert!(!parts
+            .headers
+            .contains_key(VARY));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_precompressed() -> Result<(), Box<dyn Error>> {
+        do_precompressed().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_precompressed() -> Result<(), Box<dyn Error>> {
+        do_precompressed().await
+    }
+
+    async fn do_content_type_and_cache_control() -> Result<(), Box<dyn Error>> {
+        use http::header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, EXPIRES, IF_NONE_MATCH};
+
+        use crate::config::server::virtual_host::path::static_files::CacheRule;
+
+        let directory = std::env::temp_dir().join("vetis-content-type");
+        std::fs::create_dir_all(directory.join("assets"))?;
+        std::fs::write(directory.join("index.html"), "<h1>index</h1>")?;
+        std::fs::write(directory.join("notes.txt"), "notes")?;
+        std::fs::write(directory.join("assets/app.3f2a9c1d.js"), "app")?;
+        std::fs::write(directory.join("assets/app.js"), "app")?;
+        std::fs::write(directory.join("track.vtt"), "WEBVTT")?;
+        std::fs::write(directory.join("image.svg"), "<svg/>")?;
+        std::fs::write(directory.join("blob.unknownext"), "blob")?;
+
+        let configure = |path: StaticPathConfigBuilder| {
+            path.mime_type(".VTT", "text/vtt")
+                .cache_rule(CacheRule::for_pattern(
+                    r"^/assets/.+\.[0-9a-f]{8}\.js$",
+                    "public, max-age=31536000, immutable",
+                ))
+                .cache_rule(CacheRule::for_extensions(&["html"], "no-cache"))
+        };
+
+        let cases = [
+            ("/index.html", "text/html; charset=utf-8", Some("no-cache")),
+            ("/notes.txt", "text/plain; charset=utf-8", None),
+            (
+                "/assets/app.3f2a9c1d.js",
+                "text/javascript; charset=utf-8",
+                Some("public, max-age=31536000, immutable"),
+            ),
+            ("/assets/app.js", "text/javascript; charset=utf-8", None),
+            ("/track.vtt", "text/vtt; charset=utf-8", None),
+            ("/image.svg", "image/svg+xml; charset=utf-8", None),
+            ("/blob.unknownext", "application/octet-stream", None),
+        ];
+        for method in [http::Method::GET, http::Method::HEAD] {
+            for (uri, content_type, cache_control) in cases {
+                let virtual_host = directory_host_with(&directory, configure)?;
+                let (parts, _) = send(&virtual_host, method.clone(), uri, &[]).await?;
+                assert_eq!(parts.status, StatusCode::OK, "{}", uri);
+                assert_eq!(parts.headers[CONTENT_TYPE], content_type, "{} {}", method, uri);
+                assert_eq!(
+                    parts
+                        .headers
+                        .get(CACHE_CONTROL)
+                        .and_then(|value| value.to_str().ok()),
+                    cache_control,
+                    "{} {}",
+                    method,
+                    uri
+                );
+                // Only rules with a max-age expire
+                assert_eq!(
+                    parts
+                        .headers
+                        .contains_key(EXPIRES),
+                    cache_control.is_some_and(|value| value.contains("max-age")),
+                    "{}",
+                    uri
+                );
+            }
+        }
+
+        // Not modified responses repeat the caching headers
+        let virtual_host = directory_host_with(&directory, configure)?;
+        let (parts, _) = send(&virtual_host, http::Method::GET, "/index.html", &[]).await?;
+        let virtual_host = directory_host_with(&directory, configure)?;
+        let (parts, _) = send(
+            &virtual_host,
+            http::Method::GET,
+            "/index.html",
+            &[(IF_NONE_MATCH, parts.headers[ETAG].to_str()?)],
+        )
+        .await?;
+        assert_eq!(parts.status, StatusCode::NOT_MODIFIED);
+        assert_eq!(parts.headers[CACHE_CONTROL], "no-cache");
+
+        let virtual_host = directory_host_with(&directory, |path| path.charset(None))?;
+        let (parts, _) = send(&virtual_host, http::Method::GET, "/notes.txt", &[]).await?;
+        assert_eq!(parts.headers[CONTENT_TYPE], "text/plain");
+
+        let virtual_host =
+            directory_host_with(&directory, |path| path.charset(Some("iso-8859-1")))?;
+        let (parts, _) = send(&virtual_host, http::Method::GET, "/notes.txt", &[]).await?;
+        assert_eq!(parts.headers[CONTENT_TYPE], "text/plain; charset=iso-8859-1");
+
+        // Invalid rules and content types are rejected
+        assert!(directory_host_with(&directory, |path| {
+            path.cache_rule(CacheRule::for_pattern("(", "no-cache"))
+        })
+        .is_err());
+        assert!(directory_host_with(&directory, |path| {
+            path.cache_rule(CacheRule::for_extensions(&[], "no-cache"))
+        })
+        .is_err());
+        assert!(directory_host_with(&directory, |path| path.mime_type("bad", "text/\n")).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_content_type_and_cache_control() -> Result<(), Box<dyn Error>> {
+        do_content_type_and_cache_control().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_content_type_and_cache_control() -> Result<(), Box<dyn Error>> {
+        do_content_type_and_cache_control().await
+    }
+
+    async fn do_try_files() -> Result<(), Box<dyn Error>> {
+        let directory = std::env::temp_dir().join("vetis-try-files");
+        std::fs::create_dir_all(directory.join("docs"))?;
+        std::fs::create_dir_all(directory.join("assets"))?;
+        std::fs::write(directory.join("index.html"), "shell")?;
+        std::fs::write(directory.join("about.html"), "about")?;
+        std::fs::write(directory.join("docs/index.html"), "docs")?;
+        std::fs::write(directory.join("assets/app.js"), "app")?;
+
+        let try_files = |entries: &[&str]| {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|entry| entry.to_string())
+                .collect();
+            move |path: StaticPathConfigBuilder| path.try_files(entries)
+        };
+
+        let spa = ["$uri", "$uri.html", "$uri/index.html", "/index.html"];
+        let cases = [
+            ("/", "shell"),
+            ("/assets/app.js", "app"),
+            ("/about", "about"),
+            ("/about.html", "about"),
+            ("/docs", "docs"),
+            ("/docs/", "docs"),
+            ("/users/42/settings", "shell"),
+        ];
+        for (uri, expected) in cases {
+            let virtual_host = directory_host_with(&directory, try_files(&spa))?;
+            let (parts, body) = send(&virtual_host, http::Method::GET, uri, &[]).await?;
+            assert_eq!(parts.status, StatusCode::OK, "{}", uri);
+            assert_eq!(body, expected, "{}", uri);
+        }
+
+        // Directories are served by their listing
+        let virtual_host = directory_host_with(&directory, |path| {
+            try_files(&["$uri", "$uri/", "=404"])(path).autoindex(true)
+        })?;
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/assets", &[]).await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert!(body.contains("app.js"));
+
+        for (uri, status) in [("/missing", StatusCode::GONE), ("/about.html", StatusCode::OK)] {
+            let virtual_host = directory_host_with(&directory, try_files(&["$uri", "=410"]))?;
+            let (parts, _) = send(&virtual_host, http::Method::GET, uri, &[]).await?;
+            assert_eq!(parts.status, status, "{}", uri);
+        }
+
+        // A missing fallback file is not found
+        let virtual_host = directory_host_with(&directory, try_files(&["$uri", "/404.html"]))?;
+        let (parts, _) = send(&virtual_host, http::Method::GET, "/missing", &[]).await?;
+        assert_eq!(parts.status, StatusCode::NOT_FOUND);
+
+        for invalid in [&[][..], &["=404", "$uri"], &["$uri", "=99"], &["$uri", "=abc"], &[""]] {
+            assert!(directory_host_with(&directory, try_files(invalid)).is_err(), "{:?}", invalid);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_try_files() -> Result<(), Box<dyn Error>> {
+        do_try_files().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_try_files() -> Result<(), Box<dyn Error>> {
+        do_try_files().await
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        use crate::utils::path::{decode_path, normalize_path};
+
+        let cases = [
+            ("/a/b", Some("/a/b")),
+            ("", Some("/")),
+            ("a//b/./c/", Some("/a/b/c")),
+            ("/a/../b", Some("/b")),
+            ("/a/%2e%2E/b", Some("/b")),
+            ("/..", None),
+            ("/a/../../b", None),
+            ("/%2e%2e%2fb", None),
+            ("/a%00", None),
+            ("/%ff", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(
+                decode_path(path)
+                    .and_then(|path| normalize_path(&path))
+                    .as_deref(),
+                expected,
+                "{}",
+                path
+            );
+        }
+    }
+
+    async fn do_path_traversal() -> Result<(), Box<dyn Error>> {
+        use crate::config::server::virtual_host::path::static_files::SymlinkPolicy;
+
+        let base = std::env::temp_dir().join("vetis-traversal");
+        let directory = base.join("public");
+        std::fs::create_dir_all(directory.join("sub"))?;
+        std::fs::create_dir_all(base.join("outside"))?;
+        std::fs::write(base.join("secret.txt"), "secret")?;
+        std::fs::write(base.join("outside/secret.txt"), "secret")?;
+        std::fs::write(directory.join("file.txt"), "file")?;
+        std::fs::write(directory.join("hello world.txt"), "hello")?;
+        std::fs::write(directory.join(".env"), "env")?;
+
+        let cases = [
+            ("/file.txt", StatusCode::OK),
+            ("/hello%20world.txt", StatusCode::OK),
+            ("/sub/../file.txt", StatusCode::OK),
+            ("/../secret.txt", StatusCode::FORBIDDEN),
+            ("/%2e%2e/secret.txt", StatusCode::FORBIDDEN),
+            ("/sub/%2E%2E/%2e%2e/secret.txt", StatusCode::FORBIDDEN),
+            ("/%2e%2e%2fsecret.txt", StatusCode::FORBIDDEN),
+            ("/..%5csecret.txt", StatusCode::FORBIDDEN),
+            ("/file.txt%00.js", StatusCode::FORBIDDEN),
+            ("/.env", StatusCode::NOT_FOUND),
+            ("/sub/%2eenv", StatusCode::NOT_FOUND),
+        ];
+        for (uri, status) in cases {
+            let virtual_host = directory_host(&directory)?;
+            let (parts, body) = send(&virtual_host, http::Method::GET, uri, &[]).await?;
+            assert_eq!(parts.status, status, "{}", uri);
+            assert!(!body.contains("secret"), "{}", uri);
+        }
+
+        // Paths going above a static path, but not above the root
+        let virtual_host = directory_host_with(&directory, |path| path.uri("/static"))?;
+        let (parts, _) =
+            send(&virtual_host, http::Method::GET, "/static/%2e%2e/secret.txt", &[]).await?;
+        assert_eq!(parts.status, StatusCode::FORBIDDEN);
+
+        let virtual_host = directory_host_with(&directory, |path| path.dotfiles(true))?;
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/.env", &[]).await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(body, "env");
+
+        #[cfg(unix)]
+        {
+            for (link, target) in [
+                ("link-in.txt", directory.join("file.txt")),
+                ("link-out.txt", base.join("secret.txt")),
+                ("link-dir", base.join("outside")),
+            ] {
+                let link = directory.join(link);
+                if std::fs::symlink_metadata(&link).is_err() {
+                    std::os::unix::fs::symlink(target, link)?;
+                }
+            }
+
+            let cases = [
+                (SymlinkPolicy::Follow, "/link-in.txt", StatusCode::OK),
+                (SymlinkPolicy::Follow, "/link-out.txt", StatusCode::FORBIDDEN),
+                (SymlinkPolicy::Follow, "/link-dir/secret.txt", StatusCode::FORBIDDEN),
+                (SymlinkPolicy::Deny, "/link-in.txt", StatusCode::FORBIDDEN),
+                (SymlinkPolicy::Deny, "/file.txt", StatusCode::OK),
+                (SymlinkPolicy::OwnerMatch, "/link-in.txt", StatusCode::OK),
+                (SymlinkPolicy::OwnerMatch, "/link-out.txt", StatusCode::FORBIDDEN),
+            ];
+            for (policy, uri, status) in cases {
+                let virtual_host = directory_host_with(&directory, |path| path.symlinks(policy))?;
+                let (parts, body) = send(&virtual_host, http::Method::GET, uri, &[]).await?;
+                assert_eq!(parts.status, status, "{:?} {}", policy, uri);
+                assert!(!body.contains("secret"), "{:?} {}", policy, uri);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_path_traversal() -> Result<(), Box<dyn Error>> {
+        do_path_traversal().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_path_traversal() -> Result<(), Box<dyn Error>> {
+        do_path_traversal().await
+    }
+
+    async fn do_file_cache() -> Result<(), Box<dyn Error>> {
+        use std::time::Duration;
+
+        use crate::config::server::virtual_host::path::static_files::FileCacheConfig;
+
+        let directory = std::env::temp_dir().join("vetis-file-cache");
+        std::fs::create_dir_all(&directory)?;
+        let content: String = (0..200_000)
+            .map(|i| char::from(b'a' + (i % 26) as u8))
+            .collect();
+        std::fs::write(directory.join("large.txt"), &content)?;
+        std::fs::write(directory.join("small.txt"), "one")?;
+
+        // Open files are shared by the requests, each reading at its own offsets
+        let virtual_host = directory_host(&directory)?;
+        for _ in 0..2 {
+            let (full, partial) = futures_util::future::join(
+                send(&virtual_host, http::Method::GET, "/large.txt", &[]),
+                send(
+                    &virtual_host,
+                    http::Method::GET,
+                    "/large.txt",
+                    &[(http::header::RANGE, "bytes=100000-100099")],
+                ),
+            )
+            .await;
+            let ((full_parts, full), (partial_parts, partial)) = (full?, partial?);
+            assert_eq!(full_parts.status, StatusCode::OK);
+            assert_eq!(full, content);
+            assert_eq!(partial_parts.status, StatusCode::PARTIAL_CONTENT);
+            assert_eq!(partial, content[100_000..100_100]);
+        }
+
+        // Contents kept in memory are served until they expire
+        let file_cache = |ttl| {
+            FileCacheConfig::builder()
+                .watch(false)
+                .ttl(ttl)
+                .max_content_size(1024)
+                .build()
+        };
+        let (cached, expiring) = (file_cache(None)?, file_cache(Some(Duration::ZERO))?);
+        let cached_host = directory_host_with(&directory, |path| path.file_cache(cached))?;
+        let expiring_host = directory_host_with(&directory, |path| path.file_cache(expiring))?;
+        for virtual_host in [&cached_host, &expiring_host] {
+            let (_, body) = send(virtual_host, http::Method::GET, "/small.txt", &[]).await?;
+            assert_eq!(body, "one");
+        }
+        std::fs::write(directory.join("small.txt"), "two!")?;
+        let (_, body) = send(&cached_host, http::Method::GET, "/small.txt", &[]).await?;
+        assert_eq!(body, "one");
+        let (parts, body) = send(&expiring_host, http::Method::GET, "/small.txt", &[]).await?;
+        assert_eq!(body, "two!");
+        assert_eq!(parts.headers[http::header::CONTENT_LENGTH], "4");
+
+        // Changed files are evicted as soon as they change on disk
+        #[cfg(target_os = "linux")]
+        {
+            let watched = FileCacheConfig::builder()
+                .ttl(None)
+                .max_content_size(1024)
+                .build()?;
+            let watched_host = directory_host_with(&directory, |path| path.file_cache(watched))?;
+            let (_, body) = send(&watched_host, http::Method::GET, "/small.txt", &[]).await?;
+            assert_eq!(body, "two!");
+
+            std::fs::write(directory.join("small.txt"), "three")?;
+            let mut body = String::new();
+            for _ in 0..50 {
+                crate::tests::sleep(Duration::from_millis(20)).await;
+                (_, body) = send(&watched_host, http::Method::GET, "/small.txt", &[]).await?;
+                if body == "three" {
+                    break;
+                }
+            }
+            assert_eq!(body, "three");
+        }
+
+        assert!(FileCacheConfig::builder()
+            .capacity(0)
+            .build()
+            .is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_file_cache() -> Result<(), Box<dyn Error>> {
+        do_file_cache().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_file_cache() -> Result<(), Box<dyn Error>> {
+        do_file_cache().await
+    }
+
+    #[cfg(all(target_os = "linux", feature = "tokio-rt", feature = "http1"))]
+    #[tokio::test]
+    async fn test_sendfile() -> Result<(), Box<dyn Error>> {
+        use crate::{
+            config::server::virtual_host::path::static_files::FileCacheConfig,
+            server::conn::sendfile::SendFile,
+        };
+
+        let directory = std::env::temp_dir().join("vetis-sendfile");
+        std::fs::create_dir_all(&directory)?;
+        // Larger than a placeholder frame, so it is sent in several calls
+        let content: String = (0..1_500_000)
+            .map(|i| char::from(b'a' + (i % 26) as u8))
+            .collect();
+        std::fs::write(directory.join("large.txt"), &content)?;
+        std::fs::write(directory.join("small.txt"), "small")?;
+
+        // Open files are described to the connection, contents kept in memory are not
+        let in_memory = FileCacheConfig::builder()
+            .max_content_size(1024)
+            .build()?;
+        let virtual_host = directory_host_with(&directory, |path| path.file_cache(in_memory))?;
+        for (uri, expected) in [("/large.txt", true), ("/small.txt", false)] {
+            let (parts, body) = http::Request::get(uri)
+                .body(HttpBody::from_text(""))?
+                .into_parts();
+            let response = virtual_host
+                .route(Request::from_parts(parts, body))
+                .await?;
+            let extension = response
+                .into_inner()
+                .extensions()
+                .get::<SendFile>()
+                .is_some();
+            assert_eq!(extension, expected, "{}", uri);
+        }
+
+        let ipv4 = ListenerConfig::builder()
+            .port(8095)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+        let config = ServerConfig::builder()
+            .add_listener(ipv4)
+            .build()?;
+        let security_config = SecurityConfig::builder()
+            .ca_cert_from_bytes(CA_CERT.to_vec())
+            .cert_from_bytes(SERVER_CERT.to_vec())
+            .key_from_bytes(SERVER_KEY.to_vec())
+            .build()?;
+        let host_config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .port(8095)
+            .security(security_config)
+            .build()?;
+        let mut virtual_host = VirtualHost::new(host_config);
+        virtual_host.add_path(StaticPath::new(
+            StaticPathConfig::builder()
+                .uri("/")
+                .directory(
+                    directory
+                        .to_str()
+                        .ok_or("invalid temporary directory")?,
+                )
+                .extensions("\\.txt$")
+                .build()?,
+        ));
+
+        let mut server = crate::Vetis::new(config);
+        server
+            .add_virtual_host(virtual_host)
+            .await?;
+        server
+            .start()
+            .await?;
+
+        // Responses following each other on the same connection, with and without sendfile
+        let client = deboa::Client::default();
+        for _ in 0..2 {
+            let response = request::get("http://localhost:8095/large.txt")?
+                .send_with(&client)
+                .await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response
+                    .text()
+                    .await?,
+                content
+            );
+
+            let response = request::get("http://localhost:8095/large.txt")?
+                .header(http::header::RANGE, "bytes=1000000-1000099")
+                .send_with(&client)
+                .await?;
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(
+                response
+                    .text()
+                    .await?,
+                content[1_000_000..1_000_100]
+            );
+
+            let response = request::get("http://localhost:8095/small.txt")?
+                .send_with(&client)
+                .await?;
+            assert_eq!(
+                response
+                    .text()
+                    .await?,
+                "small"
+            );
+        }
+
+        server
+            .stop()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn do_embedded_assets() -> Result<(), Box<dyn Error>> {
+        use std::time::Duration;
+
+        use http::{
+            header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, ETAG, IF_NONE_MATCH, RANGE},
+            Method,
+        };
+
+        use crate::server::virtual_host::path::static_files::embedded::{
+            EmbeddedAsset, EmbeddedAssets,
+        };
+
+        // Modified at a fixed date, which gives a known, strong entity tag
+        const MODIFIED: Option<Duration> = Some(Duration::from_secs(1_700_000_000));
+        static ASSETS: EmbeddedAssets = EmbeddedAssets::new(&[
+            EmbeddedAsset::new("index.html", b"<h1>embedded</h1>", MODIFIED),
+            EmbeddedAsset::new("/assets/app.js", b"console.log(1);", MODIFIED),
+            EmbeddedAsset::new("/assets/app.js.gz", b"gzipped", MODIFIED),
+        ]);
+
+        let host_config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .build()?;
+        let mut virtual_host = VirtualHost::new(host_config);
+        virtual_host.add_path(StaticPath::embedded(
+            StaticPathConfig::builder()
+                .uri("/")
+                .extensions("\\.(html|js)$")
+                .index_files(vec!["index.html".to_string()])
+                .try_files(vec!["$uri".to_string(), "$uri/".to_string(), "/index.html".to_string()])
+                .precompressed(true)
+                .build()?,
+            &ASSETS,
+        ));
+
+        // Directories are served by their index file, missing files by the fallback
+        for uri in ["/", "/index.html", "/settings/profile"] {
+            let (parts, body) = send(&virtual_host, Method::GET, uri, &[]).await?;
+            assert_eq!(parts.status, StatusCode::OK, "{}", uri);
+            assert_eq!(parts.headers[CONTENT_TYPE], "text/html; charset=utf-8");
+            assert_eq!(body, "<h1>embedded</h1>");
+        }
+
+        let (parts, body) = send(&virtual_host, Method::GET, "/assets/app.js", &[]).await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(parts.headers[ETAG], "\"6553f100.0-f\"");
+        assert_eq!(body, "console.log(1);");
+
+        let (parts, _) = send(
+            &virtual_host,
+            Method::GET,
+            "/assets/app.js",
+            &[(IF_NONE_MATCH, "\"6553f100.0-f\"")],
+        )
+        .await?;
+        assert_eq!(parts.status, StatusCode::NOT_MODIFIED);
+
+        let (parts, body) =
+            send(&virtual_host, Method::GET, "/assets/app.js", &[(RANGE, "bytes=0-6")]).await?;
+        assert_eq!(parts.status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(body, "console");
+
+        let (parts, body) =
+            send(&virtual_host, Method::GET, "/assets/app.js", &[(ACCEPT_ENCODING, "gzip")])
+                .await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(parts.headers[CONTENT_ENCODING], "gzip");
+        assert_eq!(parts.headers[CONTENT_TYPE], "text/javascript; charset=utf-8");
+        assert_eq!(body, "gzipped");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_embedded_assets() -> Result<(), Box<dyn Error>> {
+        do_embedded_assets().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_embedded_assets() -> Result<(), Box<dyn Error>> {
+        do_embedded_assets().await
+    }
+
+    #[cfg(feature = "webdav")]
+    #[test]
+    fn test_webdav_requires_auth() -> Result<(), Box<dyn Error>> {
+        use crate::config::server::virtual_host::path::static_files::WebDavConfig;
+
+        let path_config = StaticPathConfig::builder()
+            .uri("/dav")
+            .webdav(WebDavConfig::builder().build()?)
+            .build();
+
+        assert_eq!(
+            path_config.err(),
+            Some(VetisError::Config(ConfigError::Path(
+                "WebDAV requires the auth of the static path".into()
+            )))
+        );
+
+        // Configuration files fail to load instead of serving the directory read-only
+        let host_config = serde_yaml_ng::from_str::<VirtualHostConfig>(
+            r#"
+            hostname: "localhost"
+            port: 8080
+            root_directory: "src/tests"
+            enable_logging: false
+            security:
+              cert_from_file: "src/tests/certs/server.der"
+              key_from_file: "src/tests/certs/server.key.der"
+            static_paths:
+              - uri: "/dav"
+                extensions: "\\.txt$"
+                directory: "src/tests/files"
+                webdav: {}
+            "#,
+        );
+        assert!(host_config
+            .err()
+            .ok_or("WebDAV without auth was accepted")?
+            .to_string()
+            .contains("WebDAV requires the auth of the static path"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "webdav")]
+    async fn do_webdav() -> Result<(), Box<dyn Error>> {
+        use http::{
+            header::{HeaderName, AUTHORIZATION, CONTENT_LENGTH},
+            Method,
+        };
+
+        use crate::{
+            config::server::virtual_host::path::{auth::Algorithm, static_files::WebDavConfig},
+            server::virtual_host::path::auth::{basic_auth::BasicAuth, AuthType},
+        };
+
+        let directory = std::env::temp_dir().join("vetis-webdav");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory)?;
+
+        let users = HashMap::from([("user".to_string(), bcrypt::hash("secret", 4)?)]);
+        let auth = BasicAuthConfig::builder()
+            .users(users)
+            .algorithm(Algorithm::BCrypt)
+            .build()?;
+        let webdav = WebDavConfig::builder()
+            .max_upload_size(16)
+            .build()?;
+        let virtual_host = directory_host_with(&directory, |path| {
+            path.auth(AuthType::Basic(BasicAuth::new(auth)))
+                .webdav(webdav)
+        })?;
+
+        let method = |name: &str| Method::from_bytes(name.as_bytes());
+        let depth = HeaderName::from_static("depth");
+        let destination = HeaderName::from_static("destination");
+        let overwrite = HeaderName::from_static("overwrite");
+        let authorization = (AUTHORIZATION, "Basic dXNlcjpzZWNyZXQ=");
+
+        // Every method is authenticated by the auth of the static path
+        let (parts, _) = send_body(&virtual_host, Method::PUT, "/report.txt", &[], "hello").await?;
+        assert_eq!(parts.status, StatusCode::UNAUTHORIZED);
+        let (parts, _) = send(&virtual_host, Method::OPTIONS, "/", &[]).await?;
+        assert_eq!(parts.status, StatusCode::UNAUTHORIZED);
+
+        let auth = [authorization.clone()];
+        let (parts, _) = send(&virtual_host, Method::OPTIONS, "/", &auth).await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(parts.headers["dav"], "1, 2");
+
+        let (parts, _) =
+            send_body(&virtual_host, Method::PUT, "/report.txt", &auth, "hello").await?;
+        assert_eq!(parts.status, StatusCode::CREATED);
+        let (parts, _) =
+            send_body(&virtual_host, Method::PUT, "/report.txt", &auth, "world").await?;
+        assert_eq!(parts.status, StatusCode::NO_CONTENT);
+        let (_, body) = send(&virtual_host, Method::GET, "/report.txt", &auth).await?;
+        assert_eq!(body, "world");
+
+        let (parts, _) =
+            send_body(&virtual_host, Method::PUT, "/missing/a.txt", &auth, "a").await?;
+        assert_eq!(parts.status, StatusCode::CONFLICT);
+
+        // Uploads over the limit are refused, announced or not, without leaving temporary files
+        let large = "x".repeat(32);
+        let (parts, _) = send_body(
+            &virtual_host,
+            Method::PUT,
+            "/large.txt",
+            &[authorization.clone(), (CONTENT_LENGTH, "32")],
+            &large,
+        )
+        .await?;
+        assert_eq!(parts.status, StatusCode::PAYLOAD_TOO_LARGE);
+        let (parts, _) = send_body(&virtual_host, Method::PUT, "/large.txt", &auth, &large).await?;
+        assert_eq!(parts.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(std::fs::read_dir(&directory)?.count(), 1);
+
+        let (parts, _) = send(&virtual_host, method("MKCOL")?, "/docs", &auth).await?;
+        assert_eq!(parts.status, StatusCode::CREATED);
+        let (parts, _) = send(&virtual_host, method("MKCOL")?, "/docs", &auth).await?;
+        assert_eq!(parts.status, StatusCode::METHOD_NOT_ALLOWED);
+
+        let copy = [authorization.clone(), (destination.clone(), "/docs/copy.txt")];
+        let (parts, _) = send(&virtual_host, method("COPY")?, "/report.txt", &copy).await?;
+        assert_eq!(parts.status, StatusCode::CREATED);
+        let no_overwrite = [
+            authorization.clone(),
+            (http::header::HOST, "localhost"),
+            (destination.clone(), "http://localhost/docs/copy.txt"),
+            (overwrite, "F"),
+        ];
+        let (parts, _) = send(&virtual_host, method("COPY")?, "/report.txt", &no_overwrite).await?;
+        assert_eq!(parts.status, StatusCode::PRECONDITION_FAILED);
+        let elsewhere = [
+            authorization.clone(),
+            (http::header::HOST, "localhost"),
+            (destination.clone(), "http://example.com/docs/b.txt"),
+        ];
+        let (parts, _) = send(&virtual_host, method("COPY")?, "/report.txt", &elsewhere).await?;
+        assert_eq!(parts.status, StatusCode::BAD_GATEWAY);
+
+        let rename = [authorization.clone(), (destination.clone(), "/docs/moved%20file.txt")];
+        let (parts, _) = send(&virtual_host, method("MOVE")?, "/docs/copy.txt", &rename).await?;
+        assert_eq!(parts.status, StatusCode::CREATED);
+        assert!(!directory
+            .join("docs/copy.txt")
+            .exists());
+        assert_eq!(std::fs::read_to_string(directory.join("docs/moved file.txt"))?, "world");
+
+        let (parts, body) = send(
+            &virtual_host,
+            method("PROPFIND")?,
+            "/docs",
+            &[authorization.clone(), (depth.clone(), "1")],
+        )
+        .await?;
+        assert_eq!(parts.status, StatusCode::MULTI_STATUS);
+        roxmltree::Document::parse(&body)?;
+        assert!(body.contains("<D:href>/docs/</D:href>"));
+        assert!(body.contains("<D:collection/>"));
+        assert!(body.contains("<D:href>/docs/moved%20file.txt</D:href>"));
+        assert!(body.contains("<D:getcontentlength>5</D:getcontentlength>"));
+
+        let propfind = r#"<?xml version="1.0" encoding="utf-8"?>
+            <D:propfind xmlns:D="DAV:" xmlns:Z="urn:example">
+                <D:prop><D:getcontentlength/><Z:color/></D:prop>
+            </D:propfind>"#;
+        let (parts, body) = send_body(
+            &virtual_host,
+            method("PROPFIND")?,
+            "/report.txt",
+            &[authorization.clone(), (depth.clone(), "0")],
+            propfind,
+        )
+        .await?;
+        assert_eq!(parts.status, StatusCode::MULTI_STATUS);
+        assert!(body.contains("<D:getcontentlength>5</D:getcontentlength>"));
+        assert!(body.contains("<R:color xmlns:R=\"urn:example\"/>"));
+        assert!(body.contains("HTTP/1.1 404 Not Found"));
+
+        let (parts, body) = send(&virtual_host, method("PROPFIND")?, "/", &auth).await?;
+        assert_eq!(parts.status, StatusCode::FORBIDDEN);
+        assert!(body.contains("propfind-finite-depth"));
+
+        // Locked files are only written with their lock token
+        let lockinfo = r#"<?xml version="1.0" encoding="utf-8"?>
+            <D:lockinfo xmlns:D="DAV:">
+                <D:lockscope><D:exclusive/></D:lockscope>
+                <D:locktype><D:write/></D:locktype>
+                <D:owner><D:href>mailto:user@example.com</D:href></D:owner>
+            </D:lockinfo>"#;
+        let (parts, body) =
+            send_body(&virtual_host, method("LOCK")?, "/report.txt", &auth, lockinfo).await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        roxmltree::Document::parse(&body)?;
+        assert!(body.contains("<D:href>mailto:user@example.com</D:href>"));
+        let lock_token = parts.headers["lock-token"].to_str()?;
+        let token = lock_token
+            .trim_start_matches('<')
+            .trim_end_matches('>');
+
+        let (parts, _) =
+            send_body(&virtual_host, method("LOCK")?, "/report.txt", &auth, lockinfo).await?;
+        assert_eq!(parts.status, StatusCode::LOCKED);
+        let (parts, _) =
+            send_body(&virtual_host, Method::PUT, "/report.txt", &auth, "again").await?;
+        assert_eq!(parts.status, StatusCode::LOCKED);
+        let (parts, _) = send(&virtual_host, Method::DELETE, "/report.txt", &auth).await?;
+        assert_eq!(parts.status, StatusCode::LOCKED);
+
+        let if_header = format!("(<{}>)", token);
+        let with_token =
+            [authorization.clone(), (HeaderName::from_static("if"), if_header.as_str())];
+        let (parts, _) =
+            send_body(&virtual_host, Method::PUT, "/report.txt", &with_token, "again").await?;
+        assert_eq!(parts.status, StatusCode::NO_CONTENT);
+
+        let unlock = [authorization.clone(), (HeaderName::from_static("lock-token"), lock_token)];
+        let (parts, _) = send(&virtual_host, method("UNLOCK")?, "/report.txt", &unlock).await?;
+        assert_eq!(parts.status, StatusCode::NO_CONTENT);
+        let (parts, _) = send(&virtual_host, method("UNLOCK")?, "/report.txt", &unlock).await?;
+        assert_eq!(parts.status, StatusCode::CONFLICT);
+
+        let (parts, _) = send(&virtual_host, Method::DELETE, "/docs", &auth).await?;
+        assert_eq!(parts.status, StatusCode::NO_CONTENT);
+        assert!(!directory
+            .join("docs")
+            .exists());
+        let (parts, _) = send(&virtual_host, Method::DELETE, "/", &auth).await?;
+        assert_eq!(parts.status, StatusCode::FORBIDDEN);
+
+        // Writes through links leaving the directory are refused
+        #[cfg(unix)]
+        {
+            let outside = std::env::temp_dir().join("vetis-webdav-outside");
+            let _ = std::fs::remove_dir_all(&outside);
+            std::fs::create_dir_all(&outside)?;
+            std::os::unix::fs::symlink(&outside, directory.join("link"))?;
+            std::os::unix::fs::symlink(
+                outside.join("dangling.txt"),
+                directory.join("dangling.txt"),
+            )?;
+
+            let (parts, _) =
+                send_body(&virtual_host, Method::PUT, "/link/evil.txt", &auth, "evil").await?;
+            assert_eq!(parts.status, StatusCode::FORBIDDEN);
+            let (parts, _) = send(&virtual_host, method("MKCOL")?, "/link/evil", &auth).await?;
+            assert_eq!(parts.status, StatusCode::FORBIDDEN);
+            let (parts, _) =
+                send_body(&virtual_host, Method::PUT, "/dangling.txt", &auth, "evil").await?;
+            assert_eq!(parts.status, StatusCode::FORBIDDEN);
+            assert_eq!(std::fs::read_dir(&outside)?.count(), 0);
+
+            std::fs::remove_dir_all(&outside)?;
+        }
+
+        std::fs::remove_dir_all(&directory)?;
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "webdav", feature = "tokio-rt"))]
+    #[tokio::test]
+    async fn test_webdav() -> Result<(), Box<dyn Error>> {
+        do_webdav().await
+    }
+
+    #[cfg(all(feature = "webdav", feature = "smol-rt"))]
+    #[apply(test!)]
+    async fn test_webdav() -> Result<(), Box<dyn Error>> {
+        do_webdav().await
+    }
+
     #[cfg(feature = "auth")]
     async fn do_basic_auth(
         username: Option<String>,
@@ -385,7 +1681,7 @@ mod static_files {
         let mut server = crate::Vetis::new(config);
         server
             .add_virtual_host(virtual_host)
-            .await;
+            .await?;
 
         server
             .start()
@@ -487,7 +1783,7 @@ mod reverse_proxy {
     };
 
     use crate::{
-        config::server::virtual_host::path::proxy::ProxyPathConfig,
+        config::server::virtual_host::path::proxy::{ProxyPathConfig, DEFAULT_PROXY_TIMEOUT},
         errors::{ConfigError, VetisError},
     };
 
@@ -500,6 +1796,7 @@ mod reverse_proxy {
 
         assert_eq!(some_path.uri(), "/test");
         assert_eq!(some_path.target(), "http://localhost:8080");
+        assert_eq!(some_path.timeout(), DEFAULT_PROXY_TIMEOUT);
 
         Ok(())
     }
@@ -606,10 +1903,10 @@ mod reverse_proxy {
         let mut server = crate::Vetis::new(config);
         server
             .add_virtual_host(source_virtual_host)
-            .await;
+            .await?;
         server
             .add_virtual_host(target_virtual_host)
-            .await;
+            .await?;
 
         server
             .start()
@@ -650,6 +1947,64 @@ mod reverse_proxy {
         do_get_proxy_to_target().await
     }
 
+    async fn do_proxy_timeout() -> Result<(), Box<dyn Error>> {
+        use hyper_body_utils::HttpBody;
+
+        use crate::{
+            config::server::virtual_host::VirtualHostConfig,
+            server::{
+                http::Request, virtual_host::path::proxy::ProxyPath, virtual_host::VirtualHost,
+            },
+        };
+
+        // Connections are queued by the kernel but never answered
+        let silent = std::net::TcpListener::bind("127.0.0.1:9095")?;
+
+        let config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(config);
+        virtual_host.add_path(ProxyPath::new(
+            ProxyPathConfig::builder()
+                .uri("/")
+                .target("http://127.0.0.1:9095")
+                .timeout(std::time::Duration::from_millis(200))
+                .build()?,
+        ));
+
+        let request = http::Request::builder()
+            .uri("/")
+            .body(HttpBody::from_text(""))?;
+        let (parts, body) = request.into_parts();
+        let response = virtual_host
+            .route(Request::from_parts(parts, body))
+            .await?;
+        assert_eq!(
+            response
+                .into_inner()
+                .status(),
+            StatusCode::GATEWAY_TIMEOUT
+        );
+
+        drop(silent);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_proxy_timeout() -> Result<(), Box<dyn Error>> {
+        do_proxy_timeout().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_proxy_timeout() -> Result<(), Box<dyn Error>> {
+        do_proxy_timeout().await
+    }
+
     #[cfg(any(feature = "http1", feature = "http2"))]
     async fn do_post_proxy_to_target() -> Result<(), Box<dyn Error>> {
         use crate::tests::default_protocol;
@@ -726,10 +2081,10 @@ mod reverse_proxy {
         let mut server = crate::Vetis::new(config);
         server
             .add_virtual_host(source_virtual_host)
-            .await;
+            .await?;
         server
             .add_virtual_host(target_virtual_host)
-            .await;
+            .await?;
 
         server
             .start()
@@ -840,7 +2195,7 @@ mod wsgi_interface_tests {
         let mut server = crate::Vetis::new(config);
         server
             .add_virtual_host(virtual_host)
-            .await;
+            .await?;
 
         server
             .start()
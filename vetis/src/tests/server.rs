@@ -1,23 +1,29 @@
 mod server_tests {
     use deboa::{
-        cert::{Certificate, ContentEncoding},
+        cert::{Certificate, ContentEncoding, Identity},
         request,
     };
+    use futures_util::future::join;
     use http::StatusCode;
     #[cfg(feature = "smol-rt")]
     use macro_rules_attribute::apply;
     #[cfg(feature = "smol-rt")]
     use smol_macros::test;
-    use std::error::Error;
+    use std::{
+        error::Error,
+        time::{Duration, Instant},
+    };
 
     use crate::{
         config::server::{
-            virtual_host::{SecurityConfig, VirtualHostConfig},
+            virtual_host::{SecurityConfig, VirtualHostConfig, VirtualHostConfigBuilder},
             ListenerConfig, ServerConfig,
         },
+        errors::{VetisError, VirtualHostError},
         server::virtual_host::{handler_fn, path::HandlerPath, VirtualHost},
         tests::{
-            default_protocol, CA_CERT, IP6_SERVER_CERT, IP6_SERVER_KEY, SERVER_CERT, SERVER_KEY,
+            default_protocol, sleep, CA_CERT, CLIENT_CERT, CLIENT_KEY, IP6_SERVER_CERT,
+            IP6_SERVER_KEY, SERVER_CERT, SERVER_KEY,
         },
     };
 
@@ -94,10 +100,10 @@ mod server_tests {
         let mut server = crate::Vetis::new(config);
         server
             .add_virtual_host(localhost_virtual_host)
-            .await;
+            .await?;
         server
             .add_virtual_host(ip6_localhost_virtual_host)
-            .await;
+            .await?;
 
         server
             .start()
@@ -158,4 +164,1062 @@ mod server_tests {
     async fn test_multiple_interfaces_smol() -> Result<(), Box<dyn Error>> {
         do_multiple_interfaces().await
     }
+
+    async fn do_graceful_shutdown() -> Result<(), Box<dyn Error>> {
+        let listener = ListenerConfig::builder()
+            .port(8086)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let config = ServerConfig::builder()
+            .add_listener(listener)
+            .grace_period(Duration::from_secs(5))
+            .build()?;
+
+        let security_config = SecurityConfig::builder()
+            .ca_cert_from_bytes(CA_CERT.to_vec())
+            .cert_from_bytes(SERVER_CERT.to_vec())
+            .key_from_bytes(SERVER_KEY.to_vec())
+            .build()?;
+
+        let localhost_config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .port(8086)
+            .root_directory("src/tests")
+            .security(security_config)
+            .build()?;
+
+        let mut localhost_virtual_host = VirtualHost::new(localhost_config);
+
+        let slow_path = HandlerPath::builder()
+            .uri("/slow")
+            .handler(handler_fn(|_request| async move {
+                sleep(Duration::from_millis(500)).await;
+                let response = crate::server::http::Response::builder()
+                    .status(StatusCode::OK)
+                    .text("Finished");
+                Ok(response)
+            }))
+            .build()?;
+
+        localhost_virtual_host.add_path(slow_path);
+
+        let mut server = crate::Vetis::new(config);
+        server
+            .add_virtual_host(localhost_virtual_host)
+            .await?;
+
+        server
+            .start()
+            .await?;
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+
+        let request = async {
+            let response = request::get("https://localhost:8086/slow")?
+                .send_with(&client)
+                .await?;
+            let status = response.status();
+            let body = response
+                .text()
+                .await?;
+            Ok::<_, Box<dyn Error>>((status, body))
+        };
+
+        let stop = async {
+            sleep(Duration::from_millis(100)).await;
+            server.stop().await
+        };
+
+        let (response, stopped) = futures_util::join!(request, stop);
+        stopped?;
+
+        let (status, body) = response?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "Finished");
+
+        let result = request::get("https://localhost:8086/slow")?
+            .send_with(&client)
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_graceful_shutdown() -> Result<(), Box<dyn Error>> {
+        do_graceful_shutdown().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_graceful_shutdown_smol() -> Result<(), Box<dyn Error>> {
+        do_graceful_shutdown().await
+    }
+
+    fn localhost_virtual_host(
+        port: u16,
+        message: &'static str,
+    ) -> Result<VirtualHost, Box<dyn Error>> {
+        let localhost_config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .port(port);
+
+        secure_virtual_host(localhost_config, message)
+    }
+
+    fn slow_virtual_host(port: u16, message: &'static str) -> Result<VirtualHost, Box<dyn Error>> {
+        let mut virtual_host = localhost_virtual_host(port, message)?;
+
+        let slow_path = HandlerPath::builder()
+            .uri("/slow")
+            .handler(handler_fn(|_request| async move {
+                sleep(Duration::from_secs(2)).await;
+                let response = crate::server::http::Response::builder()
+                    .status(StatusCode::OK)
+                    .text("Hello from slow");
+                Ok(response)
+            }))
+            .build()?;
+
+        virtual_host.add_path(slow_path);
+
+        Ok(virtual_host)
+    }
+
+    fn secure_virtual_host(
+        config: VirtualHostConfigBuilder,
+        message: &'static str,
+    ) -> Result<VirtualHost, Box<dyn Error>> {
+        let security_config = SecurityConfig::builder()
+            .ca_cert_from_bytes(CA_CERT.to_vec())
+            .cert_from_bytes(SERVER_CERT.to_vec())
+            .key_from_bytes(SERVER_KEY.to_vec())
+            .build()?;
+
+        let config = config
+            .root_directory("src/tests")
+            .security(security_config)
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(config);
+
+        let root_path = HandlerPath::builder()
+            .uri("/hello")
+            .handler(handler_fn(move |_request| async move {
+                let response = crate::server::http::Response::builder()
+                    .status(StatusCode::OK)
+                    .text(message);
+                Ok(response)
+            }))
+            .build()?;
+
+        virtual_host.add_path(root_path);
+
+        Ok(virtual_host)
+    }
+
+    async fn do_runtime_virtual_hosts() -> Result<(), Box<dyn Error>> {
+        let listener = ListenerConfig::builder()
+            .port(8087)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        let mut server = crate::Vetis::new(config);
+        server
+            .add_virtual_host(slow_virtual_host(8087, "Hello from first")?)
+            .await?;
+
+        server
+            .start()
+            .await?;
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+
+        let response = request::get("https://localhost:8087/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from first"
+        );
+
+        let result = server
+            .add_virtual_host(localhost_virtual_host(8087, "Hello from duplicate")?)
+            .await;
+        assert_eq!(
+            result,
+            Err(VetisError::VirtualHost(VirtualHostError::AlreadyExists(
+                "localhost:8087".to_string()
+            )))
+        );
+
+        // Replacing does not wait for the requests still served by the previous virtual host
+        let slow = async {
+            request::get("https://localhost:8087/slow")?
+                .send_with(&client)
+                .await?
+                .text()
+                .await
+        };
+        let replace = async {
+            sleep(Duration::from_millis(500)).await;
+            let started = Instant::now();
+            let replaced = server
+                .replace_virtual_host(localhost_virtual_host(8087, "Hello from second")?)
+                .await?;
+            Ok::<_, Box<dyn Error>>((replaced, started.elapsed()))
+        };
+        let (slow, replace) = join(slow, replace).await;
+        let (replaced, elapsed) = replace?;
+        assert!(replaced.is_some());
+        assert!(elapsed < Duration::from_secs(1));
+        assert_eq!(slow?, "Hello from slow");
+
+        let response = request::get("https://localhost:8087/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from second"
+        );
+
+        let removed = server
+            .remove_virtual_host("localhost", 8087)
+            .await?;
+        assert!(removed.is_some());
+
+        // Certificate is no longer offered, so the handshake fails
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+        let result = request::get("https://localhost:8087/hello")?
+            .send_with(&client)
+            .await;
+        assert!(result.is_err());
+
+        server
+            .add_virtual_host(localhost_virtual_host(8087, "Hello from third")?)
+            .await?;
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+        let response = request::get("https://localhost:8087/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from third"
+        );
+
+        server
+            .stop()
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_runtime_virtual_hosts() -> Result<(), Box<dyn Error>> {
+        do_runtime_virtual_hosts().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_runtime_virtual_hosts_smol() -> Result<(), Box<dyn Error>> {
+        do_runtime_virtual_hosts().await
+    }
+
+    async fn do_reload() -> Result<(), Box<dyn Error>> {
+        let listener = ListenerConfig::builder()
+            .port(8089)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        let mut server = crate::Vetis::new(config);
+        server
+            .add_virtual_host(localhost_virtual_host(8089, "Hello from 8089")?)
+            .await?;
+
+        server
+            .start()
+            .await?;
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+
+        let response = request::get("https://localhost:8089/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from 8089"
+        );
+
+        let listener = ListenerConfig::builder()
+            .port(8090)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let reloaded_config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        server
+            .reload(reloaded_config.clone(), vec![slow_virtual_host(8090, "Hello from 8090")?])
+            .await?;
+
+        assert_eq!(
+            server
+                .config()
+                .listeners()[0]
+                .port(),
+            8090
+        );
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+        let response = request::get("https://localhost:8090/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from 8090"
+        );
+
+        sleep(Duration::from_millis(100)).await;
+
+        let client_8089 = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+        let result = request::get("https://localhost:8089/hello")?
+            .send_with(&client_8089)
+            .await;
+        assert!(result.is_err());
+
+        // Invalid configurations keep the running one
+        let result = server
+            .reload(
+                reloaded_config.clone(),
+                vec![
+                    localhost_virtual_host(8090, "Hello from first")?,
+                    localhost_virtual_host(8090, "Hello from second")?,
+                ],
+            )
+            .await;
+        assert_eq!(
+            result,
+            Err(VetisError::VirtualHost(VirtualHostError::AlreadyExists(
+                "localhost:8090".to_string()
+            )))
+        );
+
+        let occupied = std::net::TcpListener::bind("0.0.0.0:8091")?;
+
+        let listener = ListenerConfig::builder()
+            .port(8091)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let occupied_config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        let result = server
+            .reload(occupied_config, vec![localhost_virtual_host(8091, "Hello from 8091")?])
+            .await;
+        assert!(matches!(result, Err(VetisError::Bind(_))));
+
+        drop(occupied);
+
+        let response = request::get("https://localhost:8090/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from 8090"
+        );
+
+        // Reloading does not wait for the requests still served by the previous virtual hosts
+        let slow = async {
+            request::get("https://localhost:8090/slow")?
+                .send_with(&client)
+                .await?
+                .text()
+                .await
+        };
+        let reload = async {
+            sleep(Duration::from_millis(500)).await;
+            let started = Instant::now();
+            server
+                .reload(reloaded_config, vec![localhost_virtual_host(8090, "Hello from reloaded")?])
+                .await?;
+            Ok::<_, Box<dyn Error>>(started.elapsed())
+        };
+        let (slow, reload) = join(slow, reload).await;
+        assert!(reload? < Duration::from_secs(1));
+        assert_eq!(slow?, "Hello from slow");
+
+        let response = request::get("https://localhost:8090/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from reloaded"
+        );
+
+        // Listeners changed on the same port replace the running one
+        let listener = ListenerConfig::builder()
+            .port(8090)
+            .protocol(default_protocol())
+            .interface("127.0.0.1")
+            .build()?;
+
+        let rebound_config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        server
+            .reload(rebound_config, vec![localhost_virtual_host(8090, "Hello from 127.0.0.1")?])
+            .await?;
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+        let response = request::get("https://localhost:8090/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from 127.0.0.1"
+        );
+
+        server
+            .stop()
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_reload() -> Result<(), Box<dyn Error>> {
+        do_reload().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_reload_smol() -> Result<(), Box<dyn Error>> {
+        do_reload().await
+    }
+
+    async fn do_host_matching() -> Result<(), Box<dyn Error>> {
+        let listener = ListenerConfig::builder()
+            .port(8092)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        let mut server = crate::Vetis::new(config);
+        let aliased_config = VirtualHostConfig::builder()
+            .hostname("example.com")
+            .alias("localhost")
+            .port(8092);
+        server
+            .add_virtual_host(secure_virtual_host(aliased_config, "Hello from alias")?)
+            .await?;
+
+        server
+            .start()
+            .await?;
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+        let response = request::get("https://localhost:8092/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from alias"
+        );
+
+        let result = server
+            .add_virtual_host(localhost_virtual_host(8092, "Hello from duplicate")?)
+            .await;
+        assert_eq!(
+            result,
+            Err(VetisError::VirtualHost(VirtualHostError::AlreadyExists(
+                "localhost:8092".to_string()
+            )))
+        );
+
+        // Neither the certificate nor the routes match localhost anymore, the default serves both
+        let default_config = VirtualHostConfig::builder()
+            .hostname("example.com")
+            .default(true)
+            .port(8092);
+        server
+            .replace_virtual_host(secure_virtual_host(default_config, "Hello from default")?)
+            .await?;
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+        let response = request::get("https://localhost:8092/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello from default"
+        );
+
+        let other_default_config = VirtualHostConfig::builder()
+            .hostname("*.example.com")
+            .default(true)
+            .port(8092);
+        let result = server
+            .add_virtual_host(secure_virtual_host(other_default_config, "Hello from other")?)
+            .await;
+        assert_eq!(
+            result,
+            Err(VetisError::VirtualHost(VirtualHostError::AlreadyExists(
+                "default virtual host on port 8092".to_string()
+            )))
+        );
+
+        server
+            .stop()
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_host_matching() -> Result<(), Box<dyn Error>> {
+        do_host_matching().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_host_matching_smol() -> Result<(), Box<dyn Error>> {
+        do_host_matching().await
+    }
+
+    async fn do_connection_info() -> Result<(), Box<dyn Error>> {
+        let listener = ListenerConfig::builder()
+            .port(8093)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        let mut virtual_host = localhost_virtual_host(8093, "Hello")?;
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/info/:name")
+                .handler(handler_fn(|request| async move {
+                    let tls = request.tls();
+                    let info = format!(
+                        "{} {} {} {} {} {} {}",
+                        request
+                            .client_addr()
+                            .map(|addr| addr
+                                .ip()
+                                .to_string())
+                            .unwrap_or_default(),
+                        request
+                            .local_addr()
+                            .map(|addr| addr.port())
+                            .unwrap_or_default(),
+                        request.is_tls(),
+                        tls.and_then(|tls| tls.server_name())
+                            .unwrap_or_default(),
+                        tls.and_then(|tls| tls.protocol_version())
+                            .unwrap_or_default(),
+                        request
+                            .virtual_host()
+                            .unwrap_or_default(),
+                        request
+                            .matched_path()
+                            .unwrap_or_default(),
+                    );
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&info))
+                }))
+                .build()?,
+        );
+
+        let mut server = crate::Vetis::new(config);
+        server
+            .add_virtual_host(virtual_host)
+            .await?;
+
+        server
+            .start()
+            .await?;
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+
+        let response = request::get("https://localhost:8093/info/me")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let text = response
+            .text()
+            .await?;
+        let (client_ip, info) = text
+            .split_once(' ')
+            .ok_or("missing connection info")?;
+        assert!(client_ip
+            .parse::<std::net::IpAddr>()?
+            .is_loopback());
+        assert_eq!(info, "8093 true localhost TLSv1.3 localhost /info/:name");
+
+        server
+            .stop()
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_connection_info() -> Result<(), Box<dyn Error>> {
+        do_connection_info().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_connection_info_smol() -> Result<(), Box<dyn Error>> {
+        do_connection_info().await
+    }
+
+    async fn do_client_auth() -> Result<(), Box<dyn Error>> {
+        let listener = ListenerConfig::builder()
+            .port(8098)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        let security_config = SecurityConfig::builder()
+            .ca_cert_from_bytes(CA_CERT.to_vec())
+            .cert_from_bytes(SERVER_CERT.to_vec())
+            .key_from_bytes(SERVER_KEY.to_vec())
+            .client_auth(true)
+            .build()?;
+
+        let localhost_config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .port(8098)
+            .root_directory("src/tests")
+            .security(security_config)
+            .build()?;
+
+        let mut localhost = VirtualHost::new(localhost_config);
+        localhost.add_path(
+            HandlerPath::builder()
+                .uri("/hello")
+                .handler(handler_fn(|request| async move {
+                    let certificates = request
+                        .tls()
+                        .map(|tls| {
+                            tls.peer_certificates()
+                                .len()
+                        })
+                        .unwrap_or_default();
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&format!("Hello, {} certificate", certificates)))
+                }))
+                .build()?,
+        );
+
+        // Clients send no server name to IP addresses, so it is served by the default
+        let loopback_config = VirtualHostConfig::builder()
+            .hostname("127.0.0.1")
+            .port(8098)
+            .default(true);
+
+        let mut server = crate::Vetis::new(config);
+        server
+            .add_virtual_host(localhost)
+            .await?;
+        server
+            .add_virtual_host(secure_virtual_host(loopback_config, "Hello from 127.0.0.1")?)
+            .await?;
+
+        server
+            .start()
+            .await?;
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .identity(Identity::from_pkcs8(CLIENT_CERT, CLIENT_KEY, ContentEncoding::DER))
+            .build();
+
+        let response = request::get("https://localhost:8098/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .text()
+                .await?,
+            "Hello, 1 certificate"
+        );
+
+        let client = deboa::Client::builder()
+            .certificate(Certificate::from_slice(CA_CERT, ContentEncoding::DER))
+            .build();
+
+        let result = request::get("https://localhost:8098/hello")?
+            .send_with(&client)
+            .await;
+        assert!(matches!(
+            result,
+            Err(deboa::errors::DeboaError::Response(deboa::errors::ResponseError::Receive {
+                status_code: StatusCode::FORBIDDEN,
+                ..
+            }))
+        ));
+
+        // Certificates are optional for the other virtual hosts of the port
+        let response = request::get("https://127.0.0.1:8098/hello")?
+            .send_with(&client)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        server
+            .stop()
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_client_auth() -> Result<(), Box<dyn Error>> {
+        do_client_auth().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_client_auth_smol() -> Result<(), Box<dyn Error>> {
+        do_client_auth().await
+    }
+
+    async fn do_body_timeout() -> Result<(), Box<dyn Error>> {
+        #[cfg(feature = "smol-rt")]
+        use smol::io::{AsyncReadExt, AsyncWriteExt};
+        #[cfg(feature = "tokio-rt")]
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = ListenerConfig::builder()
+            .port(8097)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        let localhost_config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .port(8097)
+            .root_directory("src/tests")
+            .body_timeout(Duration::from_millis(200))
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(localhost_config);
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/upload")
+                .handler(handler_fn(|request| async move {
+                    let body = request
+                        .text()
+                        .await?;
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&body))
+                }))
+                .build()?,
+        );
+
+        let mut server = crate::Vetis::new(config);
+        server
+            .add_virtual_host(virtual_host)
+            .await?;
+
+        server
+            .start()
+            .await?;
+
+        // The body announces more bytes than are sent
+        #[cfg(feature = "tokio-rt")]
+        let mut stream = tokio::net::TcpStream::connect("localhost:8097").await?;
+        #[cfg(feature = "smol-rt")]
+        let mut stream = smol::net::TcpStream::connect("localhost:8097").await?;
+        stream
+            .write_all(
+                b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\n\r\nslow",
+            )
+            .await?;
+
+        let mut response = [0; 64];
+        let read = stream
+            .read(&mut response)
+            .await?;
+        assert!(String::from_utf8_lossy(&response[..read]).starts_with("HTTP/1.1 408"));
+
+        server
+            .stop()
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_body_timeout() -> Result<(), Box<dyn Error>> {
+        do_body_timeout().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_body_timeout_smol() -> Result<(), Box<dyn Error>> {
+        do_body_timeout().await
+    }
+
+    #[cfg(feature = "websocket")]
+    async fn do_websocket() -> Result<(), Box<dyn Error>> {
+        use async_tungstenite::tungstenite;
+        use futures_util::StreamExt;
+
+        use crate::server::{
+            http::{CloseFrame, Message},
+            virtual_host::path::websocket::WebSocketPath,
+        };
+
+        let listener = ListenerConfig::builder()
+            .port(8094)
+            .protocol(default_protocol())
+            .interface("0.0.0.0")
+            .build()?;
+
+        let config = ServerConfig::builder()
+            .add_listener(listener)
+            .build()?;
+
+        let host_config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .port(8094)
+            .root_directory("src/tests")
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(host_config);
+        virtual_host.add_path(WebSocketPath::new(
+            "/echo/:room",
+            |request, mut socket| async move {
+                let room = request
+                    .param("room")
+                    .unwrap_or_default()
+                    .to_string();
+                while let Ok(Some(message)) = socket.recv().await {
+                    let reply = match message {
+                        Message::Text(text) if text == "bye" => {
+                            let frame = CloseFrame { code: 1000, reason: room.clone() };
+                            let _ = socket
+                                .close(Some(frame))
+                                .await;
+                            continue;
+                        }
+                        Message::Text(text) => Message::Text(format!("{}: {}", room, text)),
+                        Message::Binary(bytes) => Message::Binary(bytes),
+                        Message::Ping(_) => Message::Text("ping".to_string()),
+                        _ => continue,
+                    };
+                    if socket
+                        .send(reply)
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            },
+        )?);
+
+        let mut server = crate::Vetis::new(config);
+        server
+            .add_virtual_host(virtual_host)
+            .await?;
+
+        server
+            .start()
+            .await?;
+
+        // Plain requests are not upgraded
+        let result = request::get("http://localhost:8094/echo/lobby")?
+            .send_with(&deboa::Client::default())
+            .await;
+        assert!(matches!(
+            result,
+            Err(deboa::errors::DeboaError::Response(deboa::errors::ResponseError::Receive {
+                status_code: StatusCode::BAD_REQUEST,
+                ..
+            }))
+        ));
+
+        #[cfg(feature = "tokio-rt")]
+        let (mut socket, response) = async_tungstenite::tokio::client_async(
+            "ws://localhost:8094/echo/lobby",
+            tokio::net::TcpStream::connect("localhost:8094").await?,
+        )
+        .await?;
+        #[cfg(feature = "smol-rt")]
+        let (mut socket, response) = async_tungstenite::client_async(
+            "ws://localhost:8094/echo/lobby",
+            smol::net::TcpStream::connect("localhost:8094").await?,
+        )
+        .await?;
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+
+        socket
+            .send(tungstenite::Message::text("hello"))
+            .await?;
+        assert_eq!(
+            socket
+                .next()
+                .await
+                .ok_or("connection closed")??,
+            tungstenite::Message::text("lobby: hello")
+        );
+
+        socket
+            .send(tungstenite::Message::binary(vec![1, 2, 3]))
+            .await?;
+        assert_eq!(
+            socket
+                .next()
+                .await
+                .ok_or("connection closed")??,
+            tungstenite::Message::binary(vec![1, 2, 3])
+        );
+
+        // Pings are answered before handlers see them
+        socket
+            .send(tungstenite::Message::Ping(bytes::Bytes::from("beat")))
+            .await?;
+        assert_eq!(
+            socket
+                .next()
+                .await
+                .ok_or("connection closed")??,
+            tungstenite::Message::Pong(bytes::Bytes::from("beat"))
+        );
+        assert_eq!(
+            socket
+                .next()
+                .await
+                .ok_or("connection closed")??,
+            tungstenite::Message::text("ping")
+        );
+
+        socket
+            .send(tungstenite::Message::text("bye"))
+            .await?;
+        match socket
+            .next()
+            .await
+            .ok_or("connection closed")??
+        {
+            tungstenite::Message::Close(Some(frame)) => {
+                assert_eq!(u16::from(frame.code), 1000);
+                assert_eq!(
+                    frame
+                        .reason
+                        .as_str(),
+                    "lobby"
+                );
+            }
+            message => return Err(format!("unexpected message: {:?}", message).into()),
+        }
+
+        server
+            .stop()
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "websocket", feature = "tokio-rt"))]
+    #[tokio::test]
+    async fn test_websocket() -> Result<(), Box<dyn Error>> {
+        do_websocket().await
+    }
+
+    #[cfg(all(feature = "websocket", feature = "smol-rt"))]
+    #[apply(test!)]
+    async fn test_websocket() -> Result<(), Box<dyn Error>> {
+        do_websocket().await
+    }
 }
@@ -56,7 +56,7 @@ mod tls_tests {
         );
 
         let mut hosts = std::collections::HashMap::new();
-        hosts.insert((Arc::from("localhost"), 8443u16), virtual_host);
+        hosts.insert((Arc::from("localhost"), 8443u16), Arc::new(virtual_host));
 
         Arc::new(RwLock::new(hosts))
     }
@@ -85,7 +85,7 @@ mod tls_tests {
         );
 
         let mut hosts = std::collections::HashMap::new();
-        hosts.insert((Arc::from("localhost"), 8443u16), virtual_host);
+        hosts.insert((Arc::from("localhost"), 8443u16), Arc::new(virtual_host));
 
         Arc::new(RwLock::new(hosts))
     }
@@ -121,7 +121,7 @@ mod tls_tests {
         );
 
         let mut hosts = std::collections::HashMap::new();
-        hosts.insert((Arc::from("localhost"), 8443u16), virtual_host);
+        hosts.insert((Arc::from("localhost"), 8443u16), Arc::new(virtual_host));
 
         Arc::new(RwLock::new(hosts))
     }
@@ -130,7 +130,7 @@ mod tls_tests {
         let virtual_hosts = create_test_virtual_hosts();
         let alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
 
-        let result = TlsFactory::create_tls_config(virtual_hosts, alpn_protocols).await;
+        let result = TlsFactory::create_tls_config(virtual_hosts, 8443, alpn_protocols).await;
 
         assert!(result.is_ok(), "TLS config creation should succeed");
         let tls_config = result.unwrap();
@@ -157,7 +157,7 @@ mod tls_tests {
         let virtual_hosts = create_test_virtual_hosts_no_security();
         let alpn_protocols = vec![b"http/1.1".to_vec()];
 
-        let result = TlsFactory::create_tls_config(virtual_hosts, alpn_protocols).await;
+        let result = TlsFactory::create_tls_config(virtual_hosts, 8443, alpn_protocols).await;
 
         assert!(result.is_ok(), "TLS config creation should succeed even without security");
         let tls_config = result.unwrap();
@@ -183,7 +183,7 @@ mod tls_tests {
         let virtual_hosts = create_test_virtual_hosts_invalid_key();
         let alpn_protocols = vec![b"http/1.1".to_vec()];
 
-        let result = TlsFactory::create_tls_config(virtual_hosts, alpn_protocols).await;
+        let result = TlsFactory::create_tls_config(virtual_hosts, 8443, alpn_protocols).await;
 
         assert!(result.is_err(), "TLS config creation should fail with invalid key");
         match result.unwrap_err() {
@@ -210,7 +210,7 @@ mod tls_tests {
         let virtual_hosts = create_test_virtual_hosts();
         let alpn_protocols = vec![];
 
-        let result = TlsFactory::create_tls_config(virtual_hosts, alpn_protocols).await;
+        let result = TlsFactory::create_tls_config(virtual_hosts, 8443, alpn_protocols).await;
 
         assert!(result.is_ok(), "TLS config creation should succeed with empty ALPN");
         let tls_config = result.unwrap();
@@ -293,13 +293,13 @@ mod tls_tests {
                 .unwrap(),
         );
 
-        hosts.insert((Arc::from("localhost"), 8443), virtual_host1);
-        hosts.insert((Arc::from("test.com"), 8443), virtual_host2);
+        hosts.insert((Arc::from("localhost"), 8443), Arc::new(virtual_host1));
+        hosts.insert((Arc::from("test.com"), 8443), Arc::new(virtual_host2));
 
         let virtual_hosts = Arc::new(RwLock::new(hosts));
         let alpn_protocols = vec![b"h2".to_vec()];
 
-        let result = TlsFactory::create_tls_config(virtual_hosts, alpn_protocols).await;
+        let result = TlsFactory::create_tls_config(virtual_hosts, 8443, alpn_protocols).await;
 
         assert!(result.is_ok(), "TLS config creation should succeed with multiple hosts");
         let tls_config = result.unwrap();
@@ -322,7 +322,7 @@ mod tls_tests {
         let virtual_hosts = create_test_virtual_hosts();
         let alpn_protocols = vec![b"http/1.1".to_vec()];
 
-        let result = TlsFactory::create_tls_config(virtual_hosts, alpn_protocols).await;
+        let result = TlsFactory::create_tls_config(virtual_hosts, 8443, alpn_protocols).await;
 
         assert!(result.is_ok(), "TLS config creation should succeed with CA cert");
         let tls_config = result.unwrap();
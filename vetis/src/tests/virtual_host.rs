@@ -1,5 +1,7 @@
 mod virtual_host_tests {
 
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+
     use http::StatusCode;
     use http_body_util::BodyExt;
     use hyper_body_utils::HttpBody;
@@ -10,9 +12,15 @@ mod virtual_host_tests {
 
     use crate::{
         config::server::virtual_host::VirtualHostConfig,
+        errors::{VetisError, VirtualHostError},
         server::{
-            http::Request,
-            virtual_host::{handler_fn, path::HandlerPath, VirtualHost},
+            http::{Cookie, Event, Request, Response, SameSite},
+            virtual_host::{
+                find_virtual_host, handler_fn,
+                middleware::{Middleware, MiddlewareFuture, Next},
+                path::HandlerPath,
+                VirtualHost,
+            },
         },
     };
 
@@ -46,6 +54,58 @@ mod virtual_host_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_find_virtual_host() -> Result<(), Box<dyn std::error::Error>> {
+        let configs = [
+            VirtualHostConfig::builder()
+                .hostname("example.com")
+                .alias("www.example.com")
+                .port(8080),
+            VirtualHostConfig::builder()
+                .hostname("*.example.com")
+                .port(8080),
+            VirtualHostConfig::builder()
+                .hostname("*.api.example.com")
+                .port(8080),
+            VirtualHostConfig::builder()
+                .hostname("fallback.com")
+                .default(true)
+                .port(8080),
+            VirtualHostConfig::builder()
+                .hostname("other.com")
+                .port(9090),
+        ];
+
+        let mut virtual_hosts = HashMap::new();
+        for config in configs {
+            let config = config
+                .root_directory("src/tests")
+                .build()?;
+            let virtual_host = Arc::new(VirtualHost::new(config));
+            virtual_hosts
+                .insert((Arc::from(virtual_host.hostname()), virtual_host.port()), virtual_host);
+        }
+
+        let find = |host: &str, port: u16| {
+            find_virtual_host(&virtual_hosts, host, port)
+                .map(|virtual_host| virtual_host.hostname())
+        };
+
+        assert_eq!(find("example.com", 8080), Some("example.com"));
+        assert_eq!(find("www.example.com", 8080), Some("example.com"));
+        assert_eq!(find("WWW.Example.com", 8080), Some("example.com"));
+        assert_eq!(find("blog.example.com", 8080), Some("*.example.com"));
+        assert_eq!(find("a.b.example.com", 8080), Some("*.example.com"));
+        assert_eq!(find("v1.api.example.com", 8080), Some("*.api.example.com"));
+        assert_eq!(find("api.example.com", 8080), Some("*.example.com"));
+        assert_eq!(find("unknown.org", 8080), Some("fallback.com"));
+        assert_eq!(find("other.com", 8080), Some("fallback.com"));
+        assert_eq!(find("other.com", 9090), Some("other.com"));
+        assert_eq!(find("example.com", 9090), None);
+
+        Ok(())
+    }
+
     #[cfg(feature = "tokio-rt")]
     #[tokio::test]
     async fn test_add_virtual_host() -> Result<(), Box<dyn std::error::Error>> {
@@ -127,4 +187,1239 @@ mod virtual_host_tests {
     async fn test_handle_request() -> Result<(), Box<dyn std::error::Error>> {
         do_handle_request().await
     }
+
+    async fn send(
+        virtual_host: &VirtualHost,
+        method: http::Method,
+        uri: &str,
+    ) -> Result<(http::response::Parts, String), Box<dyn std::error::Error>> {
+        let request = http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(HttpBody::from_text(""))?;
+
+        let (parts, body) = request.into_parts();
+
+        let response = virtual_host
+            .route(Request::from_parts(parts, body))
+            .await?;
+
+        let (parts, body) = response
+            .into_inner()
+            .into_parts();
+        let body = body
+            .collect()
+            .await?
+            .to_bytes();
+
+        Ok((parts, String::from_utf8(body.to_vec())?))
+    }
+
+    async fn do_route_path_params() -> Result<(), Box<dyn std::error::Error>> {
+        let config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(config);
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/users")
+                .handler(handler_fn(|_request| async move {
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text("users"))
+                }))
+                .build()?,
+        );
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/users/:id")
+                .get(handler_fn(|request| async move {
+                    let id = request
+                        .param("id")
+                        .unwrap_or_default()
+                        .to_string();
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&format!("user {}", id)))
+                }))
+                .delete(handler_fn(|_request| async move {
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::NO_CONTENT)
+                        .text(""))
+                }))
+                .build()?,
+        );
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/users/me")
+                .get(handler_fn(|_request| async move {
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text("me"))
+                }))
+                .build()?,
+        );
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/users/:id/posts/*rest")
+                .get(handler_fn(|request| async move {
+                    let params = request.params();
+                    let text = format!(
+                        "{} {}",
+                        params
+                            .get("id")
+                            .unwrap_or_default(),
+                        params
+                            .get("rest")
+                            .unwrap_or_default()
+                    );
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&text))
+                }))
+                .build()?,
+        );
+
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/users/42").await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(body, "user 42");
+
+        let (_, body) = send(&virtual_host, http::Method::GET, "/users/j%C3%B8rn").await?;
+        assert_eq!(body, "user j\u{f8}rn");
+
+        // Literal segments take priority over parameters
+        let (_, body) = send(&virtual_host, http::Method::GET, "/users/me").await?;
+        assert_eq!(body, "me");
+
+        let (_, body) =
+            send(&virtual_host, http::Method::GET, "/users/42/posts/2024/hello").await?;
+        assert_eq!(body, "42 2024/hello");
+
+        // Paths without parameters are still matched by prefix
+        let (_, body) = send(&virtual_host, http::Method::GET, "/users/42/comments").await?;
+        assert_eq!(body, "users");
+
+        let (parts, _) = send(&virtual_host, http::Method::DELETE, "/users/42").await?;
+        assert_eq!(parts.status, StatusCode::NO_CONTENT);
+
+        let (parts, _) = send(&virtual_host, http::Method::PUT, "/users/42").await?;
+        assert_eq!(parts.status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(parts.headers[http::header::ALLOW], "GET, DELETE, HEAD, OPTIONS");
+
+        let (parts, body) = send(&virtual_host, http::Method::OPTIONS, "/users/42").await?;
+        assert_eq!(parts.status, StatusCode::NO_CONTENT);
+        assert_eq!(parts.headers[http::header::ALLOW], "GET, DELETE, HEAD, OPTIONS");
+        assert!(body.is_empty());
+
+        let (parts, _) = send(&virtual_host, http::Method::HEAD, "/users/42").await?;
+        assert_eq!(parts.status, StatusCode::OK);
+
+        // A handler for every method still receives OPTIONS requests
+        let (parts, body) = send(&virtual_host, http::Method::OPTIONS, "/users").await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(body, "users");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_route_path_params() -> Result<(), Box<dyn std::error::Error>> {
+        do_route_path_params().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_route_path_params() -> Result<(), Box<dyn std::error::Error>> {
+        do_route_path_params().await
+    }
+
+    #[test]
+    fn test_invalid_path_pattern() {
+        let handler = || {
+            handler_fn(|_request| async move {
+                Ok(crate::server::http::Response::builder()
+                    .status(StatusCode::OK)
+                    .text(""))
+            })
+        };
+
+        for uri in ["/files/*path/edit", "/users/:", "/users/:id/posts/:id"] {
+            let result = HandlerPath::builder()
+                .uri(uri)
+                .get(handler())
+                .build();
+            assert!(result.is_err(), "{} should be rejected", uri);
+        }
+    }
+
+    /// Appends its name to the `x-trace` header of responses
+    struct Trace(&'static str);
+
+    impl Middleware for Trace {
+        fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+            Box::pin(async move {
+                let mut response = next
+                    .run(request)
+                    .await?;
+                let trace = match response
+                    .headers()
+                    .get("x-trace")
+                {
+                    Some(trace) => format!(
+                        "{},{}",
+                        trace
+                            .to_str()
+                            .unwrap_or_default(),
+                        self.0
+                    ),
+                    None => self.0.to_string(),
+                };
+                response
+                    .headers_mut()
+                    .insert(
+                        "x-trace",
+                        trace
+                            .parse()
+                            .unwrap(),
+                    );
+                Ok(response)
+            })
+        }
+    }
+
+    /// Answers requests carrying `x-block` without running the rest of the chain
+    struct Block;
+
+    impl Middleware for Block {
+        fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+            if request
+                .headers()
+                .contains_key("x-block")
+            {
+                return Box::pin(async move {
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::FORBIDDEN)
+                        .text("blocked"))
+                });
+            }
+            next.run(request)
+        }
+    }
+
+    async fn do_middleware() -> Result<(), Box<dyn std::error::Error>> {
+        let config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(config);
+        virtual_host.add_middleware(Trace("host"));
+        virtual_host.add_middleware(Block);
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/api/:version")
+                .get(handler_fn(|request| async move {
+                    let version = request
+                        .param("version")
+                        .unwrap_or_default()
+                        .to_string();
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&version))
+                }))
+                .build()?
+                .middleware(Trace("outer"))
+                .middleware(Trace("inner")),
+        );
+
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/api/v1").await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(body, "v1");
+        assert_eq!(parts.headers["x-trace"], "inner,outer,host");
+
+        // Virtual host middleware also see status pages
+        let (parts, _) = send(&virtual_host, http::Method::GET, "/missing").await?;
+        assert_eq!(parts.status, StatusCode::NOT_FOUND);
+        assert_eq!(parts.headers["x-trace"], "host");
+
+        let request = http::Request::builder()
+            .uri("/api/v1")
+            .header("x-block", "1")
+            .body(HttpBody::from_text(""))?;
+        let (parts, body) = request.into_parts();
+        let response = virtual_host
+            .route(Request::from_parts(parts, body))
+            .await?;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(response.headers()["x-trace"], "host");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_middleware() -> Result<(), Box<dyn std::error::Error>> {
+        do_middleware().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_middleware() -> Result<(), Box<dyn std::error::Error>> {
+        do_middleware().await
+    }
+
+    async fn send_body(
+        virtual_host: &VirtualHost,
+        uri: &str,
+        content_type: &str,
+        body: &str,
+    ) -> Result<(StatusCode, String), Box<dyn std::error::Error>> {
+        let request = http::Request::builder()
+            .method(http::Method::POST)
+            .uri(uri)
+            .header(http::header::CONTENT_TYPE, content_type)
+            .body(HttpBody::from_text(body))?;
+
+        let (parts, body) = request.into_parts();
+
+        let response = virtual_host
+            .route(Request::from_parts(parts, body))
+            .await?;
+
+        let (parts, body) = response
+            .into_inner()
+            .into_parts();
+        let body = body
+            .collect()
+            .await?
+            .to_bytes();
+
+        Ok((parts.status, String::from_utf8(body.to_vec())?))
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Greeting {
+        name: String,
+    }
+
+    async fn do_body_helpers() -> Result<(), Box<dyn std::error::Error>> {
+        let config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .max_body_size(32)
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(config);
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/json")
+                .post(handler_fn(|request| async move {
+                    let greeting: Greeting = request
+                        .json()
+                        .await?;
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&greeting.name))
+                }))
+                .build()?,
+        );
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/form")
+                .post(handler_fn(|request| async move {
+                    let greeting: Greeting = request
+                        .form()
+                        .await?;
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&greeting.name))
+                }))
+                .build()?,
+        );
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/text")
+                .post(handler_fn(|request| async move {
+                    let text = request
+                        .text()
+                        .await?;
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&text))
+                }))
+                .build()?,
+        );
+
+        let (status, body) =
+            send_body(&virtual_host, "/json", "application/json", r#"{"name":"vetis"}"#).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "vetis");
+
+        let (status, _) =
+            send_body(&virtual_host, "/json", "text/plain", r#"{"name":"vetis"}"#).await?;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let (status, _) = send_body(&virtual_host, "/json", "application/json", "{").await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send_body(
+            &virtual_host,
+            "/form",
+            "application/x-www-form-urlencoded",
+            "name=hello+world",
+        )
+        .await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "hello world");
+
+        let (status, body) = send_body(&virtual_host, "/text", "text/plain", "short").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "short");
+
+        // Larger than the virtual host limit
+        let (status, _) = send_body(&virtual_host, "/text", "text/plain", &"a".repeat(33)).await?;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_body_helpers() -> Result<(), Box<dyn std::error::Error>> {
+        do_body_helpers().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_body_helpers() -> Result<(), Box<dyn std::error::Error>> {
+        do_body_helpers().await
+    }
+
+    #[cfg(feature = "multipart")]
+    async fn do_multipart() -> Result<(), Box<dyn std::error::Error>> {
+        let config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(config);
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/upload")
+                .post(handler_fn(|request| async move {
+                    let mut multipart = request.multipart()?;
+                    let mut fields = Vec::new();
+                    while let Some(field) = multipart
+                        .next_field()
+                        .await?
+                    {
+                        let name = field
+                            .name()
+                            .unwrap_or_default()
+                            .to_string();
+                        let file_name = field
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string();
+                        let text = field.text().await?;
+                        fields.push(format!("{}:{}:{}", name, file_name, text));
+                    }
+                    Ok(crate::server::http::Response::builder()
+                        .status(StatusCode::OK)
+                        .text(&fields.join(",")))
+                }))
+                .build()?,
+        );
+
+        let body = "--X\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+            Report\r\n\
+            --X\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"report.txt\"\r\n\
+            Content-Type: text/plain\r\n\r\n\
+            contents\r\n\
+            --X--\r\n";
+
+        let (status, body) =
+            send_body(&virtual_host, "/upload", "multipart/form-data; boundary=X", body).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "title::Report,file:report.txt:contents");
+
+        let (status, _) = send_body(&virtual_host, "/upload", "text/plain", "").await?;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "multipart", feature = "tokio-rt"))]
+    #[tokio::test]
+    async fn test_multipart() -> Result<(), Box<dyn std::error::Error>> {
+        do_multipart().await
+    }
+
+    #[cfg(all(feature = "multipart", feature = "smol-rt"))]
+    #[apply(test!)]
+    async fn test_multipart() -> Result<(), Box<dyn std::error::Error>> {
+        do_multipart().await
+    }
+
+    async fn do_response_builder() -> Result<(), Box<dyn std::error::Error>> {
+        let config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(config);
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/json")
+                .handler(handler_fn(|_request| async move {
+                    Response::builder()
+                        .status(StatusCode::CREATED)
+                        .cookie(
+                            Cookie::new("session", "abc")
+                                .path("/")
+                                .max_age(std::time::Duration::from_secs(60))
+                                .http_only(true)
+                                .same_site(SameSite::Lax),
+                        )
+                        .cookie(Cookie::removal("theme"))
+                        .cookie(Cookie::new("bad name", "value"))
+                        .json(&HashMap::from([("id", 1)]))
+                }))
+                .build()?,
+        );
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/old")
+                .handler(handler_fn(|_request| async move {
+                    Response::builder().redirect(StatusCode::PERMANENT_REDIRECT, "/new")
+                }))
+                .build()?,
+        );
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/stream")
+                .handler(handler_fn(|_request| async move {
+                    let chunks = futures_util::stream::iter(vec![
+                        bytes::Bytes::from("Hello, "),
+                        bytes::Bytes::from("World!"),
+                    ]);
+                    Ok(Response::builder().stream(chunks))
+                }))
+                .build()?,
+        );
+        #[cfg(feature = "static-files")]
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/file")
+                .handler(handler_fn(|_request| async move {
+                    Response::builder()
+                        .file("src/tests/files/index.html")
+                        .await
+                }))
+                .build()?,
+        );
+
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/json").await?;
+        assert_eq!(parts.status, StatusCode::CREATED);
+        assert_eq!(body, r#"{"id":1}"#);
+        assert_eq!(parts.headers[http::header::CONTENT_TYPE], "application/json");
+        assert_eq!(parts.headers[http::header::CONTENT_LENGTH], "8");
+        let cookies: Vec<_> = parts
+            .headers
+            .get_all(http::header::SET_COOKIE)
+            .iter()
+            .collect();
+        assert_eq!(
+            cookies,
+            [
+                "session=abc; Path=/; Max-Age=60; HttpOnly; SameSite=Lax",
+                "theme=; Max-Age=0; Expires=Thu, 01 Jan 1970 00:00:00 GMT"
+            ]
+        );
+
+        let (parts, _) = send(&virtual_host, http::Method::GET, "/old").await?;
+        assert_eq!(parts.status, StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(parts.headers[http::header::LOCATION], "/new");
+
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/stream").await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(body, "Hello, World!");
+        assert!(!parts
+            .headers
+            .contains_key(http::header::CONTENT_LENGTH));
+
+        #[cfg(feature = "static-files")]
+        {
+            let (parts, body) = send(&virtual_host, http::Method::GET, "/file").await?;
+            assert_eq!(parts.status, StatusCode::OK);
+            assert_eq!(parts.headers[http::header::CONTENT_TYPE], "text/html; charset=utf-8");
+            assert_eq!(
+                parts.headers[http::header::CONTENT_LENGTH],
+                body.len()
+                    .to_string()
+                    .as_str()
+            );
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_response_builder() -> Result<(), Box<dyn std::error::Error>> {
+        do_response_builder().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_response_builder() -> Result<(), Box<dyn std::error::Error>> {
+        do_response_builder().await
+    }
+
+    async fn do_server_sent_events() -> Result<(), Box<dyn std::error::Error>> {
+        let config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(config);
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/events")
+                .handler(handler_fn(|request| async move {
+                    let start: u64 = request
+                        .last_event_id()
+                        .and_then(|id| id.parse().ok())
+                        .map_or(0, |id: u64| id + 1);
+                    let events = futures_util::stream::iter((start..start + 2).map(|id| {
+                        Event::new()
+                            .event("tick")
+                            .id(&id.to_string())
+                            .data(&format!("line {}\nnext", id))
+                    }));
+                    Ok(Response::builder().sse(events))
+                }))
+                .build()?,
+        );
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/idle")
+                .handler(handler_fn(|_request| async move {
+                    let events = futures_util::stream::unfold(0, |step| async move {
+                        match step {
+                            0 => Some((Event::new().retry(Duration::from_secs(3)), 1)),
+                            1 => {
+                                crate::tests::sleep(Duration::from_millis(100)).await;
+                                Some((Event::new().comment("done"), 2))
+                            }
+                            _ => None,
+                        }
+                    });
+                    Ok(Response::builder()
+                        .sse_with_keep_alive(events, Some(Duration::from_millis(20))))
+                }))
+                .build()?,
+        );
+
+        let request = http::Request::builder()
+            .uri("/events")
+            .header("last-event-id", "4")
+            .body(HttpBody::from_text(""))?;
+        let (parts, body) = request.into_parts();
+        let response = virtual_host
+            .route(Request::from_parts(parts, body))
+            .await?;
+        let (parts, body) = response
+            .into_inner()
+            .into_parts();
+        assert_eq!(parts.headers[http::header::CONTENT_TYPE], "text/event-stream");
+        assert_eq!(parts.headers[http::header::CACHE_CONTROL], "no-cache");
+        assert_eq!(parts.headers["x-accel-buffering"], "no");
+        let body = body
+            .collect()
+            .await?
+            .to_bytes();
+        assert_eq!(
+            body,
+            "event: tick\nid: 5\ndata: line 5\ndata: next\n\n\
+             event: tick\nid: 6\ndata: line 6\ndata: next\n\n"
+        );
+
+        // Keep-alive comments are sent while the stream is idle
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/idle").await?;
+        assert_eq!(parts.status, StatusCode::OK);
+        assert!(body.starts_with("retry: 3000\n\n:\n\n"));
+        assert!(body.ends_with(": done\n\n"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_server_sent_events() -> Result<(), Box<dyn std::error::Error>> {
+        do_server_sent_events().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_server_sent_events() -> Result<(), Box<dyn std::error::Error>> {
+        do_server_sent_events().await
+    }
+
+    /// Fails every request carrying `x-fail`
+    struct Fail;
+
+    impl Middleware for Fail {
+        fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> MiddlewareFuture<'a> {
+            if request
+                .headers()
+                .contains_key("x-fail")
+            {
+                return Box::pin(async move {
+                    Err(VetisError::VirtualHost(VirtualHostError::Timeout("upstream".to_string())))
+                });
+            }
+            next.run(request)
+        }
+    }
+
+    async fn do_error_responses() -> Result<(), Box<dyn std::error::Error>> {
+        let config = VirtualHostConfig::builder()
+            .hostname("localhost")
+            .root_directory("src/tests")
+            .status_pages(HashMap::from([(500, "files/index.html".to_string())]))
+            .build()?;
+
+        let mut virtual_host = VirtualHost::new(config);
+        virtual_host.add_middleware(Fail);
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/crash")
+                .handler(handler_fn(|_request| async move {
+                    Err(VetisError::VirtualHost(VirtualHostError::Interface(
+                        "application crashed".to_string(),
+                    )))
+                }))
+                .build()?,
+        );
+        virtual_host.add_path(
+            HandlerPath::builder()
+                .uri("/busy")
+                .handler(handler_fn(|_request| async move {
+                    Err(VetisError::VirtualHost(VirtualHostError::Unavailable(
+                        "maintenance".to_string(),
+                    )))
+                }))
+                .build()?,
+        );
+
+        // Status pages are served for every error
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/crash").await?;
+        assert_eq!(parts.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body, std::fs::read_to_string("src/tests/files/index.html")?);
+
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/busy").await?;
+        assert_eq!(parts.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, "Service Unavailable");
+
+        // Errors returned by middleware are mapped too
+        let request = http::Request::builder()
+            .uri("/busy")
+            .header("x-fail", "1")
+            .body(HttpBody::from_text(""))?;
+        let (parts, body) = request.into_parts();
+        let response = virtual_host
+            .route(Request::from_parts(parts, body))
+            .await?;
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        // The error handler wins over status pages, unless it returns `None`
+        virtual_host.set_error_handler(|status, error| {
+            if status == StatusCode::INTERNAL_SERVER_ERROR {
+                return None;
+            }
+
+            let message = match error {
+                Some(error) => error.to_string(),
+                None => "no error".to_string(),
+            };
+            Response::builder()
+                .status(status)
+                .json(&HashMap::from([("error", message)]))
+                .ok()
+        });
+
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/busy").await?;
+        assert_eq!(parts.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, r#"{"error":"Virtual host error: Service unavailable: maintenance"}"#);
+
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/missing").await?;
+        assert_eq!(parts.status, StatusCode::NOT_FOUND);
+        assert_eq!(body, r#"{"error":"no error"}"#);
+
+        let (parts, body) = send(&virtual_host, http::Method::GET, "/crash").await?;
+        assert_eq!(parts.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body, std::fs::read_to_string("src/tests/files/index.html")?);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[tokio::test]
+    async fn test_error_responses() -> Result<(), Box<dyn std::error::Error>> {
+        do_error_responses().await
+    }
+
+    #[cfg(feature = "smol-rt")]
+    #[apply(test!)]
+    async fn test_error_responses() -> Result<(), Box<dyn std::error::Error>> {
+        do_error_responses().await
+    }
+
+    #[cfg(feature = "tower")]
+    mod tower {
+        use std::{
+            convert::Infallible,
+            future::Future,
+            pin::Pin,
+            task::{Context, Poll},
+        };
+
+        use bytes::Bytes;
+        use http::{HeaderValue, StatusCode};
+        use http_body_util::Full;
+        use hyper_body_utils::HttpBody;
+        use tower_layer::Layer;
+        use tower_service::Service;
+
+        use crate::{
+            config::server::virtual_host::VirtualHostConfig,
+            server::virtual_host::{
+                handler_fn,
+                path::{service::ServicePath, HandlerPath},
+                VirtualHost,
+            },
+        };
+
+        use super::send;
+
+        #[derive(Clone)]
+        struct Echo;
+
+        impl Service<http::Request<HttpBody>> for Echo {
+            type Response = http::Response<Full<Bytes>>;
+            type Error = Infallible;
+            type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Infallible>> + Send>>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, request: http::Request<HttpBody>) -> Self::Future {
+                let path = request
+                    .uri()
+                    .path()
+                    .to_string();
+                Box::pin(async move { Ok(http::Response::new(Full::new(Bytes::from(path)))) })
+            }
+        }
+
+        #[derive(Clone)]
+        struct PoweredBy<S>(S);
+
+        impl<S> Service<http::Request<HttpBody>> for PoweredBy<S>
+        where
+            S: Service<http::Request<HttpBody>>,
+            S::Response: Into<http::Response<HttpBody>>,
+            S::Future: Send + 'static,
+        {
+            type Response = http::Response<HttpBody>;
+            type Error = S::Error;
+            type Future = Pin<Box<dyn Future<Output = Result<Self::Response, S::Error>> + Send>>;
+
+            fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+                self.0
+                    .poll_ready(cx)
+            }
+
+            fn call(&mut self, request: http::Request<HttpBody>) -> Self::Future {
+                let future = self.0.call(request);
+                Box::pin(async move {
+                    let mut response = future.await?.into();
+                    response
+                        .headers_mut()
+                        .insert("x-powered-by", HeaderValue::from_static("tower"));
+                    Ok(response)
+                })
+            }
+        }
+
+        struct PoweredByLayer;
+
+        impl<S> Layer<S> for PoweredByLayer {
+            type Service = PoweredBy<S>;
+
+            fn layer(&self, inner: S) -> Self::Service {
+                PoweredBy(inner)
+            }
+        }
+
+        async fn do_tower() -> Result<(), Box<dyn std::error::Error>> {
+            let config = VirtualHostConfig::builder()
+                .hostname("localhost")
+                .root_directory("src/tests")
+                .build()?;
+
+            let mut virtual_host = VirtualHost::new(config);
+            virtual_host.add_path(ServicePath::new("/echo", Echo));
+            virtual_host.add_path(
+                HandlerPath::builder()
+                    .uri("/users/:id")
+                    .get(handler_fn(|request| async move {
+                        let id = request
+                            .param("id")
+                            .unwrap_or_default()
+                            .to_string();
+                        Ok(crate::server::http::Response::builder()
+                            .status(StatusCode::OK)
+                            .text(&id))
+                    }))
+                    .build()?
+                    .layer(PoweredByLayer),
+            );
+
+            // Services receive the full request uri
+            let (parts, body) = send(&virtual_host, http::Method::GET, "/echo/a/b").await?;
+            assert_eq!(parts.status, StatusCode::OK);
+            assert_eq!(body, "/echo/a/b");
+
+            let (parts, body) = send(&virtual_host, http::Method::GET, "/users/42").await?;
+            assert_eq!(parts.status, StatusCode::OK);
+            assert_eq!(body, "42");
+            assert_eq!(parts.headers["x-powered-by"], "tower");
+
+            // Errors raised by the wrapped path keep their status
+            let (parts, _) = send(&virtual_host, http::Method::DELETE, "/users/42").await?;
+            assert_eq!(parts.status, StatusCode::METHOD_NOT_ALLOWED);
+            assert_eq!(parts.headers["x-powered-by"], "tower");
+
+            Ok(())
+        }
+
+        #[cfg(feature = "tokio-rt")]
+        #[tokio::test]
+        async fn test_tower() -> Result<(), Box<dyn std::error::Error>> {
+            do_tower().await
+        }
+
+        #[cfg(feature = "smol-rt")]
+        #[macro_rules_attribute::apply(smol_macros::test!)]
+        async fn test_tower() -> Result<(), Box<dyn std::error::Error>> {
+            do_tower().await
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    mod compression {
+        use std::io::Read;
+
+        use bytes::Bytes;
+        use http::{
+            header::{
+                ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH, ETAG, VARY,
+            },
+            HeaderValue, StatusCode,
+        };
+        use http_body_util::BodyExt;
+        use hyper_body_utils::HttpBody;
+
+        use crate::{
+            config::server::virtual_host::{
+                compression::{CompressionConfig, Encoding},
+                VirtualHostConfig,
+            },
+            server::{
+                http::{Event, Request, Response},
+                virtual_host::{
+                    compression::Compression, handler_fn, path::HandlerPath, VirtualHost,
+                },
+            },
+        };
+
+        async fn get(
+            virtual_host: &VirtualHost,
+            uri: &str,
+            accept_encoding: Option<&str>,
+        ) -> Result<(http::response::Parts, Bytes), Box<dyn std::error::Error>> {
+            let mut request = http::Request::builder().uri(uri);
+            if let Some(accept_encoding) = accept_encoding {
+                request = request.header(ACCEPT_ENCODING, accept_encoding);
+            }
+            let (parts, body) = request
+                .body(HttpBody::from_text(""))?
+                .into_parts();
+
+            let response = virtual_host
+                .route(Request::from_parts(parts, body))
+                .await?;
+            let (parts, body) = response
+                .into_inner()
+                .into_parts();
+
+            Ok((
+                parts,
+                body.collect()
+                    .await?
+                    .to_bytes(),
+            ))
+        }
+
+        fn decode(encoding: &str, body: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+            let mut decoded = String::new();
+            match encoding {
+                "gzip" => {
+                    flate2::read::GzDecoder::new(body).read_to_string(&mut decoded)?;
+                }
+                "br" => {
+                    brotli::Decompressor::new(body, 4096).read_to_string(&mut decoded)?;
+                }
+                "zstd" => {
+                    zstd::stream::read::Decoder::new(body)?.read_to_string(&mut decoded)?;
+                }
+                _ => return Err(format!("unexpected encoding {}", encoding).into()),
+            }
+            Ok(decoded)
+        }
+
+        async fn do_compression() -> Result<(), Box<dyn std::error::Error>> {
+            let text = "compress me, ".repeat(200);
+
+            let config = VirtualHostConfig::builder()
+                .hostname("localhost")
+                .root_directory("src/tests")
+                .compression(CompressionConfig::builder().build()?)
+                .build()?;
+
+            let mut virtual_host = VirtualHost::new(config);
+            let body = text.clone();
+            virtual_host.add_path(
+                HandlerPath::builder()
+                    .uri("/text")
+                    .handler(handler_fn(move |_request| {
+                        let body = body.clone();
+                        async move {
+                            Ok(Response::builder()
+                                .header(ETAG, HeaderValue::from_static("\"v1\""))
+                                .text(&body))
+                        }
+                    }))
+                    .build()?,
+            );
+            virtual_host.add_path(
+                HandlerPath::builder()
+                    .uri("/small")
+                    .handler(handler_fn(
+                        |_request| async move { Ok(Response::builder().text("small")) },
+                    ))
+                    .build()?,
+            );
+            virtual_host.add_path(
+                HandlerPath::builder()
+                    .uri("/binary")
+                    .handler(handler_fn(|_request| async move {
+                        Ok(Response::builder().bytes(&[0; 4096]))
+                    }))
+                    .build()?,
+            );
+            virtual_host.add_path(
+                HandlerPath::builder()
+                    .uri("/no-transform")
+                    .handler(handler_fn(|_request| async move {
+                        Ok(Response::builder()
+                            .header(CACHE_CONTROL, HeaderValue::from_static("no-transform"))
+                            .text(&"x".repeat(4096)))
+                    }))
+                    .build()?,
+            );
+            virtual_host.add_path(
+                HandlerPath::builder()
+                    .uri("/stream")
+                    .handler(handler_fn(|_request| async move {
+                        let chunks = futures_util::stream::iter(
+                            (0..64).map(|i| Bytes::from(format!("chunk {}\n", i))),
+                        );
+                        Ok(Response::builder()
+                            .header(
+                                http::header::CONTENT_TYPE,
+                                HeaderValue::from_static("text/plain"),
+                            )
+                            .stream(chunks))
+                    }))
+                    .build()?,
+            );
+            virtual_host.add_path(
+                HandlerPath::builder()
+                    .uri("/events")
+                    .handler(handler_fn(|_request| async move {
+                        let events = futures_util::stream::iter(
+                            (0..64).map(|i| Event::new().data(&"e".repeat(i))),
+                        );
+                        Ok(Response::builder().sse(events))
+                    }))
+                    .build()?,
+            );
+            virtual_host.add_path(
+                HandlerPath::builder()
+                    .uri("/plain")
+                    .handler(handler_fn(|_request| async move {
+                        Ok(Response::builder().text(&"y".repeat(4096)))
+                    }))
+                    .build()?
+                    .middleware(Compression::new(
+                        CompressionConfig::builder()
+                            .enabled(false)
+                            .build()?,
+                    )),
+            );
+            virtual_host.add_path(
+                HandlerPath::builder()
+                    .uri("/gzip-only")
+                    .handler(handler_fn(|_request| async move {
+                        Ok(Response::builder().text(&"z".repeat(4096)))
+                    }))
+                    .build()?
+                    .middleware(Compression::new(
+                        CompressionConfig::builder()
+                            .algorithms(vec![Encoding::Gzip])
+                            .build()?,
+                    )),
+            );
+
+            let cases = [
+                (Some("gzip"), Some("gzip")),
+                (Some("br"), Some("br")),
+                (Some("zstd"), Some("zstd")),
+                (Some("gzip, deflate, br, zstd"), Some("br")),
+                (Some("br;q=0.5, gzip"), Some("gzip")),
+                (Some("*"), Some("br")),
+                (Some("deflate"), None),
+                (Some("gzip;q=0, br;q=0, zstd;q=0"), None),
+                (None, None),
+            ];
+            for (accept_encoding, encoding) in cases {
+                let (parts, body) = get(&virtual_host, "/text", accept_encoding).await?;
+                assert_eq!(parts.status, StatusCode::OK);
+                assert_eq!(parts.headers[VARY], "accept-encoding");
+                assert_eq!(
+                    parts
+                        .headers
+                        .get(CONTENT_ENCODING)
+                        .and_then(|value| value.to_str().ok()),
+                    encoding,
+                    "{:?}",
+                    accept_encoding
+                );
+                match encoding {
+                    Some(encoding) => {
+                        assert!(!parts
+                            .headers
+                            .contains_key(CONTENT_LENGTH));
+                        assert_eq!(parts.headers[ETAG], "W/\"v1\"");
+                        assert!(body.len() < text.len());
+                        assert_eq!(decode(encoding, &body)?, text);
+                    }
+                    None => {
+                        assert_eq!(parts.headers[ETAG], "\"v1\"");
+                        assert_eq!(body, text.as_bytes());
+                    }
+                }
+            }
+
+            // Small, binary, no-transform and event stream responses are sent as they are
+            for uri in ["/small", "/binary", "/no-transform", "/events"] {
+                let (parts, _) = get(&virtual_host, uri, Some("gzip")).await?;
+                assert!(
+                    !parts
+                        .headers
+                        .contains_key(CONTENT_ENCODING),
+                    "{}",
+                    uri
+                );
+            }
+
+            // Streamed bodies are compressed as they are produced
+            let (parts, body) = get(&virtual_host, "/stream", Some("zstd")).await?;
+            assert_eq!(parts.headers[CONTENT_ENCODING], "zstd");
+            let expected: String = (0..64)
+                .map(|i| format!("chunk {}\n", i))
+                .collect();
+            assert_eq!(decode("zstd", &body)?, expected);
+
+            // Paths override the compression of the virtual host
+            let (parts, body) = get(&virtual_host, "/plain", Some("gzip")).await?;
+            assert!(!parts
+                .headers
+                .contains_key(CONTENT_ENCODING));
+            assert_eq!(body.len(), 4096);
+
+            let (parts, body) = get(&virtual_host, "/gzip-only", Some("br, gzip")).await?;
+            assert_eq!(parts.headers[CONTENT_ENCODING], "gzip");
+            assert_eq!(decode("gzip", &body)?, "z".repeat(4096));
+
+            // HEAD responses announce the encoding without a body
+            let (parts, body) = request_head(&virtual_host, "/text").await?;
+            assert_eq!(parts.headers[CONTENT_ENCODING], "br");
+            assert!(body.is_empty());
+
+            assert!(CompressionConfig::builder()
+                .algorithms(Vec::new())
+                .build()
+                .is_err());
+
+            Ok(())
+        }
+
+        async fn request_head(
+            virtual_host: &VirtualHost,
+            uri: &str,
+        ) -> Result<(http::response::Parts, Bytes), Box<dyn std::error::Error>> {
+            let (parts, body) = http::Request::builder()
+                .method(http::Method::HEAD)
+                .uri(uri)
+                .header(ACCEPT_ENCODING, "br")
+                .body(HttpBody::from_text(""))?
+                .into_parts();
+
+            let response = virtual_host
+                .route(Request::from_parts(parts, body))
+                .await?;
+            let (parts, body) = response
+                .into_inner()
+                .into_parts();
+
+            Ok((
+                parts,
+                body.collect()
+                    .await?
+                    .to_bytes(),
+            ))
+        }
+
+        #[cfg(feature = "tokio-rt")]
+        #[tokio::test]
+        async fn test_compression() -> Result<(), Box<dyn std::error::Error>> {
+            do_compression().await
+        }
+
+        #[cfg(feature = "smol-rt")]
+        #[macro_rules_attribute::apply(smol_macros::test!)]
+        async fn test_compression() -> Result<(), Box<dyn std::error::Error>> {
+            do_compression().await
+        }
+    }
 }
@@ -1,9 +1,39 @@
 use std::time::SystemTime;
 
-use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+use time::OffsetDateTime;
 
-pub fn format_date(date: SystemTime) -> String {
+/// Format a date as an HTTP date, such as `Sun, 06 Nov 1994 08:49:37 GMT`
+pub fn format_http_date(date: SystemTime) -> String {
     let date = OffsetDateTime::from(date);
-    date.format(&Rfc2822)
-        .unwrap()
+    format!(
+        "{:.3}, {:02} {:.3} {} {:02}:{:02}:{:02} GMT",
+        date.weekday()
+            .to_string(),
+        date.day(),
+        date.month()
+            .to_string(),
+        date.year(),
+        date.hour(),
+        date.minute(),
+        date.second()
+    )
+}
+
+#[cfg(feature = "webdav")]
+/// Format a date as an RFC 3339 date, such as `1994-11-06T08:49:37Z`
+pub fn format_rfc3339(date: SystemTime) -> String {
+    OffsetDateTime::from(date)
+        .replace_nanosecond(0)
+        .ok()
+        .and_then(|date| {
+            date.format(&time::format_description::well_known::Rfc3339)
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "static-files")]
+/// Parse an HTTP date, in the IMF-fixdate, RFC 850 or asctime format
+pub fn parse_http_date(date: &str) -> Option<SystemTime> {
+    httpdate::parse_http_date(date).ok()
 }
@@ -1 +1,2 @@
 pub(crate) mod date;
+pub(crate) mod path;
@@ -0,0 +1,36 @@
+//! Request paths
+
+use percent_encoding::percent_decode_str;
+
+/// Percent-decode a request path
+///
+/// # Returns
+///
+/// * `Option<String>` - The decoded path, `None` when it is not UTF-8 or contains a NUL byte
+pub(crate) fn decode_path(path: &str) -> Option<String> {
+    let decoded = percent_decode_str(path)
+        .decode_utf8()
+        .ok()?;
+
+    (!decoded.contains('\0')).then(|| decoded.into_owned())
+}
+
+/// Resolve the `.` and `..` segments of a decoded path, and collapse repeated slashes
+///
+/// # Returns
+///
+/// * `Option<String>` - The path starting with `/`, `None` when it goes above its root
+pub(crate) fn normalize_path(path: &str) -> Option<String> {
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    Some(format!("/{}", segments.join("/")))
+}